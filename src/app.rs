@@ -1,33 +1,141 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
 
-use crate::routes;
+use crate::config::Config;
 use crate::database;
+use crate::migrations;
+use crate::models::{
+    account::Account, data_export::DataExport, refresh_token::RefreshToken,
+    revoked_token::RevokedToken, session::Session, user::User,
+};
+use crate::routes;
+use crate::services::{action_token, email::Mailer, otp::OtpService};
+use crate::state::AppState;
 
 use axum::Router;
-use dotenvy_macro::dotenv;
+use surrealdb::{engine::any::Any, Surreal};
+
+// how often expired revocations, refresh tokens, sessions, action tokens, otps and data exports are removed
+const TOKEN_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// how often accounts whose deletion grace period is over are deleted
+const ACCOUNT_DELETION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub async fn run(config: Config) -> Result<(), String> {
 
-pub async fn run() -> Result<(), String> {
+    let mailer = Mailer::from_config(&config.smtp)?;
 
-    
+    let bind_address = config.server.bind_address;
 
-    let databsase_credentials = (
-        dotenv!("DB_URI"),
-        dotenv!("DB_NS"),
-        dotenv!("DB_NAME"),
-        dotenv!("DB_USERNAME"),
-        dotenv!("DB_PASSWORD")
-    );
+    let state = build_state(config, mailer).await?;
 
-    let db = database::connect(databsase_credentials).await?;
+    tokio::spawn(remove_expired_tokens(state.db.clone()));
+    tokio::spawn(delete_scheduled_accounts(state.db.clone(), state.mailer.clone()));
 
-    let app:Router = routes::get_router()
-    .with_state(db);
+    let app: Router = routes::get_router(state);
 
-    axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
-        .serve(app.into_make_service())
+    axum::Server::bind(&bind_address)
+        // the address of the client is recorded with its sessions
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .map_err(|e| e.to_string())?;
 
     Ok(())
 
 
-}
\ No newline at end of file
+}
+
+// connects the database and prepares the state shared by all handlers
+pub async fn build_state(config: Config, mailer: Mailer) -> Result<AppState, String> {
+
+    let db = database::connect(&config.database).await?;
+
+    // bring the schema up to date before serving requests
+    if config.database.run_migrations {
+        migrations::run(&db).await?;
+    }
+
+    Ok(AppState {
+        db,
+        config: Arc::new(config),
+        mailer,
+    })
+
+}
+
+// applies pending migrations without starting the server
+pub async fn migrate(config: Config) -> Result<(), String> {
+
+    let db = database::connect(&config.database).await?;
+
+    let applied = migrations::run(&db).await?;
+
+    if applied.is_empty() {
+        println!("database schema is up to date");
+    }
+
+    Ok(())
+
+}
+
+// makes an existing undergraduate or company account an administrator
+pub async fn promote_admin(config: Config, username: String) -> Result<(), String> {
+
+    let db = database::connect(&config.database).await?;
+
+    User::promote_to_admin(&db, username.clone())
+        .await
+        .map_err(|e| e.message())?;
+
+    println!("{} is now an administrator", username);
+
+    Ok(())
+
+}
+
+// runs for as long as the server, a failed cleanup is retried at the next interval
+async fn remove_expired_tokens(db: Arc<Surreal<Any>>) {
+    let mut interval = tokio::time::interval(TOKEN_CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = RevokedToken::remove_expired(&db).await {
+            tracing::warn!("could not remove expired revoked tokens: {:?}", e);
+        }
+
+        if let Err(e) = RefreshToken::remove_expired(&db).await {
+            tracing::warn!("could not remove expired refresh tokens: {:?}", e);
+        }
+
+        if let Err(e) = Session::remove_expired(&db).await {
+            tracing::warn!("could not remove expired sessions: {:?}", e);
+        }
+
+        if let Err(e) = action_token::remove_expired(&db).await {
+            tracing::warn!("could not remove expired action tokens: {:?}", e);
+        }
+
+        if let Err(e) = OtpService::remove_expired(&db).await {
+            tracing::warn!("could not remove expired otps: {:?}", e);
+        }
+
+        if let Err(e) = DataExport::remove_expired(&db).await {
+            tracing::warn!("could not remove expired data exports: {:?}", e);
+        }
+    }
+}
+
+// runs for as long as the server, an account that could not be deleted is retried at the next interval
+async fn delete_scheduled_accounts(db: Arc<Surreal<Any>>, mailer: Mailer) {
+    let mut interval = tokio::time::interval(ACCOUNT_DELETION_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match Account::delete_due(&db, &mailer).await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("deleted {} accounts", deleted),
+            Err(e) => tracing::warn!("could not delete scheduled accounts: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use std::{fmt, fs, net::SocketAddr, path::Path};
+
+use serde::Deserialize;
+
+use crate::services::{ip_range, jwt::Keyring};
+
+// default location of the optional configuration file
+// can be changed with the CONFIG_FILE environment variable
+const DEFAULT_CONFIG_FILE: &str = "ufora.toml";
+
+// environment variables that override values of the configuration file
+// (environment variable, key in the configuration file)
+const ENVIRONMENT_OVERRIDES: [(&str, &str); 31] = [
+    ("BIND_ADDRESS", "server.bind_address"),
+    ("PUBLIC_URL", "server.public_url"),
+    ("TRUSTED_PROXIES", "server.trusted_proxies"),
+    ("DB_URI", "database.uri"),
+    ("DB_NS", "database.namespace"),
+    ("DB_NAME", "database.name"),
+    ("DB_USERNAME", "database.username"),
+    ("DB_PASSWORD", "database.password"),
+    ("DB_RUN_MIGRATIONS", "database.run_migrations"),
+    ("JWT_SIGNING_KEY_ID", "jwt.signing_key_id"),
+    ("JWT_SIGNING_KEY", "jwt.signing_key"),
+    ("JWT_LIFETIME_SECONDS", "jwt.lifetime_seconds"),
+    ("JWT_REFRESH_LIFETIME_SECONDS", "jwt.refresh_lifetime_seconds"),
+    ("ENCRYPTION_KEY", "security.encryption_key"),
+    ("OTP_EXPIRY_MINUTES", "otp.expiry_minutes"),
+    ("OTP_MAX_ATTEMPTS", "otp.max_attempts"),
+    ("OTP_RESEND_COOLDOWN_SECONDS", "otp.resend_cooldown_seconds"),
+    ("PASSWORD_MIN_LENGTH", "password.min_length"),
+    ("PASSWORD_MAX_LENGTH", "password.max_length"),
+    ("PASSWORD_REQUIRE_LOWERCASE", "password.require_lowercase"),
+    ("PASSWORD_REQUIRE_UPPERCASE", "password.require_uppercase"),
+    ("PASSWORD_REQUIRE_DIGIT", "password.require_digit"),
+    ("PASSWORD_REQUIRE_SYMBOL", "password.require_symbol"),
+    ("PASSWORD_REJECT_COMMON", "password.reject_common"),
+    ("ACCOUNT_DELETION_GRACE_DAYS", "account.deletion_grace_days"),
+    (
+        "ACCOUNT_EMAIL_CHANGE_REVERT_DAYS",
+        "account.email_change_revert_days",
+    ),
+    ("SMTP_RELAY", "smtp.relay"),
+    ("SMTP_PORT", "smtp.port"),
+    ("APP_EMAIL", "smtp.email"),
+    ("APP_EMAIL_PASSWORD", "smtp.password"),
+    ("MAP_API_KEY", "maps.api_key"),
+];
+
+// keys holding numbers, the matching environment variables are parsed before use
+const NUMERIC_KEYS: [&str; 10] = [
+    "jwt.lifetime_seconds",
+    "jwt.refresh_lifetime_seconds",
+    "otp.expiry_minutes",
+    "otp.max_attempts",
+    "otp.resend_cooldown_seconds",
+    "password.min_length",
+    "password.max_length",
+    "account.deletion_grace_days",
+    "account.email_change_revert_days",
+    "smtp.port",
+];
+
+// keys holding booleans, the matching environment variables accept true/false, 1/0 and yes/no
+const BOOLEAN_KEYS: [&str; 6] = [
+    "database.run_migrations",
+    "password.require_lowercase",
+    "password.require_uppercase",
+    "password.require_digit",
+    "password.require_symbol",
+    "password.reject_common",
+];
+
+// keys holding lists, the matching environment variables are comma separated
+const LIST_KEYS: [&str; 1] = ["server.trusted_proxies"];
+
+// values that have to be present before the server starts
+const REQUIRED_KEYS: [&str; 7] = [
+    "database.uri",
+    "jwt.signing_key_id",
+    "jwt.signing_key",
+    "security.encryption_key",
+    "smtp.email",
+    "smtp.password",
+    "maps.api_key",
+];
+
+// configuration of the whole application, loaded once at startup
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub jwt: JwtConfig,
+    pub security: SecurityConfig,
+    #[serde(default)]
+    pub otp: OtpConfig,
+    #[serde(default)]
+    pub password: PasswordConfig,
+    #[serde(default)]
+    pub account: AccountConfig,
+    pub smtp: SmtpConfig,
+    pub maps: MapsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    #[serde(default = "default_bind_address")]
+    pub bind_address: SocketAddr,
+    // address the api is reached at from outside, used for links in emails
+    #[serde(default = "default_public_url")]
+    pub public_url: String,
+    // addresses or cidr ranges of the reverse proxies in front of the server
+    // x-forwarded-for is only believed from them
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: default_bind_address(),
+            public_url: default_public_url(),
+            trusted_proxies: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatabaseConfig {
+    pub uri: String,
+    #[serde(default = "default_database_namespace")]
+    pub namespace: String,
+    #[serde(default = "default_database_name")]
+    pub name: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    // apply pending schema migrations when the server starts
+    #[serde(default = "default_run_migrations")]
+    pub run_migrations: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct JwtConfig {
+    // sent as the kid header of every token, a new key needs a new id
+    pub signing_key_id: String,
+    // ed25519 private key in PKCS#8 PEM, see `ufora-backend generate-jwt-key`
+    pub signing_key: String,
+    // public keys of earlier signing keys, their tokens are accepted until the keys are removed
+    #[serde(default)]
+    pub verification_keys: Vec<VerificationKeyConfig>,
+    // lifetime of user and club access tokens
+    #[serde(default = "default_jwt_lifetime_seconds")]
+    pub lifetime_seconds: i64,
+    // lifetime of the refresh tokens used to get new access tokens
+    #[serde(default = "default_jwt_refresh_lifetime_seconds")]
+    pub refresh_lifetime_seconds: i64,
+    // built from the keys above once the configuration is loaded
+    #[serde(skip)]
+    pub keyring: Keyring,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VerificationKeyConfig {
+    pub id: String,
+    // base64url encoded ed25519 public key, the x of the key in the jwks
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityConfig {
+    // key used to sign verification and password reset tokens
+    pub encryption_key: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OtpConfig {
+    #[serde(default = "default_otp_expiry_minutes")]
+    pub expiry_minutes: i64,
+    // wrong codes before an otp is dropped
+    #[serde(default = "default_otp_max_attempts")]
+    pub max_attempts: i64,
+    // time before a new otp can be sent for the same purpose and address
+    #[serde(default = "default_otp_resend_cooldown_seconds")]
+    pub resend_cooldown_seconds: i64,
+}
+
+impl Default for OtpConfig {
+    fn default() -> Self {
+        Self {
+            expiry_minutes: default_otp_expiry_minutes(),
+            max_attempts: default_otp_max_attempts(),
+            resend_cooldown_seconds: default_otp_resend_cooldown_seconds(),
+        }
+    }
+}
+
+// rules a new password has to follow
+#[derive(Debug, Clone, Deserialize)]
+pub struct PasswordConfig {
+    #[serde(default = "default_password_min_length")]
+    pub min_length: usize,
+    // in bytes, bcrypt ignores everything after the first 72
+    #[serde(default = "default_password_max_length")]
+    pub max_length: usize,
+    #[serde(default = "default_true")]
+    pub require_lowercase: bool,
+    #[serde(default = "default_true")]
+    pub require_uppercase: bool,
+    #[serde(default = "default_true")]
+    pub require_digit: bool,
+    #[serde(default)]
+    pub require_symbol: bool,
+    // refuse passwords from the embedded list of common and breached passwords
+    #[serde(default = "default_true")]
+    pub reject_common: bool,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            min_length: default_password_min_length(),
+            max_length: default_password_max_length(),
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_common: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AccountConfig {
+    // days between a deletion request and the deletion, the request can be cancelled until then
+    #[serde(default = "default_account_deletion_grace_days")]
+    pub deletion_grace_days: i64,
+    // days the old address can undo a change of the email address
+    #[serde(default = "default_account_email_change_revert_days")]
+    pub email_change_revert_days: i64,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self {
+            deletion_grace_days: default_account_deletion_grace_days(),
+            email_change_revert_days: default_account_email_change_revert_days(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SmtpConfig {
+    #[serde(default = "default_smtp_relay")]
+    pub relay: String,
+    pub port: Option<u16>,
+    // address emails are sent from, also used as the smtp username
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MapsConfig {
+    pub api_key: String,
+}
+
+fn default_bind_address() -> SocketAddr {
+    SocketAddr::from(([0, 0, 0, 0], 3000))
+}
+
+fn default_public_url() -> String {
+    "http://localhost:3000".to_string()
+}
+
+fn default_database_namespace() -> String {
+    "ufora".to_string()
+}
+
+fn default_database_name() -> String {
+    "ufora".to_string()
+}
+
+fn default_run_migrations() -> bool {
+    true
+}
+
+fn default_jwt_lifetime_seconds() -> i64 {
+    60 * 15
+}
+
+fn default_jwt_refresh_lifetime_seconds() -> i64 {
+    60 * 60 * 24 * 30
+}
+
+fn default_otp_expiry_minutes() -> i64 {
+    10
+}
+
+fn default_otp_max_attempts() -> i64 {
+    5
+}
+
+fn default_otp_resend_cooldown_seconds() -> i64 {
+    60
+}
+
+fn default_password_min_length() -> usize {
+    10
+}
+
+fn default_password_max_length() -> usize {
+    72
+}
+
+fn default_account_deletion_grace_days() -> i64 {
+    30
+}
+
+fn default_account_email_change_revert_days() -> i64 {
+    7
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_smtp_relay() -> String {
+    "smtp.gmail.com".to_string()
+}
+
+// error returned when the configuration could not be loaded
+// every problem found is reported at once
+#[derive(Debug)]
+pub struct ConfigError {
+    problems: Vec<String>,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+        for problem in &self.problems {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // loads the configuration from the optional toml file and the environment
+    // environment variables take precedence over the file
+    pub fn load() -> Result<Self, ConfigError> {
+        let path = std::env::var("CONFIG_FILE").ok();
+
+        let table = match path.as_deref() {
+            // an explicitly given file has to exist
+            Some(path) => read_config_file(Path::new(path))?,
+            None if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                read_config_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            None => toml::Table::new(),
+        };
+
+        Self::from_table(table, |name| std::env::var(name).ok())
+    }
+
+    // builds the configuration from a parsed toml table and an environment lookup
+    pub fn from_table(
+        mut table: toml::Table,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut problems = vec![];
+
+        for (variable, key) in ENVIRONMENT_OVERRIDES {
+            if let Some(value) = env(variable).filter(|value| !value.is_empty()) {
+                match parse_environment_value(key, value) {
+                    Ok(value) => set_key(&mut table, key, value),
+                    Err(expected) => problems.push(format!("{} must be {}", variable, expected)),
+                }
+            }
+        }
+
+        for key in REQUIRED_KEYS {
+            match get_key(&table, key) {
+                Some(toml::Value::String(value)) if value.trim().is_empty() => {
+                    problems.push(format!("`{}` must not be empty", key));
+                }
+                Some(_) => {}
+                None => problems.push(format!(
+                    "`{}` is missing (set {} or add it to the configuration file)",
+                    key,
+                    environment_variable_for(key)
+                )),
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(ConfigError { problems });
+        }
+
+        let mut config: Self =
+            toml::Value::Table(table)
+                .try_into()
+                .map_err(|e: toml::de::Error| ConfigError {
+                    problems: vec![e.to_string()],
+                })?;
+
+        config.validate()?;
+
+        config.jwt.keyring =
+            Keyring::from_config(&config.jwt).map_err(|problems| ConfigError { problems })?;
+
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+
+        if self.jwt.lifetime_seconds <= 0 {
+            problems.push("`jwt.lifetime_seconds` must be greater than 0".to_string());
+        }
+
+        if self.jwt.refresh_lifetime_seconds <= self.jwt.lifetime_seconds {
+            problems.push(
+                "`jwt.refresh_lifetime_seconds` must be greater than `jwt.lifetime_seconds`"
+                    .to_string(),
+            );
+        }
+
+        // the key of the hmac on action tokens and otp hashes
+        if self.security.encryption_key.len() < 32 {
+            problems.push("`security.encryption_key` must be at least 32 bytes".to_string());
+        }
+
+        if self.otp.expiry_minutes <= 0 {
+            problems.push("`otp.expiry_minutes` must be greater than 0".to_string());
+        }
+
+        if self.otp.max_attempts <= 0 {
+            problems.push("`otp.max_attempts` must be greater than 0".to_string());
+        }
+
+        if self.otp.resend_cooldown_seconds < 0 {
+            problems.push("`otp.resend_cooldown_seconds` must not be negative".to_string());
+        }
+
+        if self.password.min_length == 0 {
+            problems.push("`password.min_length` must be greater than 0".to_string());
+        }
+
+        if self.password.max_length < self.password.min_length {
+            problems.push(
+                "`password.max_length` must not be less than `password.min_length`".to_string(),
+            );
+        }
+
+        if self.password.max_length > 72 {
+            problems.push(
+                "`password.max_length` must be at most 72, bcrypt ignores longer passwords"
+                    .to_string(),
+            );
+        }
+
+        if self.account.deletion_grace_days < 0 {
+            problems.push("`account.deletion_grace_days` must not be negative".to_string());
+        }
+
+        if self.account.email_change_revert_days <= 0 {
+            problems.push("`account.email_change_revert_days` must be greater than 0".to_string());
+        }
+
+        if !self.server.public_url.starts_with("http://")
+            && !self.server.public_url.starts_with("https://")
+        {
+            problems.push("`server.public_url` must start with http:// or https://".to_string());
+        }
+
+        if let Some(entry) = self
+            .server
+            .trusted_proxies
+            .iter()
+            .find(|entry| ip_range::parse(entry).is_none())
+        {
+            problems.push(format!(
+                "`server.trusted_proxies` has `{}`, which is not an ip address or range",
+                entry
+            ));
+        }
+
+        if self.database.username.is_some() != self.database.password.is_some() {
+            problems.push(
+                "`database.username` and `database.password` must be given together".to_string(),
+            );
+        }
+
+        if !self.smtp.email.contains('@') {
+            problems.push("`smtp.email` must be an email address".to_string());
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(ConfigError { problems }),
+        }
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<toml::Table, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|e| ConfigError {
+        problems: vec![format!("could not read {}: {}", path.display(), e)],
+    })?;
+
+    toml::from_str(&content).map_err(|e| ConfigError {
+        problems: vec![format!("could not parse {}: {}", path.display(), e)],
+    })
+}
+
+// environment values are strings, numbers and booleans are converted so they match the typed fields
+// on failure the kind of value that was expected is returned
+fn parse_environment_value(key: &str, value: String) -> Result<toml::Value, &'static str> {
+    if NUMERIC_KEYS.contains(&key) {
+        return value
+            .trim()
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| "a number");
+    }
+
+    if BOOLEAN_KEYS.contains(&key) {
+        return match value.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" => Ok(toml::Value::Boolean(true)),
+            "false" | "0" | "no" => Ok(toml::Value::Boolean(false)),
+            _ => Err("true or false"),
+        };
+    }
+
+    if LIST_KEYS.contains(&key) {
+        return Ok(toml::Value::Array(
+            value
+                .split(',')
+                .map(|entry| entry.trim())
+                .filter(|entry| !entry.is_empty())
+                .map(|entry| toml::Value::String(entry.to_string()))
+                .collect(),
+        ));
+    }
+
+    Ok(toml::Value::String(value))
+}
+
+fn environment_variable_for(key: &str) -> &'static str {
+    ENVIRONMENT_OVERRIDES
+        .iter()
+        .find(|(_, k)| *k == key)
+        .map(|(variable, _)| *variable)
+        .unwrap_or("-")
+}
+
+fn get_key<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let (section, field) = key.split_once('.')?;
+    table.get(section)?.as_table()?.get(field)
+}
+
+fn set_key(table: &mut toml::Table, key: &str, value: toml::Value) {
+    if let Some((section, field)) = key.split_once('.') {
+        let section = table
+            .entry(section.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+
+        if let toml::Value::Table(section) = section {
+            section.insert(field.to_string(), value);
+        }
+    }
+}
@@ -1,39 +1,57 @@
 use std::sync::Arc;
 
 use surrealdb::{
-    Surreal, engine::remote::ws::{Client, Ws},
+    Surreal, engine::any::{self, Any},
     opt::auth::Root
 };
 
+use crate::config::DatabaseConfig;
 
-pub async fn connect(
-    (
-        db_uri,
-        db_ns,
-        db_name,
-        username,
-        password
-    ) : ( &str, &str, &str, &str, &str)
-) -> Result<Arc<Surreal<Client>>,String> {
+// schemes of the engines that run inside the process
+// they have no users, so no sign in is done for them
+const EMBEDDED_SCHEMES: [&str; 4] = ["mem", "memory", "file", "rocksdb"];
 
-    // connect to the database
-    let db = Surreal::new::<Ws>(db_uri)
+
+pub async fn connect(config: &DatabaseConfig) -> Result<Arc<Surreal<Any>>,String> {
+
+    let endpoint = get_endpoint(&config.uri);
+
+    // connect to the database, the engine is picked from the scheme of the endpoint
+    // e.g. ws://127.0.0.1:8000, mem://, file://ufora.db
+    let db = any::connect(endpoint.as_str())
     .await
     .map_err(|e| {println!("{:?}",e);e.to_string()})?;
 
     // sign in to the database
-    db.signin( Root {
-        username,
-        password
-    })
-    .await
-    .map_err(|e| e.to_string())?;
+    if let (Some(username), Some(password), false) = (&config.username, &config.password, is_embedded(&endpoint)) {
+        db.signin( Root {
+            username,
+            password
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    }
 
     // use the namespace and database
-    db.use_ns(db_ns).use_db(db_name)
+    db.use_ns(config.namespace.as_str()).use_db(config.name.as_str())
     .await
     .map_err(|e| e.to_string())?;
 
     // return the database wrapped in an Arc
     Ok(Arc::new(db))
-}
\ No newline at end of file
+}
+
+// uris without a scheme (e.g. 127.0.0.1:8000) are remote websocket connections
+fn get_endpoint(uri: &str) -> String {
+    match uri.contains("://") {
+        true => uri.to_string(),
+        false => format!("ws://{}", uri),
+    }
+}
+
+fn is_embedded(endpoint: &str) -> bool {
+    match endpoint.split_once("://") {
+        Some((scheme, _)) => EMBEDDED_SCHEMES.contains(&scheme),
+        None => false,
+    }
+}
@@ -0,0 +1,188 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use jsonwebtoken::errors::ErrorKind;
+use serde_json::{json, Value};
+
+use super::{
+    authentication_errors::AuthenticationError, authorization_errors::AuthorizationError,
+    database_errors::DatabaseError, validation_errors::ValidationError,
+};
+
+// error returned by handlers, models and services
+// always rendered as { "code": "...", "message": "...", "details": ... }
+#[derive(Debug)]
+pub enum AppError {
+    Authentication(AuthenticationError),
+    Authorization(AuthorizationError),
+    Database(DatabaseError),
+    Validation(ValidationError),
+    NotFound { msg: String },
+    // the same request has been made too often, it can be made again after retry_after seconds
+    TooManyRequests { msg: String, retry_after: i64 },
+    Internal { msg: String },
+}
+
+impl AppError {
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        AppError::NotFound { msg: msg.into() }
+    }
+
+    pub fn internal(msg: impl Into<String>) -> Self {
+        AppError::Internal { msg: msg.into() }
+    }
+
+    pub fn invalid_input(msg: impl Into<String>) -> Self {
+        AppError::Validation(ValidationError::InvalidInput { msg: msg.into() })
+    }
+
+    pub fn too_many_requests(msg: impl Into<String>, retry_after: i64) -> Self {
+        AppError::TooManyRequests {
+            msg: msg.into(),
+            retry_after,
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::Authentication(e) => e.status_code(),
+            AppError::Authorization(e) => e.status_code(),
+            AppError::Database(e) => e.status_code(),
+            AppError::Validation(e) => e.status_code(),
+            AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Authentication(e) => e.code(),
+            AppError::Authorization(e) => e.code(),
+            AppError::Database(e) => e.code(),
+            AppError::Validation(e) => e.code(),
+            AppError::NotFound { .. } => "NOT_FOUND",
+            AppError::TooManyRequests { .. } => "TOO_MANY_REQUESTS",
+            AppError::Internal { .. } => "INTERNAL_ERROR",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Authentication(e) => e.message(),
+            AppError::Authorization(e) => e.message(),
+            AppError::Database(e) => e.message(),
+            AppError::Validation(e) => e.message(),
+            AppError::NotFound { msg } | AppError::TooManyRequests { msg, .. } => msg.clone(),
+            // internal details are logged, never returned
+            AppError::Internal { .. } => "Something went wrong".to_string(),
+        }
+    }
+
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AppError::Authentication(e) => e.details(),
+            AppError::Validation(e) => e.details(),
+            AppError::TooManyRequests { retry_after, .. } => {
+                Some(json!({ "retry_after_seconds": retry_after }))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<i64> {
+        match self {
+            AppError::Authentication(e) => e.retry_after(),
+            AppError::TooManyRequests { retry_after, .. } => Some(*retry_after),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status_code();
+
+        if status.is_server_error() {
+            tracing::error!("{:?}", self);
+        }
+
+        let body = json!({
+            "code": self.code(),
+            "message": self.message(),
+            "details": self.details(),
+        });
+
+        match self.retry_after() {
+            Some(seconds) => (
+                status,
+                [(header::RETRY_AFTER, seconds.to_string())],
+                Json(body),
+            )
+                .into_response(),
+            None => (status, Json(body)).into_response(),
+        }
+    }
+}
+
+impl From<AuthenticationError> for AppError {
+    fn from(e: AuthenticationError) -> Self {
+        AppError::Authentication(e)
+    }
+}
+
+impl From<AuthorizationError> for AppError {
+    fn from(e: AuthorizationError) -> Self {
+        AppError::Authorization(e)
+    }
+}
+
+impl From<DatabaseError> for AppError {
+    fn from(e: DatabaseError) -> Self {
+        AppError::Database(e)
+    }
+}
+
+impl From<ValidationError> for AppError {
+    fn from(e: ValidationError) -> Self {
+        AppError::Validation(e)
+    }
+}
+
+impl From<surrealdb::Error> for AppError {
+    fn from(e: surrealdb::Error) -> Self {
+        let msg = e.to_string();
+
+        // unique index violations, e.g. "Database index `user_email` already contains ..."
+        if msg.contains("already contains") {
+            return AppError::Database(DatabaseError::DuplicateEntryError {
+                msg: "A record with the same details already exists".to_string(),
+            });
+        }
+
+        AppError::Database(DatabaseError::QueryError { msg })
+    }
+}
+
+impl From<validator::ValidationErrors> for AppError {
+    fn from(errors: validator::ValidationErrors) -> Self {
+        AppError::Validation(ValidationError::InvalidFields { errors })
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(e: jsonwebtoken::errors::Error) -> Self {
+        match e.kind() {
+            ErrorKind::ExpiredSignature => {
+                AppError::Authentication(AuthenticationError::ExpiredToken {
+                    msg: "Token has expired".to_string(),
+                })
+            }
+            _ => AppError::Authentication(AuthenticationError::InvalidToken {
+                msg: "Invalid token".to_string(),
+            }),
+        }
+    }
+}
@@ -1,37 +1,78 @@
 #![allow(dead_code)]
-use axum::response::{IntoResponse,Response};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
 
+use super::app_error::AppError;
 
+#[derive(Debug)]
 pub enum AuthenticationError {
     InvalidCredentials { msg: String }, // Invalid username or password
-    InvalidToken { msg: String }, // Invalid token
-    ExpiredToken { msg: String }, // Expired token
-    InactiveUser { msg: String }, // Inactive user means locked account or disabled account
+    InvalidToken { msg: String },       // Invalid token
+    ExpiredToken { msg: String },       // Expired token
+    InactiveUser { msg: String },       // Inactive user means locked account or disabled account
     MissingCredentials { msg: String }, // Missing username or password
+    // Too many failed logins, the account can be used again after locked_until
+    AccountLocked { msg: String, locked_until: DateTime<Utc> },
 }
 
-impl IntoResponse for AuthenticationError {
+impl AuthenticationError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthenticationError::MissingCredentials { .. } => StatusCode::BAD_REQUEST,
+            AuthenticationError::AccountLocked { .. } => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
 
-    fn into_response(self) -> Response {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthenticationError::InvalidCredentials { .. } => "INVALID_CREDENTIALS",
+            AuthenticationError::InvalidToken { .. } => "INVALID_TOKEN",
+            AuthenticationError::ExpiredToken { .. } => "EXPIRED_TOKEN",
+            AuthenticationError::InactiveUser { .. } => "INACTIVE_USER",
+            AuthenticationError::MissingCredentials { .. } => "MISSING_CREDENTIALS",
+            AuthenticationError::AccountLocked { .. } => "ACCOUNT_LOCKED",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            AuthenticationError::InvalidCredentials { msg }
+            | AuthenticationError::InvalidToken { msg }
+            | AuthenticationError::ExpiredToken { msg }
+            | AuthenticationError::InactiveUser { msg }
+            | AuthenticationError::MissingCredentials { msg }
+            | AuthenticationError::AccountLocked { msg, .. } => msg.clone(),
+        }
+    }
 
+    // seconds until the request can be retried, sent as the Retry-After header
+    pub fn retry_after(&self) -> Option<i64> {
         match self {
-            AuthenticationError::InvalidCredentials { msg } => {
-                (axum::http::StatusCode::UNAUTHORIZED, msg).into_response()
-            },
-            AuthenticationError::InvalidToken { msg } => {
-                (axum::http::StatusCode::UNAUTHORIZED, msg).into_response()
-            },
-            AuthenticationError::ExpiredToken { msg } => {
-                (axum::http::StatusCode::UNAUTHORIZED, msg).into_response()
-            },
-            AuthenticationError::InactiveUser { msg } => {
-                (axum::http::StatusCode::UNAUTHORIZED, msg).into_response()
-            },
-            AuthenticationError::MissingCredentials { msg } => {
-                (axum::http::StatusCode::BAD_REQUEST, msg).into_response()
-            },
+            AuthenticationError::AccountLocked { locked_until, .. } => {
+                Some((*locked_until - Utc::now()).num_seconds().max(1))
+            }
+            _ => None,
         }
+    }
 
+    pub fn details(&self) -> Option<Value> {
+        match self {
+            AuthenticationError::AccountLocked { locked_until, .. } => Some(json!({
+                "locked_until": locked_until,
+                "retry_after_seconds": self.retry_after(),
+            })),
+            _ => None,
+        }
     }
+}
 
-}
\ No newline at end of file
+impl IntoResponse for AuthenticationError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
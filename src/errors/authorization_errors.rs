@@ -1,29 +1,45 @@
 #![allow(dead_code)]
-use axum::response::{IntoResponse,Response};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 
-#[derive(serde::Serialize)]
+use super::app_error::AppError;
+
+#[derive(serde::Serialize, Debug)]
 pub enum AuthorizationError {
     AccessDeniedError { msg: String },
     InsufficientPermissionsError { msg: String },
     ResourseNotFoundError { msg: String },
 }
 
-impl IntoResponse for AuthorizationError {
-
-    fn into_response(self) -> Response {
+impl AuthorizationError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            AuthorizationError::ResourseNotFoundError { .. } => StatusCode::NOT_FOUND,
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
 
+    pub fn code(&self) -> &'static str {
         match self {
-            AuthorizationError::AccessDeniedError { msg } => {
-                (axum::http::StatusCode::FORBIDDEN, msg).into_response()
-            },
-            AuthorizationError::InsufficientPermissionsError { msg } => {
-                (axum::http::StatusCode::FORBIDDEN, msg).into_response()
-            },
-            AuthorizationError::ResourseNotFoundError { msg } => {
-                (axum::http::StatusCode::NOT_FOUND, msg).into_response()
-            },
+            AuthorizationError::AccessDeniedError { .. } => "ACCESS_DENIED",
+            AuthorizationError::InsufficientPermissionsError { .. } => "INSUFFICIENT_PERMISSIONS",
+            AuthorizationError::ResourseNotFoundError { .. } => "RESOURCE_NOT_FOUND",
         }
+    }
 
+    pub fn message(&self) -> String {
+        match self {
+            AuthorizationError::AccessDeniedError { msg }
+            | AuthorizationError::InsufficientPermissionsError { msg }
+            | AuthorizationError::ResourseNotFoundError { msg } => msg.clone(),
+        }
     }
+}
 
-}
\ No newline at end of file
+impl IntoResponse for AuthorizationError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
@@ -1,30 +1,47 @@
 #![allow(dead_code)]
-use axum::response::{IntoResponse,Response};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 
+use super::app_error::AppError;
 
+#[derive(Debug)]
 pub enum DatabaseError {
     ConnectionError { msg: String },
     QueryError { msg: String },
     DuplicateEntryError { msg: String },
-
 }
 
-impl IntoResponse for DatabaseError {
-
-    fn into_response(self) -> Response {
+impl DatabaseError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            DatabaseError::DuplicateEntryError { .. } => StatusCode::BAD_REQUEST,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
 
+    pub fn code(&self) -> &'static str {
         match self {
-            DatabaseError::ConnectionError { msg } => {
-                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, msg).into_response()
-            },
-            DatabaseError::QueryError { msg } => {
-                (axum::http::StatusCode::INTERNAL_SERVER_ERROR, msg).into_response()
-            },
-            DatabaseError::DuplicateEntryError { msg } => {
-                (axum::http::StatusCode::BAD_REQUEST, msg).into_response()
-            },
+            DatabaseError::ConnectionError { .. } => "DATABASE_UNAVAILABLE",
+            DatabaseError::QueryError { .. } => "DATABASE_ERROR",
+            DatabaseError::DuplicateEntryError { .. } => "DUPLICATE_ENTRY",
         }
+    }
 
+    // connection and query errors carry database internals, they are logged but not returned
+    pub fn message(&self) -> String {
+        match self {
+            DatabaseError::ConnectionError { .. } | DatabaseError::QueryError { .. } => {
+                "Something went wrong while accessing the database".to_string()
+            }
+            DatabaseError::DuplicateEntryError { msg } => msg.clone(),
+        }
     }
+}
 
-}
\ No newline at end of file
+impl IntoResponse for DatabaseError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
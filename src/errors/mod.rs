@@ -3,5 +3,7 @@ pub mod validation_errors;
 pub mod database_errors;
 pub mod authentication_errors;
 pub mod authorization_errors;
+pub mod app_error;
 
+pub use app_error::AppError;
 
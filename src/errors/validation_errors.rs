@@ -1,44 +1,113 @@
 #![allow(dead_code)]
-use axum::response::{IntoResponse,Response};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::{Map, Value};
+use validator::{ValidationErrors, ValidationErrorsKind};
 
+use super::app_error::AppError;
+
+#[derive(Debug)]
 pub enum ValidationError {
     InvalidEmail,
-    InvalidPassword,
+    InvalidPassword { errors: ValidationErrors }, // password policy violations, reported like fields
     InvalidUsername,
     InvalidAge,
     InvalidPhoneNumber,
     InvalidDate { msg: String },
     InvalidCredentials { msg: String },
-} 
+    InvalidInput { msg: String }, // request is missing or has malformed values
+    InvalidFields { errors: ValidationErrors }, // validator errors, reported per field
+}
 
-impl IntoResponse for ValidationError {
+impl ValidationError {
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ValidationError::InvalidCredentials { .. } => StatusCode::NOT_FOUND,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
 
-    fn into_response(self) -> Response {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidEmail => "INVALID_EMAIL",
+            ValidationError::InvalidPassword { .. } => "INVALID_PASSWORD",
+            ValidationError::InvalidUsername => "INVALID_USERNAME",
+            ValidationError::InvalidAge => "INVALID_AGE",
+            ValidationError::InvalidPhoneNumber => "INVALID_PHONE_NUMBER",
+            ValidationError::InvalidDate { .. } => "INVALID_DATE",
+            ValidationError::InvalidCredentials { .. } => "INVALID_CREDENTIALS",
+            ValidationError::InvalidInput { .. } => "INVALID_INPUT",
+            ValidationError::InvalidFields { .. } => "VALIDATION_FAILED",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            ValidationError::InvalidEmail => "Invalid Email".to_string(),
+            ValidationError::InvalidPassword { .. } => {
+                "Password does not meet the password policy".to_string()
+            }
+            ValidationError::InvalidUsername => "Invalid Username Format".to_string(),
+            ValidationError::InvalidAge => "Invalid Age".to_string(),
+            ValidationError::InvalidPhoneNumber => "Invalid Phone Number".to_string(),
+            ValidationError::InvalidDate { msg }
+            | ValidationError::InvalidCredentials { msg }
+            | ValidationError::InvalidInput { msg } => msg.clone(),
+            ValidationError::InvalidFields { .. } => "Some fields are invalid".to_string(),
+        }
+    }
 
+    // field level messages, e.g. { "content.blocks": ["Blocks of the blog are required"] }
+    pub fn details(&self) -> Option<Value> {
         match self {
-            ValidationError::InvalidEmail => {
-                (axum::http::StatusCode::BAD_REQUEST, "Invalid Email").into_response()
-            },
-            ValidationError::InvalidPassword => {
-                (axum::http::StatusCode::BAD_REQUEST, "Invalid Password Format").into_response()
-            },
-            ValidationError::InvalidUsername => {
-                (axum::http::StatusCode::BAD_REQUEST, "Invalid Username Format").into_response()
-            },
-            ValidationError::InvalidAge => {
-                (axum::http::StatusCode::BAD_REQUEST, "Invalid Age").into_response()
-            },
-            ValidationError::InvalidPhoneNumber => {
-                (axum::http::StatusCode::BAD_REQUEST, "Invalid Phone Number").into_response()
-            },
-            ValidationError::InvalidDate { msg } => {
-                (axum::http::StatusCode::BAD_REQUEST, msg).into_response()
-            },
-            ValidationError::InvalidCredentials { msg } => {
-                (axum::http::StatusCode::NOT_FOUND, msg).into_response()
+            ValidationError::InvalidFields { errors }
+            | ValidationError::InvalidPassword { errors } => {
+                let mut fields = Map::new();
+                collect_field_messages(errors, "", &mut fields);
+                Some(Value::Object(fields))
             }
+            _ => None,
         }
+    }
+}
+
+// flattens nested validation errors into dotted field paths
+fn collect_field_messages(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut Map<String, Value>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix.is_empty() {
+            true => field.to_string(),
+            false => format!("{}.{}", prefix, field),
+        };
 
+        match kind {
+            ValidationErrorsKind::Field(field_errors) => {
+                let messages: Vec<String> = field_errors
+                    .iter()
+                    .map(|error| match &error.message {
+                        Some(message) => message.to_string(),
+                        None => error.code.to_string(),
+                    })
+                    .collect();
+                fields.insert(path, Value::from(messages));
+            }
+            ValidationErrorsKind::Struct(nested) => collect_field_messages(nested, &path, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect_field_messages(nested, &format!("{}[{}]", path, index), fields);
+                }
+            }
+        }
     }
+}
 
-}
\ No newline at end of file
+impl IntoResponse for ValidationError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    config::Config,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::{account::Account, user::User, user_claim::Claim},
+    services::{email::Mailer, password},
+};
+
+// request struct for deleting the account of the signed in user
+#[derive(serde::Deserialize)]
+pub struct DeleteAccountRequest {
+    password: String,
+}
+
+// response struct for the account actions
+#[derive(serde::Serialize)]
+pub struct AccountResponse {
+    message: String,
+    // set once the deletion of the account has been requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deletion_scheduled_for: Option<DateTime<Utc>>,
+}
+
+fn done(message: &str) -> Json<AccountResponse> {
+    Json(AccountResponse {
+        message: message.to_string(),
+        deletion_scheduled_for: None,
+    })
+}
+
+// hides the profile and content of the user until the account is reactivated
+pub async fn deactivate_account(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<AccountResponse>, AppError> {
+    let account = Account::of(&db, claim.get_surrealdb_thing()).await?;
+
+    account.set_deactivated(&db, true).await?;
+
+    Ok(done("Account has been deactivated"))
+}
+
+pub async fn reactivate_account(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<AccountResponse>, AppError> {
+    let account = Account::of(&db, claim.get_surrealdb_thing()).await?;
+
+    // the deletion has to be cancelled instead
+    if account.deletion_scheduled_for().is_some() {
+        return Err(AppError::invalid_input(
+            "The deletion of this account has been requested, cancel it to keep the account",
+        ));
+    }
+
+    if !account.is_deactivated() {
+        return Err(AppError::invalid_input("Account is not deactivated"));
+    }
+
+    account.set_deactivated(&db, false).await?;
+
+    Ok(done("Account has been reactivated"))
+}
+
+// deactivates the account right away and deletes it after the grace period
+pub async fn delete_account(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Mailer>,
+    claim: Claim,
+    Json(request): Json<DeleteAccountRequest>,
+) -> Result<Json<AccountResponse>, AppError> {
+    let user = User::get_user_by_id(db.clone(), claim.get_id()).await?;
+
+    let stored_password = user
+        .get_password()
+        .ok_or(AppError::invalid_input("This account has no password"))?;
+
+    // a wrong password counts as a failed login, a stolen token can not guess it
+    if !password::verify_password(request.password, stored_password)? {
+        user.record_failed_login(db.clone()).await?;
+
+        return Err(AuthenticationError::InvalidCredentials {
+            msg: "Password is incorrect".to_string(),
+        }
+        .into());
+    }
+
+    let account = Account::of(&db, user.get_id()).await?;
+    let scheduled_for = account
+        .schedule_deletion(&db, config.account.deletion_grace_days)
+        .await?;
+
+    mailer
+        .send_email(
+            &format!("{} <{}>", account.get_username(), account.get_email()),
+            "Your Ufora account will be deleted".to_string(),
+            format!(
+                "Your Ufora account {} will be deleted on {}. Until then it is hidden from other users, and you can log in and cancel the deletion to keep it.",
+                account.get_username(),
+                scheduled_for.format("%Y-%m-%d %H:%M UTC")
+            ),
+        )
+        .await?;
+
+    Ok(Json(AccountResponse {
+        message: "Account will be deleted".to_string(),
+        deletion_scheduled_for: Some(scheduled_for),
+    }))
+}
+
+pub async fn cancel_account_deletion(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<AccountResponse>, AppError> {
+    let account = Account::of(&db, claim.get_surrealdb_thing()).await?;
+
+    account.cancel_deletion(&db).await?;
+
+    Ok(done("Account deletion has been cancelled"))
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde_json::Value;
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::AppError,
+    models::{
+        admin::{self, Stats, UserSearch},
+        blog::Blog,
+        post::Post,
+        revoked_token::RevokedToken,
+        session::Session,
+        user::User,
+        user_claim::Claim,
+    },
+    services::query_builder::record,
+};
+
+// response struct for the admin actions
+#[derive(serde::Serialize)]
+pub struct AdminResponse {
+    message: String,
+}
+
+// request struct for rejecting the verification file of a club
+#[derive(serde::Deserialize)]
+pub struct ClubRejectionRequest {
+    reason: String,
+}
+
+fn done(message: &str) -> Json<AdminResponse> {
+    Json(AdminResponse {
+        message: message.to_string(),
+    })
+}
+
+// lists users, filtered by ?search=, ?user_type= and ?locked=true, paged with ?limit= and ?start=
+pub async fn list_users(
+    State(db): State<Arc<Surreal<Any>>>,
+    Query(search): Query<UserSearch>,
+) -> Result<Json<Vec<Value>>, AppError> {
+    Ok(Json(admin::search_users(&db, search).await?))
+}
+
+pub async fn get_user(
+    State(db): State<Arc<Surreal<Any>>>,
+    Path(user_id): Path<String>,
+) -> Result<Json<Value>, AppError> {
+    Ok(Json(admin::get_user(&db, record("user", user_id)).await?))
+}
+
+// locks the account until an administrator unlocks it, its tokens stop working right away
+pub async fn lock_user(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminResponse>, AppError> {
+    if user_id == claim.get_id() {
+        return Err(AppError::invalid_input("You can not lock your own account"));
+    }
+
+    let user = record("user", user_id);
+
+    User::set_locked_by_admin(db.clone(), user.clone(), true).await?;
+    RevokedToken::revoke_all_issued_to(&db, user.clone()).await?;
+    Session::end_all(&db, user.clone()).await?;
+
+    tracing::info!(
+        "{} was locked by the administrator {}",
+        user,
+        claim.get_id()
+    );
+
+    Ok(done("User has been locked"))
+}
+
+// lifts a lock by an administrator as well as a lockout after failed logins
+pub async fn unlock_user(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(user_id): Path<String>,
+) -> Result<Json<AdminResponse>, AppError> {
+    let user = record("user", user_id);
+
+    User::set_locked_by_admin(db, user.clone(), false).await?;
+
+    tracing::info!(
+        "{} was unlocked by the administrator {}",
+        user,
+        claim.get_id()
+    );
+
+    Ok(done("User has been unlocked"))
+}
+
+pub async fn get_clubs_pending_verification(
+    State(db): State<Arc<Surreal<Any>>>,
+) -> Result<Json<Vec<Value>>, AppError> {
+    Ok(Json(admin::clubs_pending_verification(&db).await?))
+}
+
+pub async fn approve_club(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(club_id): Path<String>,
+) -> Result<Json<AdminResponse>, AppError> {
+    let club = record("user", club_id);
+
+    admin::decide_club_verification(&db, club.clone(), None).await?;
+
+    tracing::info!(
+        "{} was verified by the administrator {}",
+        club,
+        claim.get_id()
+    );
+
+    Ok(done("Club has been verified"))
+}
+
+pub async fn reject_club(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(club_id): Path<String>,
+    Json(rejection): Json<ClubRejectionRequest>,
+) -> Result<Json<AdminResponse>, AppError> {
+    if rejection.reason.trim().is_empty() {
+        return Err(AppError::invalid_input(
+            "A reason for the rejection is required",
+        ));
+    }
+
+    let club = record("user", club_id);
+
+    admin::decide_club_verification(&db, club.clone(), Some(rejection.reason)).await?;
+
+    tracing::info!(
+        "{} was rejected by the administrator {}",
+        club,
+        claim.get_id()
+    );
+
+    Ok(done("Club verification has been rejected"))
+}
+
+pub async fn remove_post(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(post_id): Path<String>,
+) -> Result<Json<AdminResponse>, AppError> {
+    let post = record("post", post_id);
+
+    Post::delete_post_by_id(db, post.clone()).await?;
+
+    tracing::info!(
+        "{} was removed by the administrator {}",
+        post,
+        claim.get_id()
+    );
+
+    Ok(done("Post has been removed"))
+}
+
+pub async fn remove_blog(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(blog_id): Path<String>,
+) -> Result<Json<AdminResponse>, AppError> {
+    let blog = record("blog", blog_id);
+
+    Blog::delete_blog_by_id(db, blog.clone()).await?;
+
+    tracing::info!(
+        "{} was removed by the administrator {}",
+        blog,
+        claim.get_id()
+    );
+
+    Ok(done("Blog has been removed"))
+}
+
+pub async fn remove_comment(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path((post_id, comment_id)): Path<(String, String)>,
+) -> Result<Json<AdminResponse>, AppError> {
+    let post = record("post", post_id);
+
+    if !Post::has_comment(db.clone(), post.clone(), &comment_id).await? {
+        return Err(AppError::not_found("Comment not found"));
+    }
+
+    Post::delete_a_comment(db, post.clone(), comment_id.clone()).await?;
+
+    tracing::info!(
+        "comment {} on {} was removed by the administrator {}",
+        comment_id,
+        post,
+        claim.get_id()
+    );
+
+    Ok(done("Comment has been removed"))
+}
+
+pub async fn get_stats(State(db): State<Arc<Surreal<Any>>>) -> Result<Json<Stats>, AppError> {
+    Ok(Json(admin::stats(&db).await?))
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::AppError,
+    models::{
+        api_key::{ApiKey, ApiScope},
+        user_claim::Claim,
+    },
+};
+
+// request struct for creating an api key
+#[derive(serde::Deserialize)]
+pub struct ApiKeyRequest {
+    name: String,
+    // e.g. ["profiles:read", "posts:write"]
+    scopes: Vec<String>,
+    // addresses or cidr ranges the key can be used from, every address when missing
+    allowed_ips: Option<Vec<String>>,
+}
+
+#[derive(serde::Serialize)]
+pub struct ApiKeyResponse {
+    id: String,
+    name: String,
+    scopes: Vec<String>,
+    allowed_ips: Vec<String>,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+// the key is only returned when it is created
+#[derive(serde::Serialize)]
+pub struct CreatedApiKeyResponse {
+    key: String,
+    #[serde(flatten)]
+    api_key: ApiKeyResponse,
+}
+
+#[derive(serde::Serialize)]
+pub enum ApiKeyRouteResponse {
+    Success { message: String },
+}
+
+impl From<ApiKey> for ApiKeyResponse {
+    fn from(api_key: ApiKey) -> Self {
+        Self {
+            id: api_key.get_id(),
+            name: api_key.get_name(),
+            scopes: api_key.get_scopes(),
+            allowed_ips: api_key.get_allowed_ips(),
+            created_at: api_key.get_created_at().0,
+            last_used_at: api_key
+                .get_last_used_at()
+                .map(|last_used_at| last_used_at.0),
+        }
+    }
+}
+
+pub async fn create_api_key(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Json(request): Json<ApiKeyRequest>,
+) -> Result<Json<CreatedApiKeyResponse>, AppError> {
+    let scopes = request
+        .scopes
+        .iter()
+        .map(|scope| {
+            ApiScope::parse(scope).ok_or(AppError::invalid_input(format!(
+                "{} is not a valid scope",
+                scope
+            )))
+        })
+        .collect::<Result<Vec<ApiScope>, AppError>>()?;
+
+    let (api_key, key) = ApiKey::create(
+        &db,
+        claim.get_surrealdb_thing(),
+        request.name,
+        scopes,
+        request.allowed_ips.unwrap_or_default(),
+    )
+    .await?;
+
+    Ok(Json(CreatedApiKeyResponse {
+        key,
+        api_key: api_key.into(),
+    }))
+}
+
+// keys of the user that still work, without the keys themselves
+pub async fn get_api_keys(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<Vec<ApiKeyResponse>>, AppError> {
+    let api_keys = ApiKey::list_for(&db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(
+        api_keys.into_iter().map(ApiKeyResponse::from).collect(),
+    ))
+}
+
+// the key stops working right away
+pub async fn revoke_api_key(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(key_id): Path<String>,
+) -> Result<Json<ApiKeyRouteResponse>, AppError> {
+    ApiKey::revoke(&db, claim.get_surrealdb_thing(), key_id).await?;
+
+    Ok(Json(ApiKeyRouteResponse::Success {
+        message: "API key has been revoked".to_string(),
+    }))
+}
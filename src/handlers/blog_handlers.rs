@@ -5,16 +5,14 @@ use axum::{
     http::StatusCode,
     Json,
 };
-use axum_valid::Valid;
-use surrealdb::{engine::remote::ws::Client, Surreal};
+use surrealdb::{engine::any::Any, Surreal};
 use validator::Validate;
 
-use crate::models::blog;
+use crate::{errors::AppError, models::blog, services::policy::Policy};
 
 #[derive(serde::Serialize)]
 pub enum BlogRouteResponse {
     Success { message: String },
-    Failed { message: String },
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
@@ -44,11 +42,14 @@ pub struct BlogCreateResponse {
 }
 
 pub async fn create_a_blog(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
-    Valid(Json(blog_request)): Valid<Json<BlogCreateRequest>>,
-) -> (StatusCode, Json<BlogCreateResponse>) {
+    Json(blog_request): Json<BlogCreateRequest>,
+) -> Result<(StatusCode, Json<BlogCreateResponse>), AppError> {
+    blog_request.validate()?;
+
     let blog_create_request_content = blog_request.content.unwrap();
+    blog_create_request_content.validate()?;
 
     let new_blog_content = blog::BlogContent {
         time: blog_create_request_content.time.unwrap().to_string(),
@@ -58,59 +59,39 @@ pub async fn create_a_blog(
 
     let new_blog = blog::Blog::new(blog_request.title.unwrap(), new_blog_content);
 
-    match new_blog.save(db, Some(claim.get_surrealdb_thing())).await {
-        Ok(_) => (
-            StatusCode::CREATED,
-            Json(BlogCreateResponse {
-                message: "Blog created successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("Error: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(BlogCreateResponse { message: e }),
-            )
-        }
-    }
+    new_blog.save(db, Some(claim.get_surrealdb_thing())).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(BlogCreateResponse {
+            message: "Blog created successfully".to_string(),
+        }),
+    ))
 }
 
 pub async fn get_blogs_of_the_user_by_user_id(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
-) -> (StatusCode, Json<Vec<blog::Blog>>) {
-    let blogs = blog::Blog::get_blogs_by_user_id(db, claim.get_surrealdb_thing()).await;
-
-    match blogs {
-        Ok(blogs) => (StatusCode::OK, Json(blogs)),
-        Err(e) => {
-            println!("Error: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
-        }
-    }
+) -> Result<Json<Vec<blog::Blog>>, AppError> {
+    let blogs = blog::Blog::get_blogs_by_user_id(db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(blogs))
 }
 
 pub async fn delete_a_blog_of_the_user(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
     Path(blog_id): Path<String>,
-) -> (StatusCode, Json<BlogRouteResponse>) {
-    match blog::Blog::delete_a_blog_belongs_to_user(db, blog_id, claim.get_surrealdb_thing()).await
-    {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(BlogRouteResponse::Failed {
-                message: "Blog deleted successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("Error: {}", e);
-            (
-                StatusCode::BAD_REQUEST,
-                Json(BlogRouteResponse::Success { message: e }),
-            )
-        }
-    }
+) -> Result<Json<BlogRouteResponse>, AppError> {
+    Policy::new(&db, &claim)
+        .can_modify::<blog::Blog>(blog_id.clone())
+        .await?;
+
+    blog::Blog::delete_a_blog_belongs_to_user(db, blog_id, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(BlogRouteResponse::Success {
+        message: "Blog deleted successfully".to_string(),
+    }))
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Validate)]
@@ -125,46 +106,36 @@ pub struct BlogUpdateRequest {
 }
 
 pub async fn update_blog_content(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
     Path(blog_id): Path<String>,
-    Valid(Json(blog_request)): Valid<Json<BlogUpdateRequest>>,
-) -> (StatusCode, Json<BlogRouteResponse>) {
-    if let Some(mut blog) = blog::Blog::get_blog_by_id(db.clone(), blog_id).await {
-        let new_content = blog_request.content.unwrap();
-
-        blog.set_blog_content(blog::BlogContent {
-            time: new_content.time.unwrap().to_string(),
-            blocks: new_content.blocks.unwrap(),
-            version: new_content.version.unwrap(),
-        });
-
-        blog.set_blog_title(blog_request.title.unwrap());
-
-        match blog
-            .update_blog_of_user_by_id(db, claim.get_surrealdb_thing())
-            .await
-        {
-            Ok(_) => (
-                StatusCode::OK,
-                Json(BlogRouteResponse::Success {
-                    message: "Blog updated successfully".to_string(),
-                }),
-            ),
-            Err(e) => {
-                println!("Error: {}", e);
-                (
-                    StatusCode::UNAUTHORIZED,
-                    Json(BlogRouteResponse::Failed { message: e }),
-                )
-            }
-        }
-    } else {
-        return (
-            StatusCode::NOT_FOUND,
-            Json(BlogRouteResponse::Failed {
-                message: "Blog for the give id not found".to_string(),
-            }),
-        );
-    }
+    Json(blog_request): Json<BlogUpdateRequest>,
+) -> Result<Json<BlogRouteResponse>, AppError> {
+    blog_request.validate()?;
+
+    Policy::new(&db, &claim)
+        .can_modify::<blog::Blog>(blog_id.clone())
+        .await?;
+
+    let mut blog = blog::Blog::get_blog_by_id(db.clone(), blog_id, claim.get_surrealdb_thing())
+        .await?
+        .ok_or(AppError::not_found("Blog for the give id not found"))?;
+
+    let new_content = blog_request.content.unwrap();
+    new_content.validate()?;
+
+    blog.set_blog_content(blog::BlogContent {
+        time: new_content.time.unwrap().to_string(),
+        blocks: new_content.blocks.unwrap(),
+        version: new_content.version.unwrap(),
+    });
+
+    blog.set_blog_title(blog_request.title.unwrap());
+
+    blog.update_blog_of_user_by_id(db, claim.get_surrealdb_thing())
+        .await?;
+
+    Ok(Json(BlogRouteResponse::Success {
+        message: "Blog updated successfully".to_string(),
+    }))
 }
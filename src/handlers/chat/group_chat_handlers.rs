@@ -56,7 +56,7 @@ async fn websocket(stream: WebSocket, websocket_extension: Arc<GroupChatWebsocke
                 }
             };
 
-            println!("connect: {:?}", connect);
+            tracing::debug!("connect: {:?}", connect);
 
             // Scope to drop the mutex guard before the next await
             {
@@ -64,7 +64,6 @@ async fn websocket(stream: WebSocket, websocket_extension: Arc<GroupChatWebsocke
                 let mut rooms = websocket_extension.rooms.lock().unwrap();
 
                 channel = connect.channel.clone();
-                println!("channel: {:?}", channel);
                 let room = rooms.entry(connect.channel).or_insert_with(RoomState::new);
 
                 tx = Some(room.tx.clone());
@@ -89,14 +88,17 @@ async fn websocket(stream: WebSocket, websocket_extension: Arc<GroupChatWebsocke
                 let _ = sender
                     .send(Message::Text(String::from("Username already taken.")))
                     .await;
-                println!("Username already taken.");
+                tracing::debug!("Username already taken.");
                 return;
             }
         }
     }
 
-    // We know if the loop exited `tx` is not `None`.
-    let tx = tx.unwrap();
+    // `tx` is `None` if the client left or sent an invalid connect message.
+    let tx = match tx {
+        Some(tx) => tx,
+        None => return,
+    };
     // Subscribe before sending joined message.
     let mut rx = tx.subscribe();
 
@@ -137,7 +139,7 @@ async fn websocket(stream: WebSocket, websocket_extension: Arc<GroupChatWebsocke
                     }
                 );
 
-                println!("{:?}", message);
+                tracing::debug!("{:?}", message);
 
                 // Add username before message.
                 let _ = tx.send(
@@ -159,24 +161,21 @@ async fn websocket(stream: WebSocket, websocket_extension: Arc<GroupChatWebsocke
     let mut rooms = websocket_extension.rooms.lock().unwrap();
 
     // Remove username from map so new clients can take it.
-    Mutex::get_mut(&mut rooms.get_mut(&channel).unwrap().users)
-        .unwrap()
-        .remove(&username);
+    if let Some(room) = rooms.get_mut(&channel) {
+        Mutex::get_mut(&mut room.users).unwrap().remove(&username);
+    }
 
     // send left message to all in the room
     let msg = format!("{} left.", username);
     tracing::debug!("{}", msg);
-    println!("{:?}", msg);
     let _ = tx.send(msg);
 
 
     // Check if the room is empty now and remove the `RoomState` from the map.
-    if Mutex::get_mut(&mut rooms.get_mut(&channel).unwrap().users)
-        .unwrap()
-        .is_empty()
-    {
-        rooms.remove(&channel);
-        println!("room removed: {:?}", channel);
-    }  
-    println!("rooms: {:?}", rooms);
+    if let Some(room) = rooms.get_mut(&channel) {
+        if Mutex::get_mut(&mut room.users).unwrap().is_empty() {
+            rooms.remove(&channel);
+            tracing::debug!("room removed: {:?}", channel);
+        }
+    }
 }
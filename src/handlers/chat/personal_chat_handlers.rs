@@ -9,20 +9,20 @@ use axum::{
     Extension, Json,
 };
 use futures::{SinkExt, StreamExt};
-use reqwest::StatusCode;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
 use tokio::sync::broadcast;
 
 use crate::{
+    errors::AppError,
     models::chat::{ChatMessage, People, PersonalChat},
     services::websocket::{PersonalChatWebsocketExtension, RoomState},
 };
 
 pub async fn websocket_handler(
     ws: WebSocketUpgrade,
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
 
     Extension(websocket_extension): Extension<Arc<PersonalChatWebsocketExtension>>,
@@ -62,28 +62,39 @@ async fn websocket(
                 }
             };
 
-            connect
-                .as_object_mut()
-                .unwrap()
-                .insert("userid".to_string(), userid.clone().into());
+            // the connect message has to be an object naming the channel
+            let channel_name = match connect.get("channel") {
+                Some(channel_name) => channel_name.to_string(),
+                _ => {
+                    let _ = sender
+                        .send(Message::Text(String::from(
+                            "Connect message must contain a channel",
+                        )))
+                        .await;
+                    break;
+                }
+            };
+
+            if let Some(connect) = connect.as_object_mut() {
+                connect.insert("userid".to_string(), userid.clone().into());
+            }
 
-            println!("connect: {:?}", connect);
+            tracing::debug!("connect: {:?}", connect);
 
             // Scope to drop the mutex guard before the next await
             {
                 // If userid that is sent by client is not taken, fill userid string.
                 let mut rooms = websocket_extension.rooms.lock().unwrap();
 
-                channel = connect.get("channel").unwrap().to_string();
-                println!("channel: {:?}", channel);
-                let room = rooms.entry(connect.get("channel").unwrap().to_string()).or_insert_with(RoomState::new);
+                channel = channel_name;
+                let room = rooms.entry(channel.clone()).or_insert_with(RoomState::new);
 
                 tx = Some(room.tx.clone());
 
                 // if the user is not in the room, add the user to the room
                 if !Mutex::get_mut(&mut room.users)
                     .unwrap()
-                    .contains(&connect.get("userid").unwrap().to_string())
+                    .contains(&userid)
                 {
                     Mutex::get_mut(&mut room.users)
                         .unwrap()
@@ -99,14 +110,17 @@ async fn websocket(
                 let _ = sender
                     .send(Message::Text(String::from("Username already taken.")))
                     .await;
-                println!("Username already taken.");
+                tracing::debug!("Username already taken.");
                 return;
             }
         }
     }
 
-    // We know if the loop exited `tx` is not `None`.
-    let tx = tx.unwrap();
+    // `tx` is `None` if the client left or sent an invalid connect message.
+    let tx = match tx {
+        Some(tx) => tx,
+        None => return,
+    };
     // Subscribe before sending joined message.
     let mut rx = tx.subscribe();
 
@@ -137,12 +151,14 @@ async fn websocket(
         // This task will receive messages from client and send them to broadcast subscribers.
         tokio::spawn(async move {
             while let Some(Ok(Message::Text(text))) = receiver.next().await {
-                let messagebodywrapped: Result<Value, serde_json::Error> =
-                    serde_json::from_str(&text);
-
-                let messagebody = messagebodywrapped.unwrap();
-
-                println!("messagebody: {:?}", messagebody);
+                // ignore messages that are not json
+                let messagebody: Value = match serde_json::from_str(&text) {
+                    Ok(messagebody) => messagebody,
+                    Err(error) => {
+                        tracing::debug!(%error);
+                        continue;
+                    }
+                };
 
                 // if the message is typing, send the message to all in the room
                 let message = match messagebody.get("istyping") {
@@ -167,9 +183,7 @@ async fn websocket(
                     }
                 };
 
-                println!("{:?}", message);
-
-                println!("{:?}", message);
+                tracing::debug!("{:?}", message);
 
                 // Add userid before message.
                 let _ = tx.send(message.to_string());
@@ -187,70 +201,40 @@ async fn websocket(
     let mut rooms = websocket_extension.rooms.lock().unwrap();
 
     // Remove userid from map so new clients can take it.
-    Mutex::get_mut(&mut rooms.get_mut(&channel).unwrap().users)
-        .unwrap()
-        .remove(&userid.to_string());
+    if let Some(room) = rooms.get_mut(&channel) {
+        Mutex::get_mut(&mut room.users)
+            .unwrap()
+            .remove(&userid.to_string());
+    }
 
     // send left message to all in the room
     let msg = format!("{} left.", userid);
     tracing::debug!("{}", msg);
-    println!("{:?}", msg);
     let _ = tx.send(msg);
 
     // Check if the room is empty now and remove the `RoomState` from the map.
-    if Mutex::get_mut(&mut rooms.get_mut(&channel).unwrap().users)
-        .unwrap()
-        .is_empty()
-    {
-        rooms.remove(&channel);
-        println!("room removed: {:?}", channel);
+    if let Some(room) = rooms.get_mut(&channel) {
+        if Mutex::get_mut(&mut room.users).unwrap().is_empty() {
+            rooms.remove(&channel);
+            tracing::debug!("room removed: {:?}", channel);
+        }
     }
-    println!("rooms: {:?}", rooms);
 }
 
 pub async fn startpersonalchat(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
     Json(people): Json<People>,
-) -> (StatusCode, Json<Value>) {
+) -> Result<Json<Value>, AppError> {
     let chat = PersonalChat::new();
 
-    let create_chat = chat.create_chat_query(claim, db.clone(), Json(people)).await;
-
-    match create_chat {
-        Ok(_) => (),
-        Err(e) => {
-            println!("{:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "message": "Chat could not be created"
-                })),
-            );
-        }
-    }
+    let create_chat = chat
+        .create_chat_query(claim, db.clone(), Json(people))
+        .await?;
 
-    println!("create_chat: {:?}", create_chat);
-
-    let response = db.query(create_chat.unwrap()).await;
-
-    println!("response: {:?}", response);
-
-    match response {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(json!({
-                "message": "Chat created successfully"
-            })),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "message": "Chat could not be created"
-                })),
-            )
-        }
-    }
+    create_chat.execute_transaction(&db).await?;
+
+    Ok(Json(json!({
+        "message": "Chat created successfully"
+    })))
 }
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::header,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::AppError,
+    models::{
+        account::Account,
+        data_export::{DataExport, ExportStatus},
+        user_claim::Claim,
+    },
+};
+
+// response struct for the status of a data export
+#[derive(serde::Serialize)]
+pub struct DataExportResponse {
+    id: String,
+    status: ExportStatus,
+    created_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<DataExport> for DataExportResponse {
+    fn from(export: DataExport) -> Self {
+        Self {
+            id: export.get_id(),
+            status: export.get_status(),
+            created_at: export.get_created_at(),
+            completed_at: export.get_completed_at(),
+            expires_at: export.get_expires_at(),
+        }
+    }
+}
+
+// starts collecting the data of the user, the status is polled until the archive is ready
+pub async fn start_data_export(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<DataExportResponse>, AppError> {
+    // a deleted account has nothing left to export, one waiting for deletion still does
+    Account::of(&db, claim.get_surrealdb_thing()).await?;
+
+    let export = DataExport::start(db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(export.into()))
+}
+
+pub async fn get_data_export(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(export_id): Path<String>,
+) -> Result<Json<DataExportResponse>, AppError> {
+    let export = DataExport::of(&db, claim.get_surrealdb_thing(), export_id).await?;
+
+    Ok(Json(export.into()))
+}
+
+// the archive as a json file
+pub async fn download_data_export(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(export_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let export = DataExport::of(&db, claim.get_surrealdb_thing(), export_id).await?;
+    let archive = export.archive(&db).await?;
+
+    let disposition = format!(
+        "attachment; filename=\"ufora-export-{}.json\"",
+        export.get_id()
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    ))
+}
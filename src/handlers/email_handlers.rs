@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    response::Html,
+    Json,
+};
+use surrealdb::{engine::any::Any, Surreal};
+use validator::Validate;
+
+use crate::{
+    config::Config,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::{email_change::EmailChange, user::User, user_claim::Claim},
+    services::{
+        action_token::{self, ActionPurpose},
+        email::Mailer,
+        otp::{OtpPurpose, OtpService},
+        password,
+    },
+};
+
+// request struct for changing the email address of the signed in user
+#[derive(serde::Deserialize, Validate)]
+pub struct EmailChangeRequest {
+    #[validate(email(message = "New email must be an email address"))]
+    new_email: String,
+    password: String,
+}
+
+// request struct for confirming the new email address with the otp sent to it
+#[derive(serde::Deserialize)]
+pub struct EmailChangeConfirmationRequest {
+    otp: String,
+}
+
+// the token of the link sent to the old email address
+#[derive(serde::Deserialize)]
+pub struct EmailChangeRevertRequest {
+    token: String,
+}
+
+// response struct for the email change actions
+#[derive(serde::Serialize)]
+pub struct EmailChangeResponse {
+    message: String,
+}
+
+// sends an otp to the new address, the current address stays in use until it is confirmed
+pub async fn request_email_change(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(otp_service): State<OtpService>,
+    claim: Claim,
+    Json(request): Json<EmailChangeRequest>,
+) -> Result<Json<EmailChangeResponse>, AppError> {
+    request.validate()?;
+
+    let user = User::get_user_by_id(db.clone(), claim.get_id()).await?;
+
+    let stored_password = user
+        .get_password()
+        .ok_or(AppError::invalid_input("This account has no password"))?;
+
+    // a wrong password counts as a failed login, a stolen token can not guess it
+    if !password::verify_password(request.password, stored_password)? {
+        user.record_failed_login(db.clone()).await?;
+
+        return Err(AuthenticationError::InvalidCredentials {
+            msg: "Password is incorrect".to_string(),
+        }
+        .into());
+    }
+
+    EmailChange::request(&db, user.get_id(), &request.new_email).await?;
+
+    otp_service
+        .send(OtpPurpose::EmailChange, &request.new_email)
+        .await?;
+
+    Ok(Json(EmailChangeResponse {
+        message: "OTP has been sent to the new email address".to_string(),
+    }))
+}
+
+// switches to the new address and tells the old one how to undo the change
+pub async fn confirm_email_change(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Mailer>,
+    State(otp_service): State<OtpService>,
+    claim: Claim,
+    Json(request): Json<EmailChangeConfirmationRequest>,
+) -> Result<Json<EmailChangeResponse>, AppError> {
+    let new_email = EmailChange::pending_email(&db, claim.get_surrealdb_thing()).await?;
+
+    otp_service
+        .verify(OtpPurpose::EmailChange, &new_email, &request.otp)
+        .await?;
+
+    let change = EmailChange::confirm(&db, claim.get_surrealdb_thing(), new_email).await?;
+
+    let token = action_token::issue(
+        &db,
+        &config.security,
+        ActionPurpose::EmailChangeRevert,
+        &change.get_id(),
+        chrono::Duration::days(config.account.email_change_revert_days),
+    )
+    .await?;
+
+    // the change is done, a failed notification does not undo it
+    let notification = mailer
+        .send_email(
+            &format!("Receiver <{}>", change.get_old_email()),
+            "The email address of your Ufora account was changed".to_string(),
+            format!(
+                "The email address of your Ufora account was changed to {}. If you did not do this, open {}/api/account/email/revert?token={} within {} days to change it back.",
+                change.get_new_email(),
+                config.server.public_url.trim_end_matches('/'),
+                token,
+                config.account.email_change_revert_days
+            ),
+        )
+        .await;
+
+    if let Err(e) = notification {
+        tracing::warn!(
+            "could not send the email change notification to the old address of {}: {:?}",
+            change.get_user(),
+            e
+        );
+    }
+
+    Ok(Json(EmailChangeResponse {
+        message: "Email address has been changed".to_string(),
+    }))
+}
+
+// opened from the link sent to the old address, only checks the token
+// mail scanners open links in emails too, so the page asks before anything is changed
+pub async fn show_email_change_revert(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    Query(request): Query<EmailChangeRevertRequest>,
+) -> Result<Html<String>, AppError> {
+    let token = action_token::verify(
+        &config.security,
+        &request.token,
+        ActionPurpose::EmailChangeRevert,
+    )?;
+
+    let change = EmailChange::find(&db, token.get_subject()).await?;
+
+    if change.is_reverted() {
+        return Err(AppError::invalid_input(
+            "This change of the email address has already been undone",
+        ));
+    }
+
+    // the token is signed, so it only holds base64url characters and a dot
+    Ok(Html(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head><meta charset="utf-8"><title>Undo the email change</title></head>
+<body>
+<p>The email address of your Ufora account was changed. Undo the change to get your old address back and log out everywhere.</p>
+<button id="revert">Undo the change</button>
+<p id="result"></p>
+<script>
+document.getElementById("revert").addEventListener("click", async () => {{
+    const response = await fetch(window.location.pathname, {{
+        method: "POST",
+        headers: {{ "Content-Type": "application/json" }},
+        body: JSON.stringify({{ token: "{}" }}),
+    }});
+    const body = await response.json();
+    document.getElementById("result").textContent = body.message || "Something went wrong";
+}});
+</script>
+</body>
+</html>"#,
+        request.token
+    )))
+}
+
+// sent by the page of the link, the account may have been taken over
+// so the user is logged out everywhere as well
+pub async fn revert_email_change(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    Json(request): Json<EmailChangeRevertRequest>,
+) -> Result<Json<EmailChangeResponse>, AppError> {
+    let token = action_token::verify(
+        &config.security,
+        &request.token,
+        ActionPurpose::EmailChangeRevert,
+    )?;
+
+    let change = EmailChange::find(&db, token.get_subject()).await?;
+
+    token.consume(&db).await?;
+    change.revert(&db).await?;
+
+    Ok(Json(EmailChangeResponse {
+        message: format!(
+            "The email address has been changed back to {}, please log in and change your password",
+            change.get_old_email()
+        ),
+    }))
+}
@@ -2,14 +2,25 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     Json,
 };
-use surrealdb::{engine::remote::ws::Client, Surreal};
-use tower_cookies::{Cookie, Cookies};
+use chrono::{DateTime, Utc};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+use tower_cookies::{cookie::time, Cookie, Cookies};
 
 use crate::{
-    models::user::{ClubOfficial, User},
+    config::Config,
+    errors::{
+        authentication_errors::AuthenticationError, authorization_errors::AuthorizationError,
+        AppError,
+    },
+    models::{
+        mfa::Mfa,
+        refresh_token::RefreshToken,
+        session::{Device, Session},
+        user::User,
+        user_type::UserType,
+    },
     services::jwt,
 };
 
@@ -24,177 +35,313 @@ pub struct LoginRequest {
 // response enumeration for login
 #[derive(serde::Serialize)]
 pub enum LoginResponse {
-    Success { message: String, token: String },
-    InvalidLogin { message: String },
-    InternalServerError,
+    Success {
+        message: String,
+        token: String,
+        refresh_token: String,
+    },
+    // the password was right, the code from the authenticator app goes to /api/login/mfa
+    MfaRequired {
+        message: String,
+        mfa_token: String,
+    },
+}
+
+// request struct for the second step of a login with two-factor authentication
+#[derive(serde::Deserialize)]
+pub struct MfaLoginRequest {
+    pub mfa_token: String,
+    // code from the authenticator app or a recovery code
+    pub code: String,
+}
+
+// error returned for an unknown user or a wrong password
+fn invalid_login() -> AppError {
+    AuthenticationError::InvalidCredentials {
+        msg: "Invalid Login Credentials".to_string(),
+    }
+    .into()
+}
+
+// error returned while an account is locked after too many failed logins
+fn account_locked(locked_until: DateTime<Utc>) -> AppError {
+    AuthenticationError::AccountLocked {
+        msg: "Too many failed login attempts. Try again later or unlock the account with an OTP sent to your email".to_string(),
+        locked_until,
+    }
+    .into()
+}
+
+// error returned while an administrator has locked the account
+fn locked_by_admin() -> AppError {
+    AuthenticationError::InactiveUser {
+        msg: "This account has been locked by an administrator".to_string(),
+    }
+    .into()
 }
 
 // login handler
 pub async fn login_via_platform(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
     cookies: Cookies,
+    device: Device,
     Json(login_request): Json<LoginRequest>,
-) -> (StatusCode, Json<LoginResponse>) {
-    match login_request.password.clone() {
-        None => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(LoginResponse::InvalidLogin {
-                    message: "Invalid Login Credentials".to_string(),
-                }),
-            );
-        }
-        Some(_) => {}
-    }
+) -> Result<Json<LoginResponse>, AppError> {
+    let password =
+        login_request
+            .password
+            .clone()
+            .ok_or(AuthenticationError::MissingCredentials {
+                msg: "Password is required".to_string(),
+            })?;
 
     // retrieve user from database
-    let user = User::get_user_by_email_or_username(
+    let user = match User::get_user_by_email_or_username(
         db.clone(),
         login_request.email.clone(),
         login_request.username.clone(),
     )
-    .await;
-
-    match user {
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(LoginResponse::InvalidLogin {
-                    message: "Invalid Login Credentials".to_string(),
-                }),
-            );
-        }
-        Ok(_) => {}
+    .await
+    {
+        Ok(user) => user,
+        Err(AppError::NotFound { .. }) => return Err(invalid_login()),
+        Err(e) => return Err(e),
+    };
+
+    // the password is not checked while the account is locked
+    if user.is_locked_by_admin() {
+        return Err(locked_by_admin());
     }
 
-    let user = user.unwrap();
-
-    // check if user is locked
-    match user.is_user_locked() {
-        true => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(LoginResponse::InvalidLogin {
-                    message: "User Account is Locked. Please verify email to continue".to_string(),
-                }),
-            );
-        }
-        false => {}
+    if let Some(locked_until) = user.get_locked_until() {
+        return Err(account_locked(locked_until));
     }
 
+    // accounts without a password (e.g. clubs) can not log in directly
+    let stored_password = user.get_password().ok_or_else(invalid_login)?;
+
     // check whether password is correct
-    match crate::services::password::verify_password(
-        login_request.password.unwrap(),
-        user.get_password().unwrap(),
-    ) {
-        Ok(false) => {
-            // update invalid login attempts
-            let new_invalid_login_attempts = user.invalid_login_attempts.unwrap() + 1;
-            user.update_login_attempts(db.clone(), new_invalid_login_attempts)
-                .await;
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(LoginResponse::InvalidLogin {
-                    message: "Invalid Login Credentials".to_string(),
-                }),
-            );
-        }
-        Ok(true) => {}
-        Err(_) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(LoginResponse::InternalServerError),
-            );
+    if !crate::services::password::verify_password(password, stored_password)? {
+        return match user.record_failed_login(db.clone()).await? {
+            Some(locked_until) => Err(account_locked(locked_until)),
+            None => Err(invalid_login()),
+        };
+    }
+
+    // with two-factor authentication the tokens are only issued at /api/login/mfa
+    if Mfa::of(&db, user.get_id()).await?.is_enabled() {
+        return mfa_required(&db, &config, user.get_id().id.to_raw(), None).await;
+    }
+
+    User::reset_login_attempts(db.clone(), user.get_id()).await?;
+
+    issue_tokens(&db, &config, &cookies, device, &user).await
+}
+
+// second step of a login with two-factor authentication
+pub async fn login_with_mfa(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    cookies: Cookies,
+    device: Device,
+    Json(mfa_login_request): Json<MfaLoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let pending = jwt::verify_mfa_pending_jwt(&config.jwt, &mfa_login_request.mfa_token)?;
+
+    let user = match User::get_user_by_id(db.clone(), pending.user_id.clone()).await {
+        Ok(user) => user,
+        Err(AppError::NotFound { .. }) => return Err(invalid_login()),
+        Err(e) => return Err(e),
+    };
+
+    if user.is_locked_by_admin() {
+        return Err(locked_by_admin());
+    }
+
+    if let Some(locked_until) = user.get_locked_until() {
+        return Err(account_locked(locked_until));
+    }
+
+    pending.ensure_unused(&db).await?;
+
+    // wrong codes count as failed logins, so they can not be guessed
+    if !Mfa::verify(&db, user.get_id(), &mfa_login_request.code).await? {
+        return match user.record_failed_login(db.clone()).await? {
+            Some(locked_until) => Err(account_locked(locked_until)),
+            None => Err(AuthenticationError::InvalidCredentials {
+                msg: "Invalid two-factor authentication code".to_string(),
+            }
+            .into()),
+        };
+    }
+
+    // a pending token is exchanged for tokens once, even when the code is still valid
+    pending.consume(&db).await?;
+
+    User::reset_login_attempts(db.clone(), user.get_id()).await?;
+
+    match pending.club_id {
+        Some(club_id) => {
+            let (club, role) = get_club_and_role(&db, club_id, &user.get_id()).await?;
+            issue_club_tokens(&db, &config, device, user.get_id(), &club, role).await
         }
+        None => issue_tokens(&db, &config, &cookies, device, &user).await,
     }
+}
+
+// answer to a correct password when the second factor is still missing
+async fn mfa_required(
+    db: &Surreal<Any>,
+    config: &Config,
+    user_id: String,
+    club_id: Option<String>,
+) -> Result<Json<LoginResponse>, AppError> {
+    Ok(Json(LoginResponse::MfaRequired {
+        message: "Enter the code from your authenticator app".to_string(),
+        mfa_token: jwt::get_mfa_pending_jwt(db, &config.jwt, user_id, club_id).await?,
+    }))
+}
+
+// starts a session for the user and issues its access and refresh tokens
+async fn issue_tokens(
+    db: &Surreal<Any>,
+    config: &Config,
+    cookies: &Cookies,
+    device: Device,
+    user: &User,
+) -> Result<Json<LoginResponse>, AppError> {
+    let session_id = Session::start(db, &config.jwt, user.get_id(), None, device).await?;
 
     // create jwt token
-    let token = jwt::get_jwt(user.get_id().id.to_string(), user.get_user_type())
-        .await
-        .unwrap();
+    let token = jwt::get_jwt(
+        &config.jwt,
+        user.get_id().id.to_raw(),
+        user.get_user_type()
+            .ok_or(AppError::internal("User account has no user type"))?,
+        session_id.clone(),
+    )
+    .await?;
+
+    let refresh_token =
+        RefreshToken::issue(db, &config.jwt, user.get_id(), None, session_id).await?;
 
-    user.update_login_attempts(db.clone(), 0).await;
+    set_token_cookies(cookies, config, &token, &refresh_token);
 
-    // create cookie with flags
-    let cookie = Cookie::build("_Secure-jwt", token.clone())
+    Ok(Json(LoginResponse::Success {
+        message: "Login Successful".to_string(),
+        token,
+        refresh_token,
+    }))
+}
+
+// the access token is sent with every request, the refresh token only to /api/token
+pub fn set_token_cookies(cookies: &Cookies, config: &Config, token: &str, refresh_token: &str) {
+    let cookie = Cookie::build("_Secure-jwt", token.to_string())
         .domain("localhost")
         .path("/")
+        .max_age(time::Duration::seconds(config.jwt.lifetime_seconds))
         .secure(true)
         .http_only(true)
         .finish();
-
-    // set cookie
     cookies.add(cookie);
 
-    (
-        StatusCode::OK,
-        Json(LoginResponse::Success {
-            message: "Login Successful".to_string(),
-            token,
-        }),
-    )
+    let cookie = Cookie::build("_Secure-refresh", refresh_token.to_string())
+        .domain("localhost")
+        .path("/api/token")
+        .max_age(time::Duration::seconds(config.jwt.refresh_lifetime_seconds))
+        .secure(true)
+        .http_only(true)
+        .finish();
+    cookies.add(cookie);
 }
 
 pub async fn club_login(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
     user: crate::models::user_claim::Claim,
+    device: Device,
     Path(club_id): Path<String>,
-) -> (StatusCode, Json<LoginResponse>) {
-    if let Ok(club) = User::get_user_by_id(db.clone(), club_id.clone()).await {
-        if club.get_user_type() == "club" {
-            if let Some(club_officials) = club.get_club_officials() {
-                let user_id = user.get_surrealdb_thing();
-                let mut club_official_info: Option<ClubOfficial> = None;
-                for club_official in club_officials {
-                    if club_official.get_user_id() == user_id {
-                        club_official_info = Some(club_official);
-                        break;
-                    }
-                }
-                if let Some(club_official_info) = club_official_info {
-                    match jwt::get_club_jwt(club_id, club_official_info.get_role()) {
-                        Ok(token) => (
-                            StatusCode::OK,
-                            Json(LoginResponse::Success {
-                                message: "Login Successful".to_string(),
-                                token,
-                            }),
-                        ),
-                        Err(_) => (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(LoginResponse::InternalServerError),
-                        ),
-                    }
-                } else {
-                    (
-                        StatusCode::UNAUTHORIZED,
-                        Json(LoginResponse::InvalidLogin {
-                            message: "You do not have access to this club account".to_string(),
-                        }),
-                    )
-                }
-            } else {
-                (
-                    StatusCode::UNAUTHORIZED,
-                    Json(LoginResponse::InvalidLogin {
-                        message: "Invalid Login Credentials".to_string(),
-                    }),
-                )
-            }
-        } else {
-            (
-                StatusCode::UNAUTHORIZED,
-                Json(LoginResponse::InvalidLogin {
-                    message: "You do not have access to this club account".to_string(),
-                }),
-            )
+) -> Result<Json<LoginResponse>, AppError> {
+    let (club, role) = get_club_and_role(&db, club_id.clone(), &user.get_surrealdb_thing()).await?;
+
+    // officials with two-factor authentication confirm a club login with a code
+    if Mfa::of(&db, user.get_surrealdb_thing()).await?.is_enabled() {
+        return mfa_required(&db, &config, user.get_id(), Some(club_id)).await;
+    }
+
+    issue_club_tokens(
+        &db,
+        &config,
+        device,
+        user.get_surrealdb_thing(),
+        &club,
+        role,
+    )
+    .await
+}
+
+// the club account and the role the official holds in it
+async fn get_club_and_role(
+    db: &Arc<Surreal<Any>>,
+    club_id: String,
+    official: &Thing,
+) -> Result<(User, String), AppError> {
+    let no_access = || -> AppError {
+        AuthorizationError::AccessDeniedError {
+            msg: "You do not have access to this club account".to_string(),
         }
-    } else {
-        (
-            StatusCode::BAD_REQUEST,
-            Json(LoginResponse::InvalidLogin {
-                message: "Invalid Login Credentials".to_string(),
-            }),
-        )
+        .into()
+    };
+
+    let club = match User::get_user_by_id(db.clone(), club_id).await {
+        Ok(club) => club,
+        Err(AppError::NotFound { .. }) => return Err(invalid_login()),
+        Err(e) => return Err(e),
+    };
+
+    if club.get_user_type() != Some(UserType::Club) {
+        return Err(no_access());
     }
+
+    let role = club.get_club_role(official).ok_or_else(no_access)?;
+
+    Ok((club, role))
+}
+
+// starts a session for the official and issues the club token
+async fn issue_club_tokens(
+    db: &Surreal<Any>,
+    config: &Config,
+    device: Device,
+    official: Thing,
+    club: &User,
+    role: String,
+) -> Result<Json<LoginResponse>, AppError> {
+    // the session belongs to the official, the club is checked again on every refresh
+    let session_id = Session::start(
+        db,
+        &config.jwt,
+        official.clone(),
+        Some(club.get_id()),
+        device,
+    )
+    .await?;
+
+    let token = jwt::get_club_jwt(
+        &config.jwt,
+        club.get_id().id.to_raw(),
+        role,
+        official.id.to_raw(),
+        session_id.clone(),
+    )?;
+
+    let refresh_token =
+        RefreshToken::issue(db, &config.jwt, official, Some(club.get_id()), session_id).await?;
+
+    Ok(Json(LoginResponse::Success {
+        message: "Login Successful".to_string(),
+        token,
+        refresh_token,
+    }))
 }
@@ -1,19 +1,62 @@
-use axum::{ http::{ StatusCode, Response, header }, response::IntoResponse };
+use std::sync::Arc;
+
+use axum::{ extract::State, http::{ Response, header }, response::IntoResponse };
 use serde_json::json;
+use surrealdb::{ engine::any::Any, Surreal };
 use tower_cookies::{ Cookie, cookie::time };
 
-pub async fn logout() -> Result<impl IntoResponse, StatusCode> {
-    // remove secure jwt cookie, by setting max age to -1
+use crate::{
+    errors::AppError,
+    models::{ revoked_token::RevokedToken, session::Session, user_claim::Claim },
+};
+
+// logs out the token used for the request and ends its session
+pub async fn logout(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim
+) -> Result<impl IntoResponse, AppError> {
+    RevokedToken::revoke(&db, claim.get_jti(), claim.get_expires_at()).await?;
+
+    // the refresh tokens of this login can not be used anymore
+    Session::end(&db, claim.get_surrealdb_thing(), claim.get_session_id()).await?;
+
+    logged_out("Logout successful")
+}
+
+// logs out every token issued to the user until now, on every device
+pub async fn logout_everywhere(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim
+) -> Result<impl IntoResponse, AppError> {
+    RevokedToken::revoke_all_issued_to(&db, claim.get_surrealdb_thing()).await?;
+    Session::end_all(&db, claim.get_surrealdb_thing()).await?;
+
+    logged_out("Logged out on all devices")
+}
+
+fn logged_out(message: &str) -> Result<Response<String>, AppError> {
+    // remove secure jwt and refresh cookies, by setting max age to -1
     let cookie = Cookie::build("_Secure-jwt", "").max_age(time::Duration::days(-1)).finish();
+    let refresh_cookie = Cookie::build("_Secure-refresh", "")
+        .path("/api/token")
+        .max_age(time::Duration::days(-1))
+        .finish();
 
     let mut response = Response::new(
         json!({
-            "message": "Logout successful",
+            "message": message,
         }).to_string()
     );
 
-    // set cookie to expire
-    response.headers_mut().insert(header::SET_COOKIE, cookie.to_string().parse().unwrap());
+    // set cookies to expire
+    for cookie in [cookie, refresh_cookie] {
+        let cookie = cookie
+            .to_string()
+            .parse()
+            .map_err(|_| AppError::internal("logout cookie could not be created"))?;
+
+        response.headers_mut().append(header::SET_COOKIE, cookie);
+    }
 
     Ok(response)
 }
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::{mfa::Mfa, user::User, user_claim::Claim},
+};
+
+// response struct for starting the enrollment
+#[derive(serde::Serialize)]
+pub struct EnrollmentResponse {
+    // entered by hand when the qr code can not be scanned
+    secret: String,
+    // shown as a qr code
+    otpauth_uri: String,
+}
+
+// request struct carrying a code from the authenticator app
+#[derive(serde::Deserialize)]
+pub struct MfaCodeRequest {
+    code: String,
+}
+
+// response struct for confirming the enrollment
+#[derive(serde::Serialize)]
+pub struct MfaConfirmationResponse {
+    message: String,
+    // single use codes for when the authenticator app is lost, they are not shown again
+    recovery_codes: Vec<String>,
+}
+
+#[derive(serde::Serialize)]
+pub enum MfaRouteResponse {
+    Success { message: String },
+}
+
+// starts the enrollment with a new secret for the authenticator app
+pub async fn enroll(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<EnrollmentResponse>, AppError> {
+    let user = User::get_user_by_id(db.clone(), claim.get_id()).await?;
+
+    let enrollment =
+        Mfa::begin_enrollment(&db, claim.get_surrealdb_thing(), &user.get_user_email()).await?;
+
+    Ok(Json(EnrollmentResponse {
+        secret: enrollment.secret,
+        otpauth_uri: enrollment.otpauth_uri,
+    }))
+}
+
+// enables two-factor authentication once the first code from the app is right
+pub async fn confirm(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Json(mfa_code_request): Json<MfaCodeRequest>,
+) -> Result<Json<MfaConfirmationResponse>, AppError> {
+    let recovery_codes =
+        Mfa::confirm_enrollment(&db, claim.get_surrealdb_thing(), &mfa_code_request.code).await?;
+
+    Ok(Json(MfaConfirmationResponse {
+        message: "Two-factor authentication has been enabled".to_string(),
+        recovery_codes,
+    }))
+}
+
+// turns two-factor authentication off, a current code or a recovery code is required
+pub async fn disable(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Json(mfa_code_request): Json<MfaCodeRequest>,
+) -> Result<Json<MfaRouteResponse>, AppError> {
+    if !Mfa::of(&db, claim.get_surrealdb_thing())
+        .await?
+        .is_enabled()
+    {
+        return Err(AppError::invalid_input(
+            "Two-factor authentication is not enabled",
+        ));
+    }
+
+    if !Mfa::verify(&db, claim.get_surrealdb_thing(), &mfa_code_request.code).await? {
+        return Err(AuthenticationError::InvalidCredentials {
+            msg: "Invalid two-factor authentication code".to_string(),
+        }
+        .into());
+    }
+
+    Mfa::disable(&db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(MfaRouteResponse::Success {
+        message: "Two-factor authentication has been disabled".to_string(),
+    }))
+}
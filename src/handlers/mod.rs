@@ -1,6 +1,15 @@
 // handlers realted to login
 pub mod login_handlers;
 
+// handlers related to tokens -> refresh
+pub mod token_handlers;
+
+// handlers related to two-factor authentication -> enroll, confirm, disable
+pub mod mfa_handlers;
+
+// handlers related to sessions -> list, end
+pub mod session_handlers;
+
 // handlers related to logout
 pub mod logout_handlers;
 
@@ -10,9 +19,27 @@ pub mod registration_handlers;
 // handlers related to verification -> otp, email verification
 pub mod verification_handlers;
 
+// handlers related to locked accounts -> unlock otp, unlock
+pub mod unlock_handlers;
+
 // handlers related to forgot password -> email verification and reset password
 pub mod forgot_password_handlers;
 
+// handlers related to the password of a signed in user -> change password
+pub mod password_handlers;
+
+// handlers related to the account of a signed in user -> deactivate, reactivate, delete
+pub mod account_handlers;
+
+// handlers related to exporting the data of a signed in user -> start, status, download
+pub mod data_export_handlers;
+
+// handlers related to the email address of a signed in user -> change, confirm, revert
+pub mod email_handlers;
+
+// handlers related to api keys of integrations -> create, list, revoke
+pub mod api_key_handlers;
+
 // handlers related to test
 pub mod test_handlers;
 
@@ -33,3 +60,6 @@ pub mod club_handlers;
 
 // handlers related to chat -> websocket connections
 pub mod chat;
+
+// handlers related to administration -> users, club verification, moderation, stats
+pub mod admin_handlers;
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    config::Config,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::{session::Session, user::User, user_claim::Claim},
+    services::{email::Mailer, password},
+};
+
+// request struct for changing the password of a signed in user
+#[derive(serde::Deserialize)]
+pub struct ChangePasswordRequest {
+    current_password: String,
+    new_password: String,
+    confirm_password: String,
+}
+
+// response struct for changing the password
+#[derive(serde::Serialize)]
+pub struct ChangePasswordResponse {
+    message: String,
+}
+
+// changes the password of the signed in user, the user stays signed in on this device only
+pub async fn change_password(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    State(mailer): State<Mailer>,
+    claim: Claim,
+    Json(request): Json<ChangePasswordRequest>,
+) -> Result<Json<ChangePasswordResponse>, AppError> {
+    let user = User::get_user_by_id(db.clone(), claim.get_id()).await?;
+
+    // accounts without a password (e.g. clubs) can not change it
+    let stored_password = user
+        .get_password()
+        .ok_or(AppError::invalid_input("This account has no password"))?;
+
+    // a wrong current password counts as a failed login, a stolen token can not guess it
+    if !password::verify_password(request.current_password.clone(), stored_password)? {
+        user.record_failed_login(db.clone()).await?;
+
+        return Err(AuthenticationError::InvalidCredentials {
+            msg: "Current password is incorrect".to_string(),
+        }
+        .into());
+    }
+
+    if request.new_password != request.confirm_password {
+        return Err(AppError::invalid_input("Passwords do not match"));
+    }
+
+    if request.new_password == request.current_password {
+        return Err(AppError::invalid_input(
+            "New password must be different from the current password",
+        ));
+    }
+
+    password::check_policy(
+        &config.password,
+        &request.new_password,
+        &[
+            user.get_user_username().as_str(),
+            user.get_user_email().as_str(),
+        ],
+    )?;
+
+    User::set_password(db.clone(), user.get_id(), request.new_password).await?;
+
+    // every other device has to log in again with the new password
+    Session::end_all_except(&db, user.get_id(), claim.get_session_id()).await?;
+
+    // the password is changed either way, a failed notification is only logged
+    let notification = mailer
+        .send_email(
+            &format!("{} <{}>", user.get_user_username(), user.get_user_email()),
+            "Your Ufora password was changed".to_string(),
+            format!(
+                "The password of your Ufora account {} was changed and you were logged out on your other devices. If you did not change it, reset your password right away.",
+                user.get_user_username()
+            ),
+        )
+        .await;
+
+    if let Err(e) = notification {
+        tracing::warn!(
+            "could not send the password change notification to {}: {:?}",
+            user.get_id(),
+            e
+        );
+    }
+
+    Ok(Json(ChangePasswordResponse {
+        message: "Password has been changed".to_string(),
+    }))
+}
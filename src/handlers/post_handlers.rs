@@ -2,18 +2,15 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     Json,
 };
-use surrealdb::{
-    engine::remote::ws::Client,
-    sql::{Id, Thing},
-    Surreal,
-};
+use surrealdb::{engine::any::Any, Surreal};
 
 use chrono::prelude::*;
 
+use crate::errors::AppError;
 use crate::models::post::{AccessLevel, Post, Comment};
+use crate::services::policy::Policy;
 
 #[derive(serde::Deserialize, Debug)]
 pub struct CreatePostRequest {
@@ -28,99 +25,56 @@ pub struct CreatePostResponse {
 }
 
 pub async fn create_post(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
     Json(request): Json<CreatePostRequest>,
-) -> (StatusCode, Json<CreatePostResponse>) {
+) -> Result<Json<CreatePostResponse>, AppError> {
     let post = Post::new(request.caption, request.access_level, request.content);
 
-    match post.save(db, claim.get_surrealdb_thing()).await {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(CreatePostResponse {
-                message: "Post created successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(CreatePostResponse { message: e }),
-            )
-        }
-    }
+    post.save(db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(CreatePostResponse {
+        message: "Post created successfully".to_string(),
+    }))
 }
 
 pub async fn get_posts_for_profile(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     claim: crate::models::user_claim::Claim,
-) -> (StatusCode, Json<Vec<Post>>) {
+) -> Result<Json<Vec<Post>>, AppError> {
     let logged_user = claim.get_surrealdb_thing();
 
-    let posts = Post::get_post_by_user_id(db, logged_user).await;
+    let posts = Post::get_post_by_user_id(db, logged_user).await?;
 
-    match posts {
-        Ok(posts) => (StatusCode::OK, Json(posts)),
-        Err(e) => {
-            println!("{:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(vec![]))
-        }
-    }
+    Ok(Json(posts))
 }
 
 pub async fn delete_post_by_id(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     Path(post_id): Path<String>,
-) -> (StatusCode, Json<CreatePostResponse>) {
-    let post = Post::delete_post_by_id(db, post_id).await;
-
-    match post {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(CreatePostResponse {
-                message: "Post deleted successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(CreatePostResponse { message: e }),
-            )
-        }
-    }
+    claim: crate::models::user_claim::Claim,
+) -> Result<Json<CreatePostResponse>, AppError> {
+    let post_id = Policy::new(&db, &claim).can_modify::<Post>(post_id).await?;
+
+    Post::delete_post_by_id(db, post_id).await?;
+
+    Ok(Json(CreatePostResponse {
+        message: "Post deleted successfully".to_string(),
+    }))
 }
 
 pub async fn add_or_remove_reaction_to_a_post(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     Path(post_id): Path<String>,
     claim: crate::models::user_claim::Claim,
-) -> (StatusCode, Json<CreatePostResponse>) {
-    let post = Post::add_or_remove_reaction(
-        db,
-        Thing {
-            tb: "post".to_string(),
-            id: Id::String(post_id),
-        },
-        claim.get_surrealdb_thing(),
-    )
-    .await;
-
-    match post {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(CreatePostResponse {
-                message: "Reaction added successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(CreatePostResponse { message: e }),
-            )
-        }
-    }
+) -> Result<Json<CreatePostResponse>, AppError> {
+    let post_id = Policy::new(&db, &claim).can_view_post(post_id).await?;
+
+    Post::add_or_remove_reaction(db, post_id, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(CreatePostResponse {
+        message: "Reaction added successfully".to_string(),
+    }))
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -130,11 +84,12 @@ pub struct AddACommentRequest {
 }
 
 pub async fn add_a_comment(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     Path(post_id): Path<String>,
     claim: crate::models::user_claim::Claim,
     Json(comment_request): Json<AddACommentRequest>,
-) -> (StatusCode, Json<CreatePostResponse>) {
+) -> Result<Json<CreatePostResponse>, AppError> {
+    let post_id = Policy::new(&db, &claim).can_view_post(post_id).await?;
 
     let comment = Comment {
         id: uuid::Uuid::new_v4().to_string(),
@@ -144,30 +99,25 @@ pub async fn add_a_comment(
         time: Utc.from_local_datetime(&chrono::Local::now().naive_local()).single().unwrap().timestamp().to_string(),
     };
 
+    Post::add_a_comment(db, post_id, comment).await?;
+
+    Ok(Json(CreatePostResponse {
+        message: "Comment added successfully".to_string(),
+    }))
+}
+
+pub async fn delete_a_comment(
+    State(db): State<Arc<Surreal<Any>>>,
+    Path((post_id, comment_id)): Path<(String, String)>,
+    claim: crate::models::user_claim::Claim,
+) -> Result<Json<CreatePostResponse>, AppError> {
+    let post_id = Policy::new(&db, &claim)
+        .can_remove_comment(post_id, &comment_id)
+        .await?;
+
+    Post::delete_a_comment(db, post_id, comment_id).await?;
 
-    let post = Post::add_a_comment(
-        db,
-        Thing {
-            tb: "post".to_string(),
-            id: Id::String(post_id),
-        },
-        comment,
-    )
-    .await;
-
-    match post {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(CreatePostResponse {
-                message: "Comment added successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(CreatePostResponse { message: e }),
-            )
-        }
-    }
+    Ok(Json(CreatePostResponse {
+        message: "Comment deleted successfully".to_string(),
+    }))
 }
@@ -2,13 +2,17 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
     Json,
 };
 
-use surrealdb::{engine::remote::ws::Client, Surreal};
+use surrealdb::{engine::any::Any, Surreal};
 
-use crate::models::user::User;
+use crate::{
+    config::Config,
+    errors::{authorization_errors::AuthorizationError, database_errors::DatabaseError, AppError},
+    models::{user::User, user_type::UserType},
+    services::password,
+};
 
 // request struct for registration of an user
 #[derive(serde::Deserialize)]
@@ -33,15 +37,36 @@ pub struct UserRegistrationResponse {
 
 // handler for registration of an user
 pub async fn register_a_user(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
     Path(user_type): Path<String>,
     Json(user_details): Json<UserRegistrationRequest>,
-) -> (StatusCode, Json<UserRegistrationResponse>) {
-    
+) -> Result<Json<UserRegistrationResponse>, AppError> {
+    let user_type: UserType = user_type.parse()?;
+
+    // other account types are created by existing users
+    if !user_type.is_self_registrable() {
+        return Err(AuthorizationError::InsufficientPermissionsError {
+            msg: format!("{} accounts can not be registered directly", user_type),
+        }
+        .into());
+    }
+
+    // a missing password is reported when the user is created
+    if let Some(new_password) = &user_details.password {
+        password::check_policy(
+            &config.password,
+            new_password,
+            &[
+                user_details.username.as_deref().unwrap_or_default(),
+                user_details.email.as_deref().unwrap_or_default(),
+            ],
+        )?;
+    }
+
     // get user and user models
     let user = user_details.get_user_and_user_models();
 
-
     let available_user = User::get_user_by_email_or_username(
         db.clone(),
         Some(user.get_user_email()),
@@ -49,63 +74,33 @@ pub async fn register_a_user(
     )
     .await;
 
-
-
     match available_user {
         Ok(usr) if user.get_user_email() == usr.get_user_email() => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(UserRegistrationResponse {
-                    message: "Email already exists".to_string(),
-                }),
-            );
+            return Err(DatabaseError::DuplicateEntryError {
+                msg: "Email already exists".to_string(),
+            }
+            .into());
         }
         Ok(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(UserRegistrationResponse {
-                    message: "Username already exists".to_string(),
-                }),
-            );
-        }
-        Err(StatusCode::NOT_FOUND) => {}
-        Err(e) => {
-            println!("{:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(UserRegistrationResponse {
-                    message: "User account could not be created".to_string(),
-                }),
-            );
+            return Err(DatabaseError::DuplicateEntryError {
+                msg: "Username already exists".to_string(),
+            }
+            .into());
         }
+        Err(AppError::NotFound { .. }) => {}
+        Err(e) => return Err(e),
     }
 
     // get query for creating an user user and execute it
-    let response = db
-        .query(
-            user.get_create_user_query(user_type.to_string())
-                .await
-                .unwrap(),
-        )
-        .await;
-
-    match response {
-        Ok(_) => (
-            StatusCode::OK,
-            Json(UserRegistrationResponse {
-                message: "User account has been created successfully".to_string(),
-            }),
-        ),
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(UserRegistrationResponse {
-                    message: "User account could not be created".to_string(),
-                }),
-            )
-        }
-    }
+    user.get_create_user_query(user_type)
+        .await?
+        .execute(&db)
+        .await?
+        .check()?;
+
+    Ok(Json(UserRegistrationResponse {
+        message: "User account has been created successfully".to_string(),
+    }))
 }
 
 // request struct for adding university details
@@ -120,25 +115,19 @@ pub struct UniversityDetailsRequest {
 #[derive(serde::Serialize)]
 pub enum UpdateUniversityDetailsResponse {
     Successfull { message: String },
-    Unsuccessfull { message: String },
 }
 
 // handler for adding university details
 pub async fn add_university_details(
-    State(db): State<Arc<Surreal<Client>>>,
+    State(db): State<Arc<Surreal<Any>>>,
     Json(university_details): Json<UniversityDetailsRequest>,
-) -> (StatusCode, Json<UpdateUniversityDetailsResponse>) {
+) -> Result<Json<UpdateUniversityDetailsResponse>, AppError> {
     // check whether username, university and university email are present or not
     if university_details.username.is_none()
         || university_details.university.is_none()
         || university_details.university_email.is_none()
     {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(UpdateUniversityDetailsResponse::Unsuccessfull {
-                message: "Invalid request".to_string(),
-            }),
-        );
+        return Err(AppError::invalid_input("Invalid request"));
     }
 
     // retrieve user from database
@@ -146,59 +135,18 @@ pub async fn add_university_details(
         db.clone(),
         university_details.username.unwrap(),
     )
-    .await;
+    .await?;
 
-    match user {
-        // update university details if user is found
-        Ok(user) => {
-            // update university details
-            let response = User::update_university_details(
-                user.get_id(),
-                db.clone(),
-                university_details.university,
-                university_details.university_email,
-            )
-            .await;
-
-            match response {
-                // return success if university details are updated successfully
-                Ok(_) => (
-                    StatusCode::OK,
-                    Json(UpdateUniversityDetailsResponse::Successfull {
-                        message: "University details have been added successfully".to_string(),
-                    }),
-                ),
-
-                // return bad request if university details are not updated successfully, invalid inputs given
-                Err(StatusCode::BAD_REQUEST) => (
-                    StatusCode::BAD_REQUEST,
-                    Json(UpdateUniversityDetailsResponse::Unsuccessfull {
-                        message: "Invalid request".to_string(),
-                    }),
-                ),
-
-                // return internal server error if university details are not updated successfully, database error
-                Err(e) => {
-                    println!("{:?}", e);
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        Json(UpdateUniversityDetailsResponse::Unsuccessfull {
-                            message: "University details could not be added".to_string(),
-                        }),
-                    )
-                }
-            }
-        }
+    // update university details
+    User::update_university_details(
+        user.get_id(),
+        db.clone(),
+        university_details.university,
+        university_details.university_email,
+    )
+    .await?;
 
-        // return not found if user is not found
-        Err(e) => {
-            println!("{:?}", e);
-            (
-                StatusCode::NOT_FOUND,
-                Json(UpdateUniversityDetailsResponse::Unsuccessfull {
-                    message: "User could not be found".to_string(),
-                }),
-            )
-        }
-    }
+    Ok(Json(UpdateUniversityDetailsResponse::Successfull {
+        message: "University details have been added successfully".to_string(),
+    }))
 }
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::AppError,
+    models::{session::Session, user_claim::Claim},
+};
+
+#[derive(serde::Serialize)]
+pub struct SessionResponse {
+    id: String,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    // club account the session was started for
+    club: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    // whether this is the session of the token used for the request
+    current: bool,
+}
+
+#[derive(serde::Serialize)]
+pub enum SessionRouteResponse {
+    Success { message: String },
+}
+
+// sessions of the user on every device
+pub async fn get_sessions(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+) -> Result<Json<Vec<SessionResponse>>, AppError> {
+    let sessions = Session::list_for(&db, claim.get_surrealdb_thing()).await?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| SessionResponse {
+                current: session.get_id() == claim.get_session_id(),
+                id: session.get_id(),
+                user_agent: session.get_user_agent(),
+                ip: session.get_ip(),
+                club: session.get_club(),
+                created_at: session.get_created_at().0,
+                last_seen: session.get_last_seen().0,
+            })
+            .collect(),
+    ))
+}
+
+// ends one of the sessions of the user, its tokens stop working right away
+pub async fn end_session(
+    State(db): State<Arc<Surreal<Any>>>,
+    claim: Claim,
+    Path(session_id): Path<String>,
+) -> Result<Json<SessionRouteResponse>, AppError> {
+    Session::end(&db, claim.get_surrealdb_thing(), session_id).await?;
+
+    Ok(Json(SessionRouteResponse::Success {
+        message: "Session has been ended".to_string(),
+    }))
+}
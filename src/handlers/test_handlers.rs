@@ -2,11 +2,12 @@ use std::sync::Arc;
 
 use axum::{
     extract::State,
-    http::StatusCode,
     Json,
 };
 use serde::{Deserialize, Serialize};
-use surrealdb::{engine::remote::ws::Client, Surreal, sql::Thing};
+use surrealdb::{engine::any::Any, Surreal, sql::Thing};
+
+use crate::errors::AppError;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Name {
@@ -31,41 +32,15 @@ pub struct TestResponse {
 }
 
 pub async fn test_route(
-    State(db): State<Arc<Surreal<Client>>>,
-    // request: Request<Body>,
-) -> (StatusCode, Json<TestResponse>) {
-    // let claim = request
-    //     .extensions()
-    //     .get::<crate::models::user_claim::Claim>()
-    //     .unwrap();
-
-    let created: Result<Vec<Record>, surrealdb::Error> = db
+    State(db): State<Arc<Surreal<Any>>>,
+) -> Result<Json<TestResponse>, AppError> {
+    let _created: Vec<Record> = db
         .create("person")
         .content(Person {
             title: "Founder & CEO",
             name: "henry",
         })
-        .await;
-
-    // dbg!(created);
-
-    //    ok json success
-    // match created {
-    //     Ok(_) => Ok(Json(Success { success: "success" })),
-    //     Err(e) => {
-    //         println!("{:?}", e);
-    //         Err(StatusCode::INTERNAL_SERVER_ERROR)
-    //     }
-    // }
-
-    match created {
-        Ok(_) => (StatusCode::OK, Json(TestResponse { success: "Successfully inserted" })),
-        Err(e) => {
-            println!("{:?}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(TestResponse { success: "Failed to insert" }))
-        }        
-    }
-
-    // (StatusCode::OK, Json(TestResponse { success: "Successfully inserted" }))
+        .await?;
 
+    Ok(Json(TestResponse { success: "Successfully inserted" }))
 }
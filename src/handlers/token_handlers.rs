@@ -0,0 +1,101 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use surrealdb::{engine::any::Any, Surreal};
+use tower_cookies::Cookies;
+
+use crate::{
+    config::Config,
+    errors::{
+        authentication_errors::AuthenticationError, authorization_errors::AuthorizationError,
+        AppError,
+    },
+    handlers::login_handlers::{set_token_cookies, LoginResponse},
+    models::{refresh_token::RefreshToken, session::Session, user::User},
+    services::jwt,
+};
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RefreshRequest {
+    refresh_token: Option<String>,
+}
+
+// exchanges a refresh token for a new access token and refresh token
+// the refresh token is taken from the body, otherwise from the cookie set at login
+pub async fn refresh_tokens(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
+    cookies: Cookies,
+    request: Option<Json<RefreshRequest>>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let presented = request
+        .and_then(|Json(request)| request.refresh_token)
+        .or_else(|| {
+            cookies
+                .get("_Secure-refresh")
+                .map(|cookie| cookie.value().to_string())
+        })
+        .ok_or(AuthenticationError::MissingCredentials {
+            msg: "Refresh token is required".to_string(),
+        })?;
+
+    let rotation = RefreshToken::rotate(&db, &config.jwt, &presented).await?;
+
+    Session::touch(&db, &config.jwt, &rotation.family).await?;
+
+    let user = User::get_user_by_id(db.clone(), rotation.user.id.to_raw()).await?;
+
+    let token = match &rotation.club {
+        None => {
+            jwt::get_jwt(
+                &config.jwt,
+                user.get_id().id.to_raw(),
+                user.get_user_type()
+                    .ok_or(AppError::internal("User account has no user type"))?,
+                rotation.family.clone(),
+            )
+            .await?
+        }
+        // the user has to still be an official of the club
+        Some(club_id) => {
+            let club = User::get_user_by_id(db.clone(), club_id.id.to_raw()).await?;
+            let role = club.get_club_role(&rotation.user).ok_or(
+                AuthorizationError::AccessDeniedError {
+                    msg: "You do not have access to this club account".to_string(),
+                },
+            )?;
+
+            jwt::get_club_jwt(
+                &config.jwt,
+                club_id.id.to_raw(),
+                role,
+                rotation.user.id.to_raw(),
+                rotation.family.clone(),
+            )?
+        }
+    };
+
+    // club tokens are not kept in cookies
+    if rotation.club.is_none() {
+        set_token_cookies(&cookies, &config, &token, &rotation.refresh_token);
+    }
+
+    Ok(Json(LoginResponse::Success {
+        message: "Tokens refreshed".to_string(),
+        token,
+        refresh_token: rotation.refresh_token,
+    }))
+}
+
+// response struct for the public keys tokens are verified with
+#[derive(serde::Serialize)]
+pub struct JwksResponse {
+    keys: Vec<jwt::Jwk>,
+}
+
+// lets other services verify ufora tokens without being able to issue them
+pub async fn get_jwks(State(config): State<Arc<Config>>) -> Json<JwksResponse> {
+    Json(JwksResponse {
+        keys: config.jwt.keyring.jwks(),
+    })
+}
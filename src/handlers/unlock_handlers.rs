@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::AppError,
+    models::user::User,
+    services::otp::{OtpPurpose, OtpService},
+};
+
+// request struct for asking an unlock otp
+#[derive(serde::Deserialize)]
+pub struct UnlockOTPRequest {
+    email: String,
+}
+
+// request struct for unlocking an account
+#[derive(serde::Deserialize)]
+pub struct UnlockRequest {
+    email: String,
+    otp: String,
+}
+
+// response struct for the unlock handlers
+#[derive(serde::Serialize)]
+pub struct UnlockResponse {
+    message: String,
+}
+
+// handler for sending an unlock otp to the email of a locked account
+// the reply does not tell whether the account exists or is locked
+pub async fn request_unlock_otp(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(otp_service): State<OtpService>,
+    Json(unlock_request): Json<UnlockOTPRequest>,
+) -> Result<Json<UnlockResponse>, AppError> {
+    if unlock_request.email.is_empty() {
+        return Err(AppError::invalid_input("Email is required"));
+    }
+
+    let user = match User::get_user_by_email(db.clone(), unlock_request.email.clone()).await {
+        Ok(user) => Some(user),
+        Err(AppError::NotFound { .. }) => None,
+        Err(e) => return Err(e),
+    };
+
+    if let Some(user) = user {
+        if user.get_locked_until().is_some() {
+            // a cooldown answer would tell that the account is locked
+            match otp_service
+                .send(OtpPurpose::AccountUnlock, &unlock_request.email)
+                .await
+            {
+                Ok(()) | Err(AppError::TooManyRequests { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    Ok(Json(UnlockResponse {
+        message: "If the account is locked, an OTP has been sent to its email".to_string(),
+    }))
+}
+
+// handler for unlocking an account with the otp sent to its email
+pub async fn unlock_account(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(otp_service): State<OtpService>,
+    Json(unlock_request): Json<UnlockRequest>,
+) -> Result<Json<UnlockResponse>, AppError> {
+    otp_service
+        .verify(
+            OtpPurpose::AccountUnlock,
+            &unlock_request.email,
+            &unlock_request.otp,
+        )
+        .await?;
+
+    let user = User::get_user_by_email(db.clone(), unlock_request.email).await?;
+
+    User::reset_login_attempts(db.clone(), user.get_id()).await?;
+
+    Ok(Json(UnlockResponse {
+        message: "Account has been unlocked successfully".to_string(),
+    }))
+}
@@ -1,6 +1,7 @@
 use dotenvy::dotenv;
 
 mod app;
+mod config;
 mod routes;
 mod database;
 mod middlewares;
@@ -8,14 +9,64 @@ mod errors;
 mod handlers;
 mod services;
 mod models;
+mod state;
+mod migrations;
+
+#[cfg(test)]
+mod tests;
 
 #[tokio::main]
 async fn main() {
 
     // load environment variables
     dotenv().ok();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // `ufora-backend generate-jwt-key` prints a new token signing key, before a key is configured
+    if args == ["generate-jwt-key"] {
+        match services::jwt::generate_signing_key() {
+            Ok((private_key, public_key)) => {
+                println!("{}", private_key);
+                println!("public key: {}", public_key);
+                return;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // load and check the configuration before anything else starts
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     
-    app::run().await.unwrap();
+    // `ufora-backend migrate` only applies the schema migrations
+    // `ufora-backend promote-admin <username>` makes an existing account an administrator
+
+    let result = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        [] => app::run(config).await,
+        ["migrate"] => app::migrate(config).await,
+        ["promote-admin", username] => app::promote_admin(config, username.to_string()).await,
+        _ => {
+            eprintln!(
+                "unknown command `{}`, expected `migrate`, `promote-admin <username>` or `generate-jwt-key`",
+                args.join(" ")
+            );
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
 
 
 }
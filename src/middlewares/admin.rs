@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::{
+        authentication_errors::AuthenticationError, authorization_errors::AuthorizationError,
+        AppError,
+    },
+    models::{user_claim::Claim, user_type::UserType},
+    services::query_builder::Select,
+};
+
+// only lets administrators through, must run inside validate_jwt
+// the user type is read from the database as well, so a demoted administrator
+// can not keep using a token issued before
+pub async fn require_admin<T>(
+    State(db): State<Arc<Surreal<Any>>>,
+    request: Request<T>,
+    next: Next<T>,
+) -> Result<Response, AppError> {
+    let (claimed_type, user) = request
+        .extensions()
+        .get::<Claim>()
+        .map(|claim| (claim.get_user_type(), claim.get_surrealdb_thing()))
+        .ok_or(AuthenticationError::InvalidToken {
+            msg: "Missing claim".to_string(),
+        })?;
+
+    let user_type: Option<UserType> = match claimed_type {
+        UserType::Admin => Select::from_record(user)
+            .value("user_type")
+            .execute(&db)
+            .await?
+            .take(0)?,
+        _ => None,
+    };
+
+    if user_type != Some(UserType::Admin) {
+        return Err(AuthorizationError::InsufficientPermissionsError {
+            msg: "This action is only available to administrators".to_string(),
+        }
+        .into());
+    }
+
+    Ok(next.run(request).await)
+}
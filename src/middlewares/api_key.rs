@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    errors::{authorization_errors::AuthorizationError, AppError},
+    models::{
+        api_key::{ApiKey, ApiScope},
+        session::Device,
+        user_claim::Claim,
+    },
+};
+
+// header integrations send their api key in
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+// authenticates requests made with an api key instead of a user token
+// must wrap validate_jwt, which lets requests through that already carry a claim
+// requests without the header are left to validate_jwt
+pub async fn validate_api_key<T>(
+    State(db): State<Arc<Surreal<Any>>>,
+    device: Device,
+    mut request: Request<T>,
+    next: Next<T>,
+) -> Result<Response, AppError> {
+    let key = match request
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(key) => key.trim().to_string(),
+        None => return Ok(next.run(request).await),
+    };
+
+    let (api_key, user_type) = ApiKey::authenticate(&db, &key, device.ip.as_deref()).await?;
+
+    // only the routes of the scopes of the key, never e.g. the management of the keys themselves
+    let allowed = ApiScope::required_for(request.method(), request.uri().path())
+        .is_some_and(|scope| api_key.has_scope(scope));
+
+    if !allowed {
+        return Err(AuthorizationError::InsufficientPermissionsError {
+            msg: "This API key has no scope for this action".to_string(),
+        }
+        .into());
+    }
+
+    let user_id = api_key.get_user().id.to_raw();
+
+    request
+        .extensions_mut()
+        .insert(Claim::for_api_key(user_id, user_type, api_key.get_id()));
+
+    Ok(next.run(request).await)
+}
@@ -1,46 +1,78 @@
-use axum::{
-    http::{Request, StatusCode},
-    middleware::Next,
-    response::Response,
-};
+use std::sync::Arc;
+
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use surrealdb::{engine::any::Any, Surreal};
 use tower_cookies::Cookies;
 
-use crate::services::jwt;
+use crate::{
+    config::Config,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::revoked_token::RevokedToken,
+    services::{jwt, query_builder::record},
+};
 
 pub async fn validate_jwt<T>(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
     cookies: Cookies,
     mut request: Request<T>,
     next: Next<T>,
-) -> Result<Response, StatusCode> {
-    if request.headers().contains_key("Authorization") {
-        let authorization_header = request
-            .headers()
-            .get("Authorization")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-
-        let token =
-            jwt::verify_jwt(authorization_header.split(" ").collect::<Vec<&str>>()[1].to_string())
-                .await?;
+) -> Result<Response, AppError> {
+    // already authenticated by an api key, see middlewares::api_key
+    if request
+        .extensions()
+        .get::<crate::models::user_claim::Claim>()
+        .is_some()
+    {
+        return Ok(next.run(request).await);
+    }
 
-        let claim = crate::models::user_claim::Claim::from(token);
+    // token from the authorization header ("Bearer <token>"), otherwise from the cookie
+    let token = match request.headers().get("Authorization") {
+        Some(authorization_header) => authorization_header
+            .to_str()
+            .ok()
+            .and_then(|header| header.split_once(' '))
+            .map(|(_, token)| token.to_string())
+            .ok_or(AuthenticationError::InvalidToken {
+                msg: "Malformed authorization header".to_string(),
+            })?,
+        None => match cookies.get("_Secure-jwt") {
+            Some(cookie) => cookie.value().to_string(),
+            None => {
+                return Err(AuthenticationError::InvalidToken {
+                    msg: "Missing authentication token".to_string(),
+                }
+                .into())
+            }
+        },
+    };
 
-        request.extensions_mut().insert(claim);
+    let token = jwt::verify_jwt(&config.jwt, token).await?;
 
-        Ok(next.run(request).await)
-    } else if let Some(cookie) = cookies.get("_Secure-jwt") {
-        let token = cookie.value().to_string();
+    // tokens stay valid until they expire unless they have been revoked by a logout
+    if RevokedToken::is_revoked(
+        &db,
+        token.get_jti(),
+        record("user", token.get_id()),
+        token.get_session_id(),
+        token.get_issued_at(),
+    )
+    .await?
+    {
+        return Err(revoked());
+    }
 
-        let token = jwt::verify_jwt(token).await?;
+    let claim = crate::models::user_claim::Claim::from(token);
 
-        let claim = crate::models::user_claim::Claim::from(token);
+    request.extensions_mut().insert(claim);
 
-        request.extensions_mut().insert(claim);
+    Ok(next.run(request).await)
+}
 
-        Ok(next.run(request).await)
-    } else {
-        return Err(StatusCode::UNAUTHORIZED);
+pub fn revoked() -> AppError {
+    AuthenticationError::InvalidToken {
+        msg: "Token has been revoked, please log in again".to_string(),
     }
+    .into()
 }
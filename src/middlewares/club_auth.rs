@@ -1,23 +1,47 @@
-use axum::{http::Request, middleware::Next, response::Response};
-use reqwest::StatusCode;
+use std::sync::Arc;
+
+use axum::{extract::State, http::Request, middleware::Next, response::Response};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    config::Config,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    middlewares::auth::revoked,
+    models::revoked_token::RevokedToken,
+    services::query_builder::record,
+};
 
 pub async fn validate_club_token<T>(
+    State(db): State<Arc<Surreal<Any>>>,
+    State(config): State<Arc<Config>>,
     mut request: Request<T>,
     next: Next<T>,
-) -> Result<Response, StatusCode> {
-    if request.headers().contains_key("Club-Authorization") {
-        let authorization_header = request
-            .headers()
-            .get("Club-Authorization")
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-        let club_token = crate::services::jwt::verify_club_jwt(authorization_header.to_string())?;
-        let claim = crate::models::club_claim::ClubClaim::from(club_token);
-        request.extensions_mut().insert(claim);
-        Ok(next.run(request).await)
-    } else {
-        return Err(StatusCode::UNAUTHORIZED);
+) -> Result<Response, AppError> {
+    let authorization_header = request
+        .headers()
+        .get("Club-Authorization")
+        .and_then(|header| header.to_str().ok())
+        .map(|header| header.to_string())
+        .ok_or(AuthenticationError::InvalidToken {
+            msg: "Missing club authentication token".to_string(),
+        })?;
+
+    let club_token = crate::services::jwt::verify_club_jwt(&config.jwt, authorization_header)?;
+
+    // club tokens are revoked together with the tokens of the official they were issued to
+    if RevokedToken::is_revoked(
+        &db,
+        club_token.get_jti(),
+        record("user", club_token.get_user_id()),
+        club_token.sid.clone(),
+        club_token.iat,
+    )
+    .await?
+    {
+        return Err(revoked());
     }
+
+    let claim = crate::models::club_claim::ClubClaim::from(club_token);
+    request.extensions_mut().insert(claim);
+    Ok(next.run(request).await)
 }
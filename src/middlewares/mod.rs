@@ -1,2 +1,5 @@
+pub mod admin;
+pub mod api_key;
 pub mod auth;
-pub mod club_auth;
\ No newline at end of file
+pub mod club_auth;
+pub mod role;
\ No newline at end of file
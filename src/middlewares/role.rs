@@ -0,0 +1,54 @@
+use axum::{
+    http::Request,
+    middleware::{self, FromFnLayer, Next},
+    response::Response,
+};
+use futures::future::BoxFuture;
+
+use crate::{
+    errors::{
+        authentication_errors::AuthenticationError, authorization_errors::AuthorizationError,
+        AppError,
+    },
+    models::{user_claim::Claim, user_type::UserType},
+};
+
+// route layer that only lets the given user types through
+// must run inside validate_jwt, which puts the claim into the request
+// e.g. .route_layer(require_role(&[UserType::Company]))
+#[allow(clippy::type_complexity)]
+pub fn require_role<B: Send + 'static>(
+    allowed: &'static [UserType],
+) -> FromFnLayer<
+    impl Fn(Request<B>, Next<B>) -> BoxFuture<'static, Result<Response, AppError>> + Clone + Send + Sync,
+    (),
+    (Request<B>,),
+> {
+    middleware::from_fn(move |request: Request<B>, next: Next<B>| {
+        Box::pin(check_role(allowed, request, next))
+            as BoxFuture<'static, Result<Response, AppError>>
+    })
+}
+
+async fn check_role<B>(
+    allowed: &'static [UserType],
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AppError> {
+    let user_type = request
+        .extensions()
+        .get::<Claim>()
+        .map(|claim| claim.get_user_type())
+        .ok_or(AuthenticationError::InvalidToken {
+            msg: "Missing claim".to_string(),
+        })?;
+
+    if !allowed.contains(&user_type) {
+        return Err(AuthorizationError::InsufficientPermissionsError {
+            msg: format!("This action is not available to {} accounts", user_type),
+        }
+        .into());
+    }
+
+    Ok(next.run(request).await)
+}
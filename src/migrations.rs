@@ -0,0 +1,168 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+// a versioned schema change written in SurrealQL
+// migrations are applied in order of their version and each one only once
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+// every migration known to this build, new migrations are appended with the next version
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        sql: include_str!("../migrations/0001_initial_schema.surql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_club_edge",
+        sql: include_str!("../migrations/0002_create_club_edge.surql"),
+    },
+    Migration {
+        version: 3,
+        name: "refresh_tokens",
+        sql: include_str!("../migrations/0003_refresh_tokens.surql"),
+    },
+    Migration {
+        version: 4,
+        name: "token_revocation",
+        sql: include_str!("../migrations/0004_token_revocation.surql"),
+    },
+    Migration {
+        version: 5,
+        name: "sessions",
+        sql: include_str!("../migrations/0005_sessions.surql"),
+    },
+    Migration {
+        version: 6,
+        name: "login_lockout",
+        sql: include_str!("../migrations/0006_login_lockout.surql"),
+    },
+    Migration {
+        version: 7,
+        name: "mfa",
+        sql: include_str!("../migrations/0007_mfa.surql"),
+    },
+    Migration {
+        version: 8,
+        name: "action_tokens",
+        sql: include_str!("../migrations/0008_action_tokens.surql"),
+    },
+    Migration {
+        version: 9,
+        name: "otp_service",
+        sql: include_str!("../migrations/0009_otp_service.surql"),
+    },
+    Migration {
+        version: 10,
+        name: "admin",
+        sql: include_str!("../migrations/0010_admin.surql"),
+    },
+    Migration {
+        version: 11,
+        name: "account_lifecycle",
+        sql: include_str!("../migrations/0011_account_lifecycle.surql"),
+    },
+    Migration {
+        version: 12,
+        name: "data_export",
+        sql: include_str!("../migrations/0012_data_export.surql"),
+    },
+    Migration {
+        version: 13,
+        name: "email_change",
+        sql: include_str!("../migrations/0013_email_change.surql"),
+    },
+    Migration {
+        version: 14,
+        name: "api_keys",
+        sql: include_str!("../migrations/0014_api_keys.surql"),
+    },
+];
+
+// table keeping track of the migrations that have been applied
+const MIGRATION_TABLE_QUERY: &str = "
+    DEFINE TABLE migration SCHEMAFULL;
+    DEFINE FIELD version ON TABLE migration TYPE int;
+    DEFINE FIELD name ON TABLE migration TYPE string;
+    DEFINE FIELD applied_at ON TABLE migration TYPE datetime;
+    DEFINE INDEX migration_version ON TABLE migration COLUMNS version UNIQUE;
+";
+
+// applies all pending migrations and returns the versions that were applied
+pub async fn run(db: &Surreal<Any>) -> Result<Vec<u32>, String> {
+    db.query(MIGRATION_TABLE_QUERY)
+        .await
+        .and_then(|response| response.check())
+        .map_err(|e| format!("could not create the migration table: {}", e))?;
+
+    let applied = get_applied_versions(db).await?;
+
+    // a database migrated by a newer build can not be handled by this one
+    if let Some(latest) = applied.iter().max() {
+        if MIGRATIONS
+            .iter()
+            .all(|migration| migration.version < *latest)
+        {
+            return Err(format!(
+                "database is at migration {} which is unknown to this build",
+                latest
+            ));
+        }
+    }
+
+    let mut newly_applied = vec![];
+
+    for migration in MIGRATIONS {
+        if applied.contains(&migration.version) {
+            continue;
+        }
+
+        apply(db, migration).await?;
+
+        println!(
+            "applied migration {:04}_{}",
+            migration.version, migration.name
+        );
+
+        newly_applied.push(migration.version);
+    }
+
+    Ok(newly_applied)
+}
+
+async fn get_applied_versions(db: &Surreal<Any>) -> Result<Vec<u32>, String> {
+    db.query("SELECT VALUE version FROM migration")
+        .await
+        .and_then(|mut response| response.take::<Vec<u32>>(0))
+        .map_err(|e| format!("could not read applied migrations: {}", e))
+}
+
+// runs the migration and records it in one transaction, so a failing migration leaves nothing behind
+async fn apply(db: &Surreal<Any>, migration: &Migration) -> Result<(), String> {
+    let statements = migration.sql.trim().trim_end_matches(';');
+
+    let query = format!(
+        "BEGIN TRANSACTION;
+        {};
+        CREATE type::thing('migration', $version) SET version = $version, name = $name, applied_at = time::now();
+        COMMIT TRANSACTION;",
+        statements
+    );
+
+    db.query(query)
+        .bind(("version", migration.version))
+        .bind(("name", migration.name))
+        .await
+        .and_then(|response| response.check())
+        .map_err(|e| {
+            format!(
+                "migration {:04}_{} failed: {}",
+                migration.version, migration.name, e
+            )
+        })?;
+
+    Ok(())
+}
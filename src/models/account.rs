@@ -0,0 +1,392 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    errors::AppError,
+    models::{revoked_token::RevokedToken, session::Session},
+    services::{
+        email::Mailer,
+        query_builder::{
+            field, get_update_query_for_merge_or_content, to_value, transaction, Item, Query,
+            Select, UpdateOperator,
+        },
+    },
+};
+
+// tables of the content a user creates, each linked to its creator with a create_<table> edge
+const CONTENT_TABLES: [&str; 3] = ["post", "blog", "project"];
+
+// every edge from a user to something they created
+const CREATE_EDGES: [&str; 5] = [
+    "create_post",
+    "create_blog",
+    "create_project",
+    "create_chat",
+    "create_club",
+];
+
+// lifecycle state of an account, kept on the user record
+// a deactivated account and its content are hidden from other users until it is reactivated
+// an account scheduled for deletion is deactivated and deleted once the grace period is over
+#[derive(Deserialize, Debug)]
+pub struct Account {
+    id: Thing,
+    username: String,
+    email: String,
+    user_type: String,
+    registration_date: Option<Datetime>,
+    deactivated: Option<bool>,
+    deletion_scheduled_for: Option<Datetime>,
+}
+
+// what is left of a deleted user, chats and other records keep pointing to it
+#[derive(Serialize)]
+struct DeletedUser {
+    username: String,
+    email: String,
+    user_type: String,
+    registration_date: Option<Datetime>,
+    deactivated: bool,
+    deleted_at: Datetime,
+    // the schema only fills in defaults when a record is created
+    locked_flag: bool,
+    locked_by_admin: bool,
+    email_verification_flag: bool,
+    mfa_enabled: bool,
+    invalid_login_attempts: i64,
+    lockouts: i64,
+}
+
+// a personal chat with only the messages, which are rewritten as they are
+#[derive(Deserialize)]
+struct ChatMessages {
+    id: Thing,
+    #[serde(default)]
+    messages: Vec<serde_json::Value>,
+}
+
+impl Account {
+    pub async fn of(db: &Surreal<Any>, user: Thing) -> Result<Self, AppError> {
+        let account: Option<Self> = Select::from_record(user)
+            .fields(&[
+                "id",
+                "username",
+                "email",
+                "user_type",
+                "registration_date",
+                "deactivated",
+                "deletion_scheduled_for",
+            ])
+            .where_(field("deleted_at").is_none())
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        account.ok_or(AppError::not_found("User could not be found"))
+    }
+
+    pub fn is_deactivated(&self) -> bool {
+        self.deactivated.unwrap_or(false)
+    }
+
+    pub fn deletion_scheduled_for(&self) -> Option<DateTime<Utc>> {
+        self.deletion_scheduled_for
+            .as_ref()
+            .map(|scheduled_for| scheduled_for.0)
+    }
+
+    pub fn get_username(&self) -> &str {
+        &self.username
+    }
+
+    pub fn get_email(&self) -> &str {
+        &self.email
+    }
+
+    // hides the account and its content, or shows them again
+    pub async fn set_deactivated(
+        &self,
+        db: &Surreal<Any>,
+        deactivated: bool,
+    ) -> Result<(), AppError> {
+        let mut account = Query::raw("UPDATE ");
+        account
+            .push_value(self.id.clone())
+            .push(" SET deactivated = ")
+            .push_value(deactivated);
+
+        let mut queries = vec![account];
+        queries.extend(self.content_visibility_queries(deactivated));
+
+        transaction(queries).execute_transaction(db).await?;
+
+        Ok(())
+    }
+
+    // deactivates the account and deletes it after the grace period
+    // returns the time the account will be deleted at
+    pub async fn schedule_deletion(
+        &self,
+        db: &Surreal<Any>,
+        grace_days: i64,
+    ) -> Result<DateTime<Utc>, AppError> {
+        if self.deletion_scheduled_for.is_some() {
+            return Err(AppError::invalid_input(
+                "The deletion of this account has already been requested",
+            ));
+        }
+
+        let scheduled_for = Utc::now() + Duration::days(grace_days);
+
+        let mut account = Query::raw("UPDATE ");
+        account
+            .push_value(self.id.clone())
+            .push(" SET deactivated = true, deletion_scheduled_for = ")
+            .push_value(Datetime::from(scheduled_for));
+
+        let mut queries = vec![account];
+        queries.extend(self.content_visibility_queries(true));
+
+        transaction(queries).execute_transaction(db).await?;
+
+        Ok(scheduled_for)
+    }
+
+    // keeps the account, it is reactivated as well
+    pub async fn cancel_deletion(&self, db: &Surreal<Any>) -> Result<(), AppError> {
+        if self.deletion_scheduled_for.is_none() {
+            return Err(AppError::not_found(
+                "No deletion has been requested for this account",
+            ));
+        }
+
+        let mut account = Query::raw("UPDATE ");
+        account
+            .push_value(self.id.clone())
+            .push(" SET deactivated = false, deletion_scheduled_for = NONE");
+
+        let mut queries = vec![account];
+        queries.extend(self.content_visibility_queries(false));
+
+        transaction(queries).execute_transaction(db).await?;
+
+        Ok(())
+    }
+
+    // deletes every account whose grace period is over and tells its owner
+    // returns the number of accounts deleted, an account that fails is retried the next time
+    pub async fn delete_due(db: &Surreal<Any>, mailer: &Mailer) -> Result<usize, AppError> {
+        let due: Vec<Self> = Select::from("user")
+            .fields(&[
+                "id",
+                "username",
+                "email",
+                "user_type",
+                "registration_date",
+                "deactivated",
+                "deletion_scheduled_for",
+            ])
+            // NONE sorts before every datetime, so accounts without a deletion are left out first
+            .where_(
+                field("deletion_scheduled_for")
+                    .is_not_none()
+                    .and(field("deletion_scheduled_for").lte(Datetime::default()))
+                    .and(field("deleted_at").is_none()),
+            )
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let mut deleted = 0;
+
+        for account in due {
+            if let Err(e) = account.delete(db).await {
+                tracing::warn!("could not delete {}: {:?}", account.id, e);
+                continue;
+            }
+
+            deleted += 1;
+
+            let confirmation = mailer
+                .send_email(
+                    &format!("{} <{}>", account.username, account.email),
+                    "Your Ufora account has been deleted".to_string(),
+                    format!(
+                        "Your Ufora account {} has been deleted as you asked. Your profile, posts, blogs and projects have been removed and your chat messages no longer show your name.",
+                        account.username
+                    ),
+                )
+                .await;
+
+            if let Err(e) = confirmation {
+                tracing::warn!(
+                    "could not send the deletion confirmation for {}: {:?}",
+                    account.id,
+                    e
+                );
+            }
+        }
+
+        Ok(deleted)
+    }
+
+    // removes the content of the user and every link to it, and anonymises the user record
+    async fn delete(&self, db: &Surreal<Any>) -> Result<(), AppError> {
+        RevokedToken::revoke_all_issued_to(db, self.id.clone()).await?;
+        Session::end_all(db, self.id.clone()).await?;
+
+        let mut queries = self.anonymise_chat_messages_queries(db).await?;
+
+        for table in CONTENT_TABLES {
+            let mut content = Query::raw(&format!(
+                "DELETE {} WHERE <-create_{}<-user CONTAINS ",
+                table, table
+            ));
+            content.push_value(self.id.clone());
+            queries.push(content);
+        }
+
+        for edge in CREATE_EDGES {
+            let mut edges = Query::raw(&format!("DELETE {} WHERE in = ", edge));
+            edges.push_value(self.id.clone());
+            queries.push(edges);
+        }
+
+        // reactions and comments on the posts of other users
+        let mut posts = Query::new();
+        let user = posts.bind(self.id.clone());
+        posts.push(&format!(
+            "UPDATE post SET reactions -= {user}, comments = comments[WHERE user != {user}] \
+             WHERE reactions CONTAINS {user} OR comments.*.user CONTAINS {user}"
+        ));
+        queries.push(posts);
+
+        // the user is no longer an official of any club
+        let mut clubs = Query::new();
+        let user = clubs.bind(self.id.clone());
+        clubs.push(&format!(
+            "UPDATE user SET officials = officials[WHERE user_id != {user}] \
+             WHERE officials.*.user_id CONTAINS {user}"
+        ));
+        queries.push(clubs);
+
+        let mut recovery_codes = Query::raw("DELETE recovery_code WHERE user = ");
+        recovery_codes.push_value(self.id.clone());
+        queries.push(recovery_codes);
+
+        let mut data_exports = Query::raw("DELETE data_export WHERE user = ");
+        data_exports.push_value(self.id.clone());
+        queries.push(data_exports);
+
+        // the old addresses of the user
+        let mut email_changes = Query::raw("DELETE email_change WHERE user = ");
+        email_changes.push_value(self.id.clone());
+        queries.push(email_changes);
+
+        let mut api_keys = Query::raw("DELETE api_key WHERE user = ");
+        api_keys.push_value(self.id.clone());
+        queries.push(api_keys);
+
+        // replaces the whole record, the profile, every secret and a pending email are dropped
+        let id = self.id.id.to_raw();
+        queries.push(get_update_query_for_merge_or_content(
+            Item::Record(self.id.clone()),
+            UpdateOperator::Content,
+            to_value(DeletedUser {
+                username: format!("deleted-{}", id),
+                email: format!("{}@deleted.invalid", id),
+                user_type: self.user_type.clone(),
+                registration_date: self.registration_date.clone(),
+                deactivated: true,
+                deleted_at: Datetime::default(),
+                locked_flag: false,
+                locked_by_admin: false,
+                email_verification_flag: false,
+                mfa_enabled: false,
+                invalid_login_attempts: 0,
+                lockouts: 0,
+            })?,
+            None,
+            None,
+        ));
+
+        transaction(queries).execute_transaction(db).await?;
+
+        Ok(())
+    }
+
+    // the messages of the user stay in the chats of the other users, without saying who sent them
+    async fn anonymise_chat_messages_queries(
+        &self,
+        db: &Surreal<Any>,
+    ) -> Result<Vec<Query>, AppError> {
+        let chats: Vec<ChatMessages> = Select::from("personalchat")
+            .fields(&["id", "messages"])
+            .where_(
+                field("chatmadeby")
+                    .eq(self.id.clone())
+                    .or(field("chatmadewith").eq(self.id.clone())),
+            )
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        // the sender is recorded either by user id or by username
+        let senders = [
+            self.id.to_string(),
+            self.id.id.to_raw(),
+            self.username.clone(),
+        ];
+
+        let mut queries = vec![];
+
+        for mut chat in chats {
+            let mut changed = false;
+
+            for message in chat.messages.iter_mut() {
+                let sent_by_user = message
+                    .get("from")
+                    .and_then(|from| from.as_str())
+                    .is_some_and(|from| senders.iter().any(|sender| sender == from));
+
+                if sent_by_user {
+                    if let Some(message) = message.as_object_mut() {
+                        message.remove("from");
+                        changed = true;
+                    }
+                }
+            }
+
+            if changed {
+                let mut query = Query::raw("UPDATE ");
+                query
+                    .push_value(chat.id)
+                    .push(" SET messages = ")
+                    .push_value(to_value(chat.messages)?);
+                queries.push(query);
+            }
+        }
+
+        Ok(queries)
+    }
+
+    // hides or shows the posts, blogs and projects of the user
+    fn content_visibility_queries(&self, hidden: bool) -> Vec<Query> {
+        CONTENT_TABLES
+            .iter()
+            .map(|table| {
+                let mut query = Query::raw(&format!("UPDATE {} SET hidden = ", table));
+                query
+                    .push_value(hidden)
+                    .push(&format!(" WHERE <-create_{}<-user CONTAINS ", table))
+                    .push_value(self.id.clone());
+                query
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use surrealdb::{
+    engine::any::Any,
+    sql::{self, Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    errors::AppError,
+    models::user_type::UserType,
+    services::query_builder::{field, Condition, OrderBy, Query, Select},
+};
+
+// users listed to administrators at most per request
+const MAX_USERS_PER_PAGE: i64 = 100;
+
+// fields of a user shown to administrators, secrets are never part of it
+const USER_FIELDS: [&str; 11] = [
+    "username",
+    "name",
+    "email",
+    "user_type",
+    "email_verification_flag",
+    "registration_date",
+    "locked_by_admin",
+    "locked_until",
+    "club_type",
+    "club_verification_file",
+    "club_verification_flag",
+];
+
+// filters for listing users, every filter is optional
+#[derive(Deserialize, Debug, Default)]
+pub struct UserSearch {
+    // part of the username, name or email, in any case
+    search: Option<String>,
+    user_type: Option<UserType>,
+    // only users that are locked, by an administrator or after failed logins
+    #[serde(default)]
+    locked: bool,
+    limit: Option<i64>,
+    start: Option<i64>,
+}
+
+// counts shown on the administration dashboard
+#[derive(Serialize, Debug)]
+pub struct Stats {
+    // users per user type
+    users: BTreeMap<String, i64>,
+    posts: i64,
+    blogs: i64,
+    projects: i64,
+    clubs_pending_verification: i64,
+    locked_users: i64,
+    active_sessions: i64,
+}
+
+#[derive(Deserialize)]
+struct Count {
+    count: i64,
+}
+
+#[derive(Deserialize)]
+struct UserTypeCount {
+    user_type: String,
+    count: i64,
+}
+
+pub async fn search_users(db: &Surreal<Any>, search: UserSearch) -> Result<Vec<Value>, AppError> {
+    let mut conditions = vec![];
+
+    if let Some(text) = search.search.filter(|text| !text.trim().is_empty()) {
+        let text = text.trim().to_lowercase();
+        conditions.push(
+            field("string::lowercase(username)")
+                .contains(text.clone())
+                .or(field("string::lowercase(name ?? '')").contains(text.clone()))
+                .or(field("string::lowercase(email)").contains(text)),
+        );
+    }
+
+    if let Some(user_type) = search.user_type {
+        conditions.push(field("user_type").eq(user_type.to_string()));
+    }
+
+    if search.locked {
+        conditions.push(locked());
+    }
+
+    let users: Vec<Value> = users_query()
+        .where_some(Condition::all(conditions))
+        .order_by(OrderBy::Ascending(vec!["username".to_string()]))
+        .limit(
+            search
+                .limit
+                .unwrap_or(MAX_USERS_PER_PAGE)
+                .clamp(1, MAX_USERS_PER_PAGE),
+        )
+        .start(search.start.unwrap_or(0).max(0))
+        .execute(db)
+        .await?
+        .take(0)?;
+
+    Ok(users)
+}
+
+pub async fn get_user(db: &Surreal<Any>, user_id: Thing) -> Result<Value, AppError> {
+    let user: Option<Value> = Select::from_record(user_id)
+        .field_as("meta::id(id)", "id")
+        .fields(&USER_FIELDS)
+        .execute(db)
+        .await?
+        .take(0)?;
+
+    user.ok_or(AppError::not_found("User could not be found"))
+}
+
+// clubs whose verification file has been neither approved nor rejected
+pub async fn clubs_pending_verification(db: &Surreal<Any>) -> Result<Vec<Value>, AppError> {
+    let clubs: Vec<Value> = users_query()
+        .where_(pending_club())
+        .order_by(OrderBy::Ascending(vec!["registration_date".to_string()]))
+        .execute(db)
+        .await?
+        .take(0)?;
+
+    Ok(clubs)
+}
+
+// approves the verification file of a club, or rejects it with a reason
+pub async fn decide_club_verification(
+    db: &Surreal<Any>,
+    club_id: Thing,
+    rejection: Option<String>,
+) -> Result<(), AppError> {
+    let mut query = Query::raw("UPDATE ");
+    query
+        .push_value(club_id)
+        .push(" SET club_verification_flag = ")
+        .push_value(rejection.is_none())
+        .push(", club_verification_rejection = ")
+        .push_value(rejection.map_or(sql::Value::None, Into::into))
+        .push(" WHERE user_type = ")
+        .push_value(UserType::Club.to_string())
+        .push(" RETURN id");
+
+    let updated: Option<Value> = query.execute(db).await?.take(0)?;
+    updated
+        .map(|_| ())
+        .ok_or(AppError::not_found("Club could not be found"))
+}
+
+pub async fn stats(db: &Surreal<Any>) -> Result<Stats, AppError> {
+    let mut query = Query::raw("SELECT user_type, count() AS count FROM user GROUP BY user_type;");
+    query.append(count_of("post", None));
+    query.append(count_of("blog", None));
+    query.append(count_of("project", None));
+    query.append(count_of("user", Some(pending_club())));
+    query.append(count_of("user", Some(locked())));
+    query.append(count_of(
+        "session",
+        Some(
+            field("revoked")
+                .eq(false)
+                .and(field("expires_at").gt(Datetime::default())),
+        ),
+    ));
+
+    let mut response = query.execute(db).await?;
+
+    let users: Vec<UserTypeCount> = response.take(0)?;
+    let mut counts = vec![];
+    for index in 1..=6 {
+        let count: Option<Count> = response.take(index)?;
+        counts.push(count.map_or(0, |count| count.count));
+    }
+
+    Ok(Stats {
+        users: users
+            .into_iter()
+            .map(|users| (users.user_type, users.count))
+            .collect(),
+        posts: counts[0],
+        blogs: counts[1],
+        projects: counts[2],
+        clubs_pending_verification: counts[3],
+        locked_users: counts[4],
+        active_sessions: counts[5],
+    })
+}
+
+fn users_query() -> Select {
+    Select::from("user")
+        .field_as("meta::id(id)", "id")
+        .fields(&USER_FIELDS)
+}
+
+fn count_of(table: &str, condition: Option<Condition>) -> Query {
+    let mut query = Select::from(table)
+        .field_as("count()", "count")
+        .where_some(condition)
+        .group_all()
+        .build();
+    query.push(";");
+    query
+}
+
+fn pending_club() -> Condition {
+    field("user_type")
+        .eq(UserType::Club.to_string())
+        .and(field("club_verification_flag").ne(true))
+        .and(field("club_verification_rejection").is_none())
+}
+
+fn locked() -> Condition {
+    field("locked_by_admin")
+        .eq(true)
+        .or(field("locked_until").gt(Datetime::default()))
+}
@@ -0,0 +1,340 @@
+use std::net::IpAddr;
+
+use axum::http::Method;
+use serde::Deserialize;
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    errors::{authentication_errors::AuthenticationError, AppError},
+    models::user_type::UserType,
+    services::{
+        ip_range,
+        query_builder::{
+            field, get_create_query_for_an_object, record, to_value, DatabaseObject, Item, OrderBy,
+            Query, Return, Select,
+        },
+        token::{constant_time_eq, random_hex, sha256_hex},
+    },
+};
+
+// every key starts with it, so leaked keys are easy to recognise
+const KEY_PREFIX: &str = "ufora";
+
+// what an api key may do, a key can only reach the routes of its scopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiScope {
+    // profiles of other users, e.g. of applicants
+    ProfilesRead,
+    PostsWrite,
+    BlogsRead,
+    BlogsWrite,
+}
+
+// the routes open to api keys, every other route refuses them
+// (scope, method, path with :params)
+const SCOPED_ROUTES: [(ApiScope, Method, &str); 8] = [
+    (
+        ApiScope::ProfilesRead,
+        Method::GET,
+        "/api/profile/retrieveProfile",
+    ),
+    (
+        ApiScope::ProfilesRead,
+        Method::GET,
+        "/api/profile/allProfiles",
+    ),
+    (ApiScope::PostsWrite, Method::POST, "/api/post/create"),
+    (
+        ApiScope::PostsWrite,
+        Method::DELETE,
+        "/api/post/delete/:post_id",
+    ),
+    (ApiScope::BlogsRead, Method::GET, "/api/blog/get"),
+    (ApiScope::BlogsWrite, Method::POST, "/api/blog/create"),
+    (
+        ApiScope::BlogsWrite,
+        Method::PUT,
+        "/api/blog/update/:blog_id",
+    ),
+    (
+        ApiScope::BlogsWrite,
+        Method::DELETE,
+        "/api/blog/delete/:blog_id",
+    ),
+];
+
+impl ApiScope {
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "profiles:read" => Some(ApiScope::ProfilesRead),
+            "posts:write" => Some(ApiScope::PostsWrite),
+            "blogs:read" => Some(ApiScope::BlogsRead),
+            "blogs:write" => Some(ApiScope::BlogsWrite),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::ProfilesRead => "profiles:read",
+            ApiScope::PostsWrite => "posts:write",
+            ApiScope::BlogsRead => "blogs:read",
+            ApiScope::BlogsWrite => "blogs:write",
+        }
+    }
+
+    // the scope a request needs, none for routes api keys can not reach
+    pub fn required_for(method: &Method, path: &str) -> Option<Self> {
+        SCOPED_ROUTES
+            .iter()
+            .find(|(_, route_method, pattern)| {
+                route_method == method && path_matches(pattern, path)
+            })
+            .map(|(scope, _, _)| *scope)
+    }
+}
+
+// an api key as stored, the key itself is only known to its owner
+#[derive(Deserialize, Debug)]
+pub struct ApiKey {
+    id: Thing,
+    user: Thing,
+    name: String,
+    key_hash: String,
+    scopes: Vec<String>,
+    allowed_ips: Vec<String>,
+    created_at: Datetime,
+    last_used_at: Option<Datetime>,
+    revoked_at: Option<Datetime>,
+}
+
+// owner of a key, as far as the authentication needs it
+#[derive(Deserialize)]
+struct Owner {
+    user_type: UserType,
+    locked_by_admin: Option<bool>,
+    deactivated: Option<bool>,
+    deleted_at: Option<Datetime>,
+}
+
+impl ApiKey {
+    // creates a key for the user and returns it with the key, which is not shown again
+    // the key is ufora_<id>_<secret>
+    pub async fn create(
+        db: &Surreal<Any>,
+        user: Thing,
+        name: String,
+        scopes: Vec<ApiScope>,
+        allowed_ips: Vec<String>,
+    ) -> Result<(Self, String), AppError> {
+        if name.trim().is_empty() {
+            return Err(AppError::invalid_input("Name of the key is required"));
+        }
+
+        if scopes.is_empty() {
+            return Err(AppError::invalid_input("At least one scope is required"));
+        }
+
+        if let Some(entry) = allowed_ips
+            .iter()
+            .find(|entry| ip_range::parse(entry).is_none())
+        {
+            return Err(AppError::invalid_input(format!(
+                "`{}` is not an ip address or range",
+                entry
+            )));
+        }
+
+        let id = random_hex(8);
+        let key = format!("{}_{}_{}", KEY_PREFIX, id, random_hex(32));
+
+        let mut scopes: Vec<String> = scopes
+            .iter()
+            .map(|scope| scope.as_str().to_string())
+            .collect();
+        scopes.sort();
+        scopes.dedup();
+
+        let created: Option<Self> = get_create_query_for_an_object(
+            Item::Record(record("api_key", id)),
+            DatabaseObject {
+                keys: vec![
+                    "user".to_string(),
+                    "name".to_string(),
+                    "key_hash".to_string(),
+                    "scopes".to_string(),
+                    "allowed_ips".to_string(),
+                ],
+                values: vec![
+                    user.into(),
+                    name.trim().into(),
+                    sha256_hex(&key).into(),
+                    to_value(&scopes)?,
+                    to_value(&allowed_ips)?,
+                ],
+            },
+            Return::After,
+        )
+        .execute(db)
+        .await?
+        .take(0)?;
+
+        let created = created.ok_or(AppError::internal("api key could not be created"))?;
+
+        Ok((created, key))
+    }
+
+    // keys of the user that have not been revoked, the newest first
+    pub async fn list_for(db: &Surreal<Any>, user: Thing) -> Result<Vec<Self>, AppError> {
+        let keys: Vec<Self> = Select::from("api_key")
+            .where_(field("user").eq(user).and(field("revoked_at").is_none()))
+            .order_by(OrderBy::Descending(vec!["created_at".to_string()]))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        Ok(keys)
+    }
+
+    // the key stops working right away
+    pub async fn revoke(db: &Surreal<Any>, user: Thing, id: String) -> Result<(), AppError> {
+        let api_key: Option<Thing> = Select::from_record(record("api_key", id))
+            .value("id")
+            .where_(field("user").eq(user).and(field("revoked_at").is_none()))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let api_key = api_key.ok_or(AppError::not_found("API key not found"))?;
+
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(api_key)
+            .push(" SET revoked_at = time::now()");
+        query.execute(db).await?.check()?;
+
+        Ok(())
+    }
+
+    // checks a key sent with a request and returns it with the user type of its owner
+    // a key of a locked, deactivated or deleted account is refused like a revoked one
+    pub async fn authenticate(
+        db: &Surreal<Any>,
+        key: &str,
+        ip: Option<&str>,
+    ) -> Result<(Self, UserType), AppError> {
+        let invalid = || {
+            AppError::from(AuthenticationError::InvalidToken {
+                msg: "Invalid API key".to_string(),
+            })
+        };
+
+        let id = key
+            .strip_prefix(KEY_PREFIX)
+            .and_then(|rest| rest.strip_prefix('_'))
+            .and_then(|rest| rest.split_once('_'))
+            .map(|(id, _)| id.to_string())
+            .ok_or_else(invalid)?;
+
+        let api_key: Option<Self> = Select::from_record(record("api_key", id))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let api_key = api_key
+            .filter(|api_key| {
+                api_key.revoked_at.is_none()
+                    && constant_time_eq(sha256_hex(key).as_bytes(), api_key.key_hash.as_bytes())
+            })
+            .ok_or_else(invalid)?;
+
+        let owner: Option<Owner> = Select::from_record(api_key.user.clone())
+            .fields(&["user_type", "locked_by_admin", "deactivated", "deleted_at"])
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let owner = owner
+            .filter(|owner| {
+                !owner.locked_by_admin.unwrap_or(false)
+                    && !owner.deactivated.unwrap_or(false)
+                    && owner.deleted_at.is_none()
+            })
+            .ok_or_else(invalid)?;
+
+        if !api_key.allows_ip(ip) {
+            return Err(AuthenticationError::InvalidToken {
+                msg: "This API key can not be used from this address".to_string(),
+            }
+            .into());
+        }
+
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(api_key.id.clone())
+            .push(" SET last_used_at = time::now()");
+        query.execute(db).await?.check()?;
+
+        Ok((api_key, owner.user_type))
+    }
+
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|granted| granted == scope.as_str())
+    }
+
+    // every address when the allowlist is empty
+    fn allows_ip(&self, ip: Option<&str>) -> bool {
+        if self.allowed_ips.is_empty() {
+            return true;
+        }
+
+        let Some(ip) = ip.and_then(|ip| ip.parse::<IpAddr>().ok()) else {
+            return false;
+        };
+
+        ip_range::contains(&self.allowed_ips, ip)
+    }
+
+    pub fn get_id(&self) -> String {
+        self.id.id.to_raw()
+    }
+
+    pub fn get_user(&self) -> Thing {
+        self.user.clone()
+    }
+
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn get_scopes(&self) -> Vec<String> {
+        self.scopes.clone()
+    }
+
+    pub fn get_allowed_ips(&self) -> Vec<String> {
+        self.allowed_ips.clone()
+    }
+
+    pub fn get_created_at(&self) -> Datetime {
+        self.created_at.clone()
+    }
+
+    pub fn get_last_used_at(&self) -> Option<Datetime> {
+        self.last_used_at.clone()
+    }
+}
+
+// a route pattern matches a path segment by segment, :params match any segment
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+
+    pattern.len() == path.len()
+        && pattern.iter().zip(path.iter()).all(|(expected, segment)| {
+            (expected.starts_with(':') && !segment.is_empty()) || expected == segment
+        })
+}
@@ -1,18 +1,25 @@
 use std::{sync::Arc, vec};
 
-use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
 use validator::Validate;
 
+use crate::errors::{authorization_errors::AuthorizationError, AppError};
 use crate::services::query_builder::{
-    get_delete_query_with_conditions, get_relate_query_with_content, get_select_query, Column,
-    Expression, ExpressionConnector, Item, Return,
+    field, get_create_query_for_an_object, get_delete_query_for_specific_record,
+    get_delete_query_with_conditions, get_relate_query_with_content,
+    get_update_query_for_merge_or_content, graph, new_record, record, to_value, transaction, Item,
+    Return, Select, UpdateOperator,
 };
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct Blog {
+    #[serde(skip_serializing_if = "Option::is_none")]
     id: Option<Thing>,
     title: String,
     content: BlogContent,
+    // set while the account of the creator is deactivated, kept when the blog is updated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -48,11 +55,29 @@ impl Blog {
             id: None,
             title: blog_title,
             content: blog_content,
+            hidden: None,
         }
     }
 
-    pub async fn get_blog_by_id(db: Arc<Surreal<Client>>, blog_id: String) -> Option<Self> {
-        db.select(("blog", blog_id)).await.unwrap()
+    // the blogs of a deactivated account are only found by their creator
+    pub async fn get_blog_by_id(
+        db: Arc<Surreal<Any>>,
+        blog_id: String,
+        viewer: Thing,
+    ) -> Result<Option<Self>, AppError> {
+        let blog: Option<Self> = Select::from_record(record("blog", blog_id))
+            .where_(
+                field("hidden").ne(true).or(graph()
+                    .in_("create_blog")
+                    .from("user")
+                    .field()
+                    .contains(viewer)),
+            )
+            .execute(&db)
+            .await?
+            .take(0)?;
+
+        Ok(blog)
     }
 
     pub fn get_blog_content(&self) -> &BlogContent {
@@ -75,179 +100,117 @@ impl Blog {
         &self.id
     }
 
-    pub async fn save(&self, db: Arc<Surreal<Client>>, user: Option<Thing>) -> Result<(), String> {
-        match user.clone() {
-            None => {
-                println!("Error: {:?}", "No user provided");
-                return Err(format!("{:?}", "User details cannot be found"));
-            }
-            Some(_) => {}
-        }
+    pub async fn save(&self, db: Arc<Surreal<Any>>, user: Option<Thing>) -> Result<(), AppError> {
+        let user = user.ok_or(AppError::invalid_input("User details cannot be found"))?;
 
-        let response: Result<Vec<Self>, surrealdb::Error> =
-            db.create("blog").content(self).await;
+        // the id is chosen up front so the blog can be linked to its user in the same transaction
+        let blog_id = new_record("blog");
 
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Err(format!("{:?}", e));
-            }
-            Ok(_) => {}
-        }
-
-        let blog = response.unwrap();
-
-        // match blog {
-        //     None => {
-        //         println!("Error: {:?}", "No blog returned");
-        //         return Err(format!("{:?}", "No blog returned"));
-        //     }
-        //     Some(blog) => {
-        //         Self::relate_user_with_blog(db.clone(), blog.id.unwrap(), user.unwrap()).await?;
-        //     }
-        // }
-        if blog.len() == 0 {
-            println!("Error: {:?}", "No blog returned");
-            return Err(format!("{:?}", "No blog returned"));
-        } else {
-            Self::relate_user_with_blog(db.clone(), blog.get(0).unwrap().clone().id.unwrap(), user.unwrap()).await?;
-        }
-
-        Ok(())
-    }
+        let create_query = get_create_query_for_an_object(
+            Item::Record(blog_id.clone()),
+            to_value(self)?,
+            Return::NONE,
+        );
 
-    async fn relate_user_with_blog(
-        db: Arc<Surreal<Client>>,
-        blog_id: Thing,
-        user_id: Thing,
-    ) -> Result<(), String> {
-        let query =
-            get_relate_query_with_content(user_id, blog_id, "create_blog".to_string(), None);
+        let relate_query =
+            get_relate_query_with_content(user, blog_id, "create_blog".to_string(), None);
 
-        let response = db.query(query).await;
+        transaction(vec![create_query, relate_query])
+            .execute_transaction(&db)
+            .await?;
 
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-            Ok(_) => Ok(()),
-        }
+        Ok(())
     }
 
     pub async fn get_blogs_by_user_id(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<Vec<Self>, String> {
-        let query = get_select_query(
-            Item::Record {
-                tb: user_id.tb,
-                id: user_id.id.to_string(),
-            },
-            Column::Specific(vec!["->create_blog->blog.* as blogs".to_string()]),
-            None,
-            None,
-            None,
-            None,
-            None,
-        );
-
-        let response = db.query(query).await;
+    ) -> Result<Vec<Self>, AppError> {
+        let query = Select::from_record(user_id)
+            .field_as(graph().out("create_blog").to("blog").all(), "blogs")
+            .build();
 
         #[derive(serde::Deserialize, serde::Serialize, Debug)]
         struct Blogs {
             blogs: Vec<Blog>,
         }
 
-        match response {
-            Ok(mut response) => {
-                let blogs: Result<Option<Blogs>, surrealdb::Error> = response.take(0);
-                match blogs {
-                    Ok(Some(blogs)) => Ok(blogs.blogs),
-                    Ok(None) => {
-                        return Err("No blogs found".to_string());
-                    }
-                    Err(e) => {
-                        return Err(format!("{:?}", e.to_string()));
-                    }
-                }
-            }
-            Err(e) => {
-                return Err(format!("{:?}", e.to_string()));
-            }
-        }
+        let blogs: Option<Blogs> = query.execute(&db).await?.take(0)?;
+
+        blogs
+            .map(|blogs| blogs.blogs)
+            .ok_or(AppError::not_found("No blogs found"))
     }
 
     pub async fn delete_a_blog_belongs_to_user(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         blog_id: String,
         user_id: Thing,
-    ) -> Result<(), String> {
-        let condition = vec![(
-            Expression::EdgeExpression(
-                "<-create_blog<-(user WHERE id = ".to_string() + &user_id.to_string() + ")",
-            ),
-            ExpressionConnector::End,
-        )];
-
+    ) -> Result<(), AppError> {
         let query = get_delete_query_with_conditions(
-            "blog:".to_string() + &blog_id,
-            condition,
+            Item::Record(record("blog", blog_id)),
+            graph()
+                .in_("create_blog")
+                .from("user")
+                .field()
+                .contains(user_id),
             Some(Return::Before),
         );
 
-        let response = db.query(query).await;
+        // a record that does not exist is returned as NONE
+        let blog: Option<Self> = query.execute(&db).await?.take(0)?;
 
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-            Ok(mut response) => {
-                let blog: Result<Vec<Self>, surrealdb::Error> = response.take(0);
-                if blog.unwrap().len() == 0 {
-                    Err("Blog with given id was not found".to_string())
-                } else {
-                    Ok(())
-                }
-            }
+        match blog {
+            None => Err(AppError::not_found("Blog with given id was not found")),
+            Some(_) => Ok(()),
+        }
+    }
+
+    // removes a blog whoever created it, used by administrators
+    pub async fn delete_blog_by_id(db: Arc<Surreal<Any>>, blog_id: Thing) -> Result<(), AppError> {
+        let query = get_delete_query_for_specific_record(blog_id.tb, blog_id.id.to_raw());
+
+        // a record that does not exist is returned as NONE
+        let blog: Option<Self> = query.execute(&db).await?.take(0)?;
+
+        match blog {
+            None => Err(AppError::not_found("Blog with given id was not found")),
+            Some(_) => Ok(()),
         }
     }
 
     pub async fn update_blog_of_user_by_id(
         &self,
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<(), String> {
-        let blog_json_string = serde_json::to_string(self).unwrap();
-
-        let update_query = "UPDATE ".to_string()
-            + &self.get_blog_id().as_ref().unwrap().to_string()
-            + " CONTENT "
-            + &blog_json_string
-            + " WHERE <-create_blog<-( user WHERE id = "
-            + &user_id.to_string()
-            + " )";
-
-        let response = db.query(update_query).await;
-
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e.to_string()))
-            }
-            Ok(mut response) => {
-                let blog: Result<Option<Self>, surrealdb::Error> = response.take(0);
-                match blog {
-                    Ok(Some(_)) => Ok(()),
-                    Ok(None) => {
-                        return Err("You don't have access to edit this blog".to_string());
-                    }
-                    Err(e) => {
-                        return Err(format!("{:?}", e.to_string()));
-                    }
-                }
+    ) -> Result<(), AppError> {
+        let blog_id = self
+            .get_blog_id()
+            .as_ref()
+            .ok_or(AppError::invalid_input("Blog id is required"))?;
+
+        let update_query = get_update_query_for_merge_or_content(
+            Item::Record(blog_id.clone()),
+            UpdateOperator::Content,
+            to_value(self)?,
+            Some(
+                graph()
+                    .in_("create_blog")
+                    .from("user")
+                    .field()
+                    .contains(user_id),
+            ),
+            None,
+        );
+
+        let blog: Vec<Self> = update_query.execute(&db).await?.take(0)?;
+
+        match blog.len() {
+            0 => Err(AuthorizationError::AccessDeniedError {
+                msg: "You don't have access to edit this blog".to_string(),
             }
+            .into()),
+            _ => Ok(()),
         }
     }
 }
@@ -1,19 +1,19 @@
 use std::sync::Arc;
 
 use axum::Json;
-use reqwest::StatusCode;
-use simple_collection_macros::bmap;
 use surrealdb::{
-    engine::remote::ws::Client,
-    sql::{
-        statements::{CreateStatement, SelectStatement},
-        Array, Cond, Data, Datetime, Expression, Field, Fields, Ident, Idiom, Object, Output, Part,
-        Table, Thing, Value, Values,
-    },
+    engine::any::Any,
+    sql::{Datetime, Thing, Value},
     Surreal,
 };
 
-use crate::services::query_builder::{get_select_query, Column, Item};
+use crate::{
+    errors::{database_errors::DatabaseError, AppError},
+    services::query_builder::{
+        field, get_create_query_for_an_object, get_relate_query_with_content, graph, new_record,
+        transaction, DatabaseObject, Item, Query, Return, Select,
+    },
+};
 
 use super::user::User;
 
@@ -101,133 +101,76 @@ impl PersonalChat {
     pub async fn create_chat_query(
         &self,
         claim: crate::models::user_claim::Claim,
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         Json(people): Json<People>,
-    ) -> Result<CreateStatement, StatusCode> {
+    ) -> Result<Query, AppError> {
         let chatmadewith =
-            User::get_user_by_email_or_username(db.clone(), None, people.chatwith).await;
+            User::get_user_by_email_or_username(db.clone(), None, people.chatwith).await?;
 
         let chatmadebyid = claim.get_surrealdb_thing();
-        let chatmadewithid = chatmadewith.unwrap().get_id();
-
-        let getifchat = SelectStatement {
-            expr: Fields(vec![Field::All], true),
-            what: Values(vec![Value::Table(Table("personalchat".to_string()))]),
-            // omit: None,
-            // only: false,
-            // explain: false,
-            // with: None,
-            // check chatmadeby an chatmadewith
-            cond: Some(Cond(Value::Array(Array(vec![
-                Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident("chatmadeby".to_string()))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Thing(chatmadebyid.clone()),
-                })),
-                Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident("chatmadewith".to_string()))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Thing(chatmadewithid.clone()),
-                })),
-            ])))),
-
-            //     Expression(Box::from(Expression {
-            //     l: Value::Idiom(Idiom(vec![Part::Field(Ident("chatmadeby".to_string()))])),
-            //     o: surrealdb::sql::Operator::Equal,
-            //     r: Value::Thing(chatmadewithid.clone()),
-            // }))
-            group: None,
-            order: None,
-            limit: None,
-            start: None,
-            fetch: None,
-            omit: None,
-            only: false,
-            explain: None,
-            with: None,
-            version: None,
-            split: None,
-            timeout: None,
-            parallel: false,
-        };
-
-        println!("getifchat: {:?}", getifchat);
-
-        let response = db.query(getifchat).await;
-
-        match response {
-            Ok(mut response) => {
-                let chat: Result<Option<PersonalChat>, surrealdb::Error> = response.take(0);
-                match chat {
-                    Ok(Some(_)) => {
-                        return Err(StatusCode::BAD_REQUEST);
-                    }
-                    Ok(None) => {}
-                    Err(_) => {
-                        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-                    }
-                }
-            }
-            Err(_) => {
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        let chatmadewithid = chatmadewith.get_id();
+
+        let existing_chat = Select::from("personalchat")
+            .where_(
+                field("chatmadeby")
+                    .eq(chatmadebyid.clone())
+                    .and(field("chatmadewith").eq(chatmadewithid.clone())),
+            )
+            .limit(1)
+            .build();
+
+        let chat: Option<PersonalChat> = existing_chat.execute(&db).await?.take(0)?;
+
+        if chat.is_some() {
+            return Err(DatabaseError::DuplicateEntryError {
+                msg: "Chat already exists".to_string(),
             }
+            .into());
         }
 
-        Ok(CreateStatement {
-            what: Values(vec![Value::Table(Table("personalchat".to_string()))]),
-            data: Some(Data::ContentExpression(Value::Object(Object(bmap!(
-                "chatmadeby".to_string() => Value::Thing(chatmadebyid),
-                "chatmadewith".to_string() => Value::Thing(chatmadewithid),
-
-                "messages".to_string() => Value::Array(Array(vec![])),
-            ))))),
-            only: false,
-            output: Some(Output::Null),
-            timeout: None,
-            parallel: false,
-        })
+        // the id is chosen up front so the chat can be linked to its creator in the same transaction
+        let chat_id = new_record("personalchat");
+
+        let create_query = get_create_query_for_an_object(
+            Item::Record(chat_id.clone()),
+            DatabaseObject {
+                keys: vec![
+                    "chatmadeby".to_string(),
+                    "chatmadewith".to_string(),
+                    "messages".to_string(),
+                ],
+                values: vec![
+                    chatmadebyid.clone().into(),
+                    chatmadewithid.into(),
+                    Vec::<Value>::new().into(),
+                ],
+            },
+            Return::NONE,
+        );
+
+        let relate_query =
+            get_relate_query_with_content(chatmadebyid, chat_id, "create_chat".to_string(), None);
+
+        Ok(transaction(vec![create_query, relate_query]))
     }
 
     pub async fn get_chats_by_user_id(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<Vec<Self>, String> {
-        let query = get_select_query(
-            Item::Record {
-                tb: user_id.tb,
-                id: user_id.id.to_string(),
-            },
-            Column::Specific(vec!["->create_chat->personalchat   .* as chats".to_string()]),
-            None,
-            None,
-            None,
-            None,
-            None,
-        );
-
-        let response = db.query(query).await;
+    ) -> Result<Vec<Self>, AppError> {
+        let query = Select::from_record(user_id)
+            .field_as(graph().out("create_chat").to("personalchat").all(), "chats")
+            .build();
 
         #[derive(serde::Deserialize, serde::Serialize, Debug)]
         struct Chats {
             chats: Vec<PersonalChat>,
         }
 
-        match response {
-            Ok(mut response) => {
-                let chats: Result<Option<Chats>, surrealdb::Error> = response.take(0);
-                match chats {
-                    Ok(Some(chats)) => Ok(chats.chats),
-                    Ok(None) => {
-                        return Err("No chats found".to_string());
-                    }
-                    Err(e) => {
-                        return Err(format!("{:?}", e.to_string()));
-                    }
-                }
-            }
-            Err(e) => {
-                return Err(format!("{:?}", e.to_string()));
-            }
-        }
+        let chats: Option<Chats> = query.execute(&db).await?.take(0)?;
+
+        chats
+            .map(|chats| chats.chats)
+            .ok_or(AppError::not_found("No chats found"))
     }
 }
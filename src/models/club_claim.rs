@@ -2,6 +2,8 @@ use async_trait::async_trait;
 use axum::{extract::FromRequestParts, http::request::Parts};
 use surrealdb::sql::{Id, Thing};
 
+use crate::errors::{authentication_errors::AuthenticationError, AppError};
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct ClubClaim {
     pub club_id: String,
@@ -38,10 +40,16 @@ impl ClubClaim {
 
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for ClubClaim {
-    type Rejection = String;
+    type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, String> {
-        let claim = parts.extensions.get::<ClubClaim>().ok_or("Missing claim")?;
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, AppError> {
+        let claim =
+            parts
+                .extensions
+                .get::<ClubClaim>()
+                .ok_or(AuthenticationError::InvalidToken {
+                    msg: "Missing claim".to_string(),
+                })?;
 
         Ok(claim.to_owned())
     }
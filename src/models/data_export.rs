@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing, Value},
+    Surreal,
+};
+
+use crate::{
+    errors::AppError,
+    models::{
+        blog::Blog, chat::ChatMessage, post::Post, project::Project, session::Session, user::User,
+    },
+    services::query_builder::{
+        field, get_create_query_for_an_object, new_record, record, to_value, DatabaseObject, Item,
+        OrderBy, Query, Return, Select,
+    },
+};
+
+// an archive can be downloaded for this long after it is ready
+const EXPORT_LIFETIME_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportStatus {
+    Pending,
+    Ready,
+    Failed,
+}
+
+// a request of a user for a copy of their data, collected in the background
+#[derive(Deserialize, Debug)]
+pub struct DataExport {
+    id: Thing,
+    status: ExportStatus,
+    created_at: Datetime,
+    completed_at: Option<Datetime>,
+    expires_at: Datetime,
+}
+
+// everything tied to a user, downloaded as a single json document
+#[derive(Serialize)]
+struct Archive {
+    exported_at: DateTime<Utc>,
+    // the user record without the password hash
+    user: serde_json::Value,
+    // with their comments and reactions
+    posts: Vec<Post>,
+    blogs: Vec<Blog>,
+    projects: Vec<Project>,
+    // personal chats the user started or was added to
+    chats: Vec<Chat>,
+    club_memberships: Vec<ClubMembership>,
+    login_history: Vec<Login>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Chat {
+    id: Thing,
+    chatmadeby: Thing,
+    chatmadewith: Thing,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+#[derive(Serialize)]
+struct ClubMembership {
+    club: Thing,
+    username: String,
+    role: String,
+}
+
+#[derive(Serialize)]
+struct Login {
+    user_agent: Option<String>,
+    ip: Option<String>,
+    // club account the login was for
+    club: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+impl DataExport {
+    // records the request and collects the archive in the background
+    // a request that is still being collected is returned instead of starting another one
+    pub async fn start(db: Arc<Surreal<Any>>, user: Thing) -> Result<Self, AppError> {
+        let pending: Option<Self> = Select::from("data_export")
+            .where_(
+                field("user")
+                    .eq(user.clone())
+                    .and(field("status").eq("pending")),
+            )
+            .limit(1)
+            .execute(&db)
+            .await?
+            .take(0)?;
+
+        if let Some(pending) = pending {
+            return Ok(pending);
+        }
+
+        let id = new_record("data_export");
+
+        let created: Option<Self> = get_create_query_for_an_object(
+            Item::Record(id.clone()),
+            DatabaseObject {
+                keys: vec![
+                    "user".to_string(),
+                    "status".to_string(),
+                    "expires_at".to_string(),
+                ],
+                values: vec![
+                    user.clone().into(),
+                    to_value(ExportStatus::Pending)?,
+                    Datetime::from(Utc::now() + Duration::days(EXPORT_LIFETIME_DAYS)).into(),
+                ],
+            },
+            Return::After,
+        )
+        .execute(&db)
+        .await?
+        .take(0)?;
+
+        let export = created.ok_or(AppError::internal("data export could not be created"))?;
+
+        tokio::spawn(collect(db, id, user));
+
+        Ok(export)
+    }
+
+    // an export of the user, exports of other users are not found
+    pub async fn of(db: &Surreal<Any>, user: Thing, export_id: String) -> Result<Self, AppError> {
+        let export: Option<Self> = Select::from_record(record("data_export", export_id))
+            .where_(field("user").eq(user))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        export.ok_or(AppError::not_found("Data export not found"))
+    }
+
+    // the archive as the json text it was stored as
+    pub async fn archive(&self, db: &Surreal<Any>) -> Result<String, AppError> {
+        if self.status != ExportStatus::Ready {
+            return Err(AppError::invalid_input("The data export is not ready yet"));
+        }
+
+        let archive: Option<String> = Select::from_record(self.id.clone())
+            .value("archive")
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        archive.ok_or(AppError::not_found("Data export not found"))
+    }
+
+    pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+        Query::raw("DELETE data_export WHERE expires_at < time::now()")
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> String {
+        self.id.id.to_raw()
+    }
+
+    pub fn get_status(&self) -> ExportStatus {
+        self.status
+    }
+
+    pub fn get_created_at(&self) -> DateTime<Utc> {
+        self.created_at.0
+    }
+
+    pub fn get_completed_at(&self) -> Option<DateTime<Utc>> {
+        self.completed_at
+            .as_ref()
+            .map(|completed_at| completed_at.0)
+    }
+
+    pub fn get_expires_at(&self) -> DateTime<Utc> {
+        self.expires_at.0
+    }
+}
+
+// background job of an export, a failure is recorded on the export and logged
+async fn collect(db: Arc<Surreal<Any>>, export: Thing, user: Thing) {
+    let (status, archive) = match build_archive(db.clone(), user).await {
+        Ok(archive) => (ExportStatus::Ready, Some(archive)),
+        Err(e) => {
+            tracing::warn!("could not collect the data export {}: {:?}", export, e);
+            (ExportStatus::Failed, None)
+        }
+    };
+
+    if let Err(e) = complete(&db, export.clone(), status, archive).await {
+        tracing::warn!("could not store the data export {}: {:?}", export, e);
+    }
+}
+
+async fn complete(
+    db: &Surreal<Any>,
+    export: Thing,
+    status: ExportStatus,
+    archive: Option<String>,
+) -> Result<(), AppError> {
+    let mut query = Query::raw("UPDATE ");
+    query
+        .push_value(export)
+        .push(" SET status = ")
+        .push_value(to_value(status)?)
+        .push(", completed_at = time::now(), archive = ")
+        .push_value(archive.map(Value::from).unwrap_or(Value::None));
+
+    query.execute(db).await?.check()?;
+
+    Ok(())
+}
+
+async fn build_archive(db: Arc<Surreal<Any>>, user_id: Thing) -> Result<String, AppError> {
+    let user = User::get_user_by_id(db.clone(), user_id.id.to_raw()).await?;
+
+    let mut user_record = serde_json::to_value(&user)
+        .map_err(|e| AppError::internal(format!("user could not be exported: {}", e)))?;
+    if let Some(user_record) = user_record.as_object_mut() {
+        user_record.remove("password");
+    }
+
+    let chats: Vec<Chat> = Select::from("personalchat")
+        .fields(&["id", "chatmadeby", "chatmadewith", "messages"])
+        .where_(
+            field("chatmadeby")
+                .eq(user_id.clone())
+                .or(field("chatmadewith").eq(user_id.clone())),
+        )
+        .execute(&db)
+        .await?
+        .take(0)?;
+
+    let clubs: Vec<User> = Select::from("user")
+        .where_(field("officials.*.user_id").contains(user_id.clone()))
+        .order_by(OrderBy::Ascending(vec!["username".to_string()]))
+        .execute(&db)
+        .await?
+        .take(0)?;
+
+    let club_memberships = clubs
+        .into_iter()
+        .filter_map(|club| {
+            club.get_club_role(&user_id).map(|role| ClubMembership {
+                club: club.get_id(),
+                username: club.get_user_username(),
+                role,
+            })
+        })
+        .collect();
+
+    let login_history = Session::history_for(&db, user_id.clone())
+        .await?
+        .into_iter()
+        .map(|session| Login {
+            user_agent: session.get_user_agent(),
+            ip: session.get_ip(),
+            club: session.get_club(),
+            created_at: session.get_created_at().0,
+            last_seen: session.get_last_seen().0,
+        })
+        .collect();
+
+    let archive = Archive {
+        exported_at: Utc::now(),
+        user: user_record,
+        posts: Post::get_post_by_user_id(db.clone(), user_id.clone()).await?,
+        blogs: Blog::get_blogs_by_user_id(db.clone(), user_id.clone()).await?,
+        projects: Project::get_projects_by_user_id(db.clone(), user_id.clone()).await?,
+        chats,
+        club_memberships,
+        login_history,
+    };
+
+    serde_json::to_string(&archive)
+        .map_err(|e| AppError::internal(format!("data export could not be serialized: {}", e)))
+}
@@ -0,0 +1,219 @@
+use serde::Deserialize;
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    errors::AppError,
+    models::{revoked_token::RevokedToken, session::Session},
+    services::query_builder::{
+        field, get_create_query_for_an_object, new_record, record, transaction, DatabaseObject,
+        Item, Query, Return, Select,
+    },
+};
+
+// a change of the email address of a user, kept so that the old address can undo it
+#[derive(Deserialize, Debug)]
+pub struct EmailChange {
+    id: Thing,
+    user: Thing,
+    old_email: String,
+    new_email: String,
+    reverted_at: Option<Datetime>,
+}
+
+// addresses of a user, the pending one is waiting for its otp
+#[derive(Deserialize)]
+struct Addresses {
+    email: String,
+    pending_email: Option<String>,
+}
+
+impl EmailChange {
+    // remembers the new address until its otp is confirmed, the current one stays in use until then
+    pub async fn request(db: &Surreal<Any>, user: Thing, new_email: &str) -> Result<(), AppError> {
+        let addresses = addresses_of(db, user.clone()).await?;
+
+        if addresses.email == new_email {
+            return Err(AppError::invalid_input(
+                "This is already the email address of the account",
+            ));
+        }
+
+        ensure_available(db, user.clone(), new_email).await?;
+
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(user)
+            .push(" SET pending_email = ")
+            .push_value(new_email);
+        query.execute(db).await?.check()?;
+
+        Ok(())
+    }
+
+    // the address waiting for its otp
+    pub async fn pending_email(db: &Surreal<Any>, user: Thing) -> Result<String, AppError> {
+        addresses_of(db, user)
+            .await?
+            .pending_email
+            .ok_or(AppError::not_found(
+                "No change of the email address has been requested",
+            ))
+    }
+
+    // switches the user to the new address once its otp is confirmed, the address is verified by it
+    pub async fn confirm(
+        db: &Surreal<Any>,
+        user: Thing,
+        new_email: String,
+    ) -> Result<Self, AppError> {
+        let addresses = addresses_of(db, user.clone()).await?;
+
+        // taken by another account since the change was requested
+        ensure_available(db, user.clone(), &new_email).await?;
+
+        let id = new_record("email_change");
+
+        let change = get_create_query_for_an_object(
+            Item::Record(id.clone()),
+            DatabaseObject {
+                keys: vec![
+                    "user".to_string(),
+                    "old_email".to_string(),
+                    "new_email".to_string(),
+                ],
+                values: vec![
+                    user.clone().into(),
+                    addresses.email.clone().into(),
+                    new_email.clone().into(),
+                ],
+            },
+            Return::NONE,
+        );
+
+        let mut account = Query::raw("UPDATE ");
+        account
+            .push_value(user.clone())
+            .push(" SET email = ")
+            .push_value(new_email.clone())
+            .push(", email_verification_flag = true, pending_email = NONE");
+
+        transaction(vec![change, account])
+            .execute_transaction(db)
+            .await?;
+
+        Ok(Self {
+            id,
+            user,
+            old_email: addresses.email,
+            new_email,
+            reverted_at: None,
+        })
+    }
+
+    pub async fn find(db: &Surreal<Any>, id: String) -> Result<Self, AppError> {
+        let change: Option<Self> = Select::from_record(record("email_change", id))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        change.ok_or(AppError::not_found("Email change not found"))
+    }
+
+    // gives the user the old address back, also after further changes
+    // the old address was verified by receiving the link
+    // whoever made the change may still be signed in, every session and token of the user ends
+    pub async fn revert(&self, db: &Surreal<Any>) -> Result<(), AppError> {
+        if self.reverted_at.is_some() {
+            return Err(already_reverted());
+        }
+
+        ensure_available(db, self.user.clone(), &self.old_email).await?;
+
+        // of two concurrent reverts only one finds the change not yet reverted
+        let mut change = Query::raw("LET $reverted = (UPDATE ");
+        change
+            .push_value(self.id.clone())
+            .push(" SET reverted_at = time::now() WHERE reverted_at = NONE RETURN AFTER); ")
+            .push("IF array::len($reverted) = 0 { THROW ")
+            .push_value(ALREADY_REVERTED)
+            .push(" }");
+
+        let mut account = Query::raw("UPDATE ");
+        account
+            .push_value(self.user.clone())
+            .push(" SET email = ")
+            .push_value(self.old_email.clone())
+            .push(", email_verification_flag = true, pending_email = NONE");
+
+        transaction(vec![change, account])
+            .execute_transaction(db)
+            .await
+            .map_err(|e| match e.to_string().contains(ALREADY_REVERTED) {
+                true => already_reverted(),
+                false => e.into(),
+            })?;
+
+        RevokedToken::revoke_all_issued_to(db, self.user.clone()).await?;
+        Session::end_all(db, self.user.clone()).await?;
+
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> String {
+        self.id.id.to_raw()
+    }
+
+    pub fn get_user(&self) -> Thing {
+        self.user.clone()
+    }
+
+    pub fn is_reverted(&self) -> bool {
+        self.reverted_at.is_some()
+    }
+
+    pub fn get_old_email(&self) -> &str {
+        &self.old_email
+    }
+
+    pub fn get_new_email(&self) -> &str {
+        &self.new_email
+    }
+}
+
+async fn addresses_of(db: &Surreal<Any>, user: Thing) -> Result<Addresses, AppError> {
+    let addresses: Option<Addresses> = Select::from_record(user)
+        .fields(&["email", "pending_email"])
+        .execute(db)
+        .await?
+        .take(0)?;
+
+    addresses.ok_or(AppError::not_found("User could not be found"))
+}
+
+// an address can only belong to one account
+async fn ensure_available(db: &Surreal<Any>, user: Thing, email: &str) -> Result<(), AppError> {
+    let owner: Option<Thing> = Select::from("user")
+        .value("id")
+        .where_(field("email").eq(email).and(field("id").ne(user)))
+        .limit(1)
+        .execute(db)
+        .await?
+        .take(0)?;
+
+    match owner {
+        Some(_) => Err(AppError::invalid_input(
+            "This email address is already in use",
+        )),
+        None => Ok(()),
+    }
+}
+
+const ALREADY_REVERTED: &str = "This change of the email address has already been undone";
+
+fn already_reverted() -> AppError {
+    AppError::invalid_input(ALREADY_REVERTED)
+}
@@ -0,0 +1,195 @@
+use chrono::Utc;
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    errors::AppError,
+    services::{
+        query_builder::{
+            get_create_query_for_an_object, transaction, DatabaseObject, Item, Query, Return,
+            Select,
+        },
+        token::{random_hex, sha256_hex},
+        totp,
+    },
+};
+
+// recovery codes handed out when two-factor authentication is enabled
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// two-factor authentication state of a user, kept on the user record
+#[derive(Deserialize, Debug)]
+pub struct Mfa {
+    mfa_enabled: Option<bool>,
+    mfa_secret: Option<String>,
+    mfa_last_step: Option<i64>,
+}
+
+// what the authenticator app needs to generate codes
+pub struct Enrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+impl Mfa {
+    pub async fn of(db: &Surreal<Any>, user: Thing) -> Result<Self, AppError> {
+        let mfa: Option<Self> = Select::from_record(user)
+            .fields(&["mfa_enabled", "mfa_secret", "mfa_last_step"])
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        mfa.ok_or(AppError::not_found("User could not be found"))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mfa_enabled.unwrap_or(false) && self.mfa_secret.is_some()
+    }
+
+    // stores a new secret, it is only used once a code generated from it is confirmed
+    // starting again replaces a secret that was never confirmed
+    pub async fn begin_enrollment(
+        db: &Surreal<Any>,
+        user: Thing,
+        account: &str,
+    ) -> Result<Enrollment, AppError> {
+        if Self::of(db, user.clone()).await?.is_enabled() {
+            return Err(already_enabled());
+        }
+
+        let secret = totp::generate_secret();
+
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(user)
+            .push(" SET mfa_enabled = false, mfa_last_step = NONE, mfa_secret = ")
+            .push_value(secret.clone());
+        query.execute(db).await?.check()?;
+
+        Ok(Enrollment {
+            otpauth_uri: totp::otpauth_uri(account, &secret),
+            secret,
+        })
+    }
+
+    // enables two-factor authentication with the first code from the app
+    // returns the recovery codes, they are not shown again
+    pub async fn confirm_enrollment(
+        db: &Surreal<Any>,
+        user: Thing,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let mfa = Self::of(db, user.clone()).await?;
+
+        if mfa.is_enabled() {
+            return Err(already_enabled());
+        }
+
+        let secret = mfa.mfa_secret.ok_or(AppError::invalid_input(
+            "Two-factor authentication enrollment has not been started",
+        ))?;
+
+        let step = totp::verify(&secret, code.trim(), Utc::now().timestamp()).ok_or(
+            AppError::invalid_input("Invalid two-factor authentication code"),
+        )?;
+
+        let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| {
+                let code = random_hex(5);
+                format!("{}-{}", &code[..5], &code[5..])
+            })
+            .collect();
+
+        let mut enable = Query::raw("UPDATE ");
+        enable
+            .push_value(user.clone())
+            .push(" SET mfa_enabled = true, mfa_last_step = ")
+            .push_value(step);
+
+        let mut queries = vec![enable, remove_recovery_codes_query(user.clone())];
+        queries.extend(codes.iter().map(|code| {
+            get_create_query_for_an_object(
+                Item::Table("recovery_code".to_string()),
+                DatabaseObject {
+                    keys: vec!["user".to_string(), "code_hash".to_string()],
+                    values: vec![user.clone().into(), hash_recovery_code(code).into()],
+                },
+                Return::NONE,
+            )
+        }));
+
+        transaction(queries).execute_transaction(db).await?;
+
+        Ok(codes)
+    }
+
+    // checks a code from the app or an unused recovery code, neither can be used twice
+    pub async fn verify(db: &Surreal<Any>, user: Thing, code: &str) -> Result<bool, AppError> {
+        let mfa = Self::of(db, user.clone()).await?;
+
+        let secret = match (mfa.is_enabled(), mfa.mfa_secret) {
+            (true, Some(secret)) => secret,
+            _ => return Ok(false),
+        };
+
+        let code = code.trim();
+
+        if let Some(step) = totp::verify(&secret, code, Utc::now().timestamp()) {
+            if mfa.mfa_last_step.is_some_and(|last_step| step <= last_step) {
+                return Ok(false);
+            }
+
+            // only one of two concurrent logins with the same code gets the step
+            let mut query = Query::raw("UPDATE ");
+            query
+                .push_value(user)
+                .push(" SET mfa_last_step = ")
+                .push_value(step)
+                .push(" WHERE mfa_last_step = NONE OR mfa_last_step < ")
+                .push_value(step);
+
+            let accepted: Option<Self> = query.execute(db).await?.take(0)?;
+            return Ok(accepted.is_some());
+        }
+
+        // recovery codes are single use as well
+        let mut query = Query::raw("UPDATE recovery_code SET used_at = time::now() WHERE user = ");
+        query
+            .push_value(user)
+            .push(" AND code_hash = ")
+            .push_value(hash_recovery_code(code))
+            .push(" AND used_at = NONE");
+
+        let used: Vec<serde_json::Value> = query.execute(db).await?.take(0)?;
+        Ok(!used.is_empty())
+    }
+
+    // turns two-factor authentication off and drops the recovery codes
+    pub async fn disable(db: &Surreal<Any>, user: Thing) -> Result<(), AppError> {
+        let mut disable = Query::raw("UPDATE ");
+        disable
+            .push_value(user.clone())
+            .push(" SET mfa_enabled = false, mfa_secret = NONE, mfa_last_step = NONE");
+
+        transaction(vec![disable, remove_recovery_codes_query(user)])
+            .execute_transaction(db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn remove_recovery_codes_query(user: Thing) -> Query {
+    let mut query = Query::raw("DELETE recovery_code WHERE user = ");
+    query.push_value(user);
+    query
+}
+
+// codes are accepted with or without the dash and in any case
+fn hash_recovery_code(code: &str) -> String {
+    sha256_hex(&code.replace('-', "").to_lowercase())
+}
+
+fn already_enabled() -> AppError {
+    AppError::invalid_input("Two-factor authentication is already enabled")
+}
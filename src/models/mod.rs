@@ -1,8 +1,18 @@
 // pub mod company;
 pub mod user;
 pub mod user_claim;
+pub mod user_type;
 pub mod post;
 pub mod blog;
 pub mod project;
 pub mod chat;
 pub mod club_claim;
+pub mod refresh_token;
+pub mod revoked_token;
+pub mod session;
+pub mod mfa;
+pub mod admin;
+pub mod account;
+pub mod data_export;
+pub mod email_change;
+pub mod api_key;
@@ -2,11 +2,18 @@ use core::fmt;
 use std::{str::FromStr, sync::Arc};
 
 use chrono::prelude::*;
-use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Thing, Value},
+    Surreal,
+};
 
+use crate::errors::AppError;
 use crate::services::query_builder::{
     get_create_query_for_an_object, get_delete_query_for_specific_record,
-    get_relate_query_with_content, get_select_query, Column, DatabaseObject, Item, Return,
+    get_relate_query_with_content, get_update_query_with_set_opertor, graph, new_record, to_value,
+    transaction, DatabaseObject, Item, Query, Return, Select, UpdateObjectForSet,
+    UpdateSetOperator,
 };
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -20,10 +27,14 @@ pub struct Post {
     time: String,
 }
 
+// stored in lowercase, the variant names are still accepted in requests
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
 pub enum AccessLevel {
+    #[serde(rename = "public", alias = "Public")]
     Public,
+    #[serde(rename = "friends", alias = "Friends")]
     Friends,
+    #[serde(rename = "only me", alias = "OnlyMe")]
     OnlyMe,
 }
 
@@ -82,20 +93,25 @@ impl Post {
         }
     }
 
-    pub async fn save(&self, db: Arc<Surreal<Client>>, user_id: Thing) -> Result<(), String> {
+    pub async fn save(&self, db: Arc<Surreal<Any>>, user_id: Thing) -> Result<(), AppError> {
         match (
             self.caption.clone(),
             self.access_level.clone(),
             self.content.clone(),
         ) {
             (None, _, _) | (_, None, _) | (_, _, None) => {
-                return Err("caption, access_level and content are required".to_string());
+                return Err(AppError::invalid_input(
+                    "caption, access_level and content are required",
+                ));
             }
             (_, _, _) => {}
         }
 
+        // the id is chosen up front so the post can be linked to its user in the same transaction
+        let post_id = new_record("post");
+
         let post_create_query = get_create_query_for_an_object(
-            Item::Table("post".to_string()),
+            Item::Record(post_id.clone()),
             DatabaseObject {
                 keys: vec![
                     "caption".to_string(),
@@ -107,173 +123,129 @@ impl Post {
                     "time".to_string(),
                 ],
                 values: vec![
-                    "'".to_string() + &self.caption.clone().unwrap() + "'",
-                    "'".to_string() + &self.access_level.clone().unwrap().to_string() + "'",
-                    "'".to_string() + &self.content.clone().unwrap() + "'",
-                    "[]".to_string(),
-                    "[]".to_string(),
-                    "None".to_string(),
-                    "'".to_string() + self.time.clone().as_ref() + "'",
+                    Value::from(self.caption.clone().unwrap()),
+                    Value::from(self.access_level.clone().unwrap().to_string()),
+                    Value::from(self.content.clone().unwrap()),
+                    Value::from(Vec::<Value>::new()),
+                    Value::from(Vec::<Value>::new()),
+                    Value::None,
+                    Value::from(self.time.clone()),
                 ],
             },
-            Return::Fields {
-                fields: vec!["id".to_string()],
-            },
+            Return::NONE,
         );
 
-        let response = db.query(post_create_query).await;
-
-        #[derive(serde::Deserialize, Debug)]
-        struct RecordID {
-            id: Thing,
-        }
-
-        let post_id = match response {
-            Ok(mut response) => {
-                let record_id: Result<Option<RecordID>, surrealdb::Error> = response.take(0);
-                match record_id {
-                    Ok(Some(record_id)) => record_id.id,
-                    Ok(None) => {
-                        return Err("Post could not be created".to_string());
-                    }
-                    Err(e) => {
-                        return Err(format!("{:?}", e.to_string()));
-                    }
-                }
-            }
-            Err(e) => {
-                return Err(format!("{:?}", e.to_string()));
-            }
-        };
-
-        self.link_user_with_post(db.clone(), user_id, post_id).await
-    }
-
-    async fn link_user_with_post(
-        &self,
-        db: Arc<Surreal<Client>>,
-        user_id: Thing,
-        post_id: Thing,
-    ) -> Result<(), String> {
-        let relate_query =
+        let link_query =
             get_relate_query_with_content(user_id, post_id, "create_post".to_string(), None);
 
-        let response = db.query(relate_query).await;
+        transaction(vec![post_create_query, link_query])
+            .execute_transaction(&db)
+            .await?;
 
-        match response {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("{:?}", e.to_string())),
-        }
+        Ok(())
     }
 
     pub async fn get_post_by_user_id(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<Vec<Self>, String> {
-        let query = get_select_query(
-            Item::Record {
-                tb: user_id.tb,
-                id: user_id.id.to_string(),
-            },
-            Column::Specific(vec!["->create_post->post.* as posts".to_string()]),
-            None,
-            None,
-            None,
-            None,
-            None,
-        );
-
-        let response = db.query(query).await;
+    ) -> Result<Vec<Self>, AppError> {
+        let query = Select::from_record(user_id)
+            .field_as(graph().out("create_post").to("post").all(), "posts")
+            .build();
 
         #[derive(serde::Deserialize, Debug)]
         struct Posts {
             pub posts: Vec<Post>,
         }
 
-        match response {
-            Ok(mut response) => {
-                let posts: Result<Option<Posts>, surrealdb::Error> = response.take(0);
-                match posts {
-                    Ok(posts) => Ok(posts.unwrap().posts),
-                    Err(e) => Err(format!("{:?}", e.to_string())),
-                }
-            }
-            Err(e) => Err(format!("{:?}", e.to_string())),
-        }
+        let posts: Option<Posts> = query.execute(&db).await?.take(0)?;
+
+        Ok(posts.map(|posts| posts.posts).unwrap_or_default())
     }
 
-    pub async fn delete_post_by_id(
-        db: Arc<Surreal<Client>>,
-        post_id: String,
-    ) -> Result<(), String> {
-        let delete_query = get_delete_query_for_specific_record("post".to_string(), post_id);
-
-        let response = db.query(delete_query).await;
-
-        match response {
-            Ok(mut response) => {
-                let post: Result<Vec<Post>, surrealdb::Error> = response.take(0);
-                if post.unwrap().len() == 0 {
-                    return Err("Post not found".to_string());
-                } else {
-                    Ok(())
-                }
-            }
-            Err(e) => Err(format!("{:?}", e.to_string())),
+    pub async fn delete_post_by_id(db: Arc<Surreal<Any>>, post_id: Thing) -> Result<(), AppError> {
+        let delete_query = get_delete_query_for_specific_record(post_id.tb, post_id.id.to_raw());
+
+        // a record that does not exist is returned as NONE
+        let post: Option<Post> = delete_query.execute(&db).await?.take(0)?;
+
+        match post {
+            None => Err(AppError::not_found("Post not found")),
+            Some(_) => Ok(()),
         }
     }
 
     pub async fn add_or_remove_reaction(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         post_id: Thing,
         user_id: Thing,
-    ) -> Result<(), String> {
-        let query = "LET $reactions = (SELECT VALUE reactions FROM ".to_string()
-            + &post_id.to_string()
-            + " ); "
-            // Check whether user has already reacted to the post or not
-            + "IF $reactions CONTAINS "
-            + &user_id.to_string()
-            // If user has already reacted, then remove the reaction
-            + " THEN (UPDATE "
-            + &post_id.to_string()
-            + " SET reactions -= [ "
-            + &user_id.to_string()
-            + " ])"
-            // If user has not reacted, then add the reaction
-            + " ELSE (UPDATE "
-            + &post_id.to_string()
-            + " SET reactions += [ "
-            + &user_id.to_string()
-            + " ])"
-            + " END;";
-
-        let response = db.query(query).await;
-
-        match response {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("{:?}", e.to_string())),
-        }
+    ) -> Result<(), AppError> {
+        let mut query = Query::new();
+        let post = query.bind(post_id);
+        let user = query.bind(user_id);
+
+        // Check whether user has already reacted to the post or not
+        // If user has already reacted, then remove the reaction, otherwise add it
+        query.push(&format!(
+            "IF {post}.reactions CONTAINS {user} \
+             THEN (UPDATE {post} SET reactions -= {user}) \
+             ELSE (UPDATE {post} SET reactions += {user}) \
+             END;"
+        ));
+
+        query.execute(&db).await?.check()?;
+
+        Ok(())
     }
 
     pub async fn add_a_comment(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
+        post_id: Thing,
+        comment: Comment,
+    ) -> Result<(), AppError> {
+        let query = get_update_query_with_set_opertor(
+            Item::Record(post_id),
+            UpdateObjectForSet {
+                fields: vec![(
+                    "comments".to_string(),
+                    UpdateSetOperator::Add,
+                    to_value(&comment)?,
+                )],
+            },
+            None,
+            None,
+        );
+
+        query.execute(&db).await?.check()?;
+
+        Ok(())
+    }
+
+    // whether the post has a comment with the given id
+    pub async fn has_comment(
+        db: Arc<Surreal<Any>>,
         post_id: Thing,
-        comment : Comment,
-    ) -> Result<(),String> {
-        let comment_json_string = serde_json::to_string(&comment).unwrap();
+        comment_id: &str,
+    ) -> Result<bool, AppError> {
+        let post: Option<Post> = Select::from_record(post_id).execute(&db).await?.take(0)?;
+        let post = post.ok_or(AppError::not_found("Post not found"))?;
 
-        let query = "UPDATE ".to_string()
-            + &post_id.to_string()
-            + " SET comments += [ "
-            + &comment_json_string
-            + " ]";
+        Ok(post.comments.iter().any(|comment| comment.id == comment_id))
+    }
 
-        let response = db.query(query).await;
+    pub async fn delete_a_comment(
+        db: Arc<Surreal<Any>>,
+        post_id: Thing,
+        comment_id: String,
+    ) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE ");
+        query.push_value(post_id);
+        query.push(" SET comments = comments[WHERE id != ");
+        query.push_value(comment_id);
+        query.push("]");
 
-        match response {
-            Ok(_) => Ok(()),
-            Err(e) => Err(format!("{:?}", e.to_string())),
-        }
+        query.execute(&db).await?.check()?;
+
+        Ok(())
     }
 }
@@ -1,17 +1,23 @@
 use std::sync::Arc;
 
-use surrealdb::{engine::remote::ws::Client, sql::Thing, Surreal};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
 
+use crate::errors::AppError;
 use crate::services::query_builder::{
-    get_delete_query_with_conditions, get_relate_query_with_content, get_select_query, Column,
-    Expression, ExpressionConnector, Item, Return,
+    field, get_create_query_for_an_object, get_delete_query_with_conditions,
+    get_relate_query_with_content, get_update_query_for_merge_or_content, graph, new_record,
+    record, to_value, transaction, Item, Return, Select, UpdateOperator,
 };
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub struct Project {
+    #[serde(skip_serializing_if = "Option::is_none")]
     id: Option<Thing>,
     title: String,
     content: ProjectContent,
+    // set while the account of the creator is deactivated, kept when the project is updated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hidden: Option<bool>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -47,11 +53,29 @@ impl Project {
             id: None,
             title: project_title,
             content: project_content,
+            hidden: None,
         }
     }
 
-    pub async fn get_project_by_id(db: Arc<Surreal<Client>>, project_id: String) -> Option<Self> {
-        db.select(("project", project_id)).await.unwrap()
+    // the projects of a deactivated account are only found by their creator
+    pub async fn get_project_by_id(
+        db: Arc<Surreal<Any>>,
+        project_id: String,
+        viewer: Thing,
+    ) -> Result<Option<Self>, AppError> {
+        let project: Option<Self> = Select::from_record(record("project", project_id))
+            .where_(
+                field("hidden").ne(true).or(graph()
+                    .in_("create_project")
+                    .from("user")
+                    .field()
+                    .contains(viewer)),
+            )
+            .execute(&db)
+            .await?
+            .take(0)?;
+
+        Ok(project)
     }
 
     pub fn get_project_content(&self) -> &ProjectContent {
@@ -74,174 +98,103 @@ impl Project {
         &self.id
     }
 
-    pub async fn save(&self, db: Arc<Surreal<Client>>, user: Option<Thing>) -> Result<(), String> {
-        match user.clone() {
-            None => {
-                println!("Error: {:?}", "No user provided");
-                return Err(format!("{:?}", "User details cannot be found"));
-            }
-            Some(_) => {}
-        }
+    pub async fn save(&self, db: Arc<Surreal<Any>>, user: Option<Thing>) -> Result<(), AppError> {
+        let user = user.ok_or(AppError::invalid_input("User details cannot be found"))?;
 
-        let response: Result<Vec<Self>, surrealdb::Error> =
-            db.create("project").content(self).await;
+        // the id is chosen up front so the project can be linked to its user in the same transaction
+        let project_id = new_record("project");
 
-        match response {
-            Ok(_) => {}
-            Err(e) => {
-                println!("Error: {:?}", e);
-                return Err(format!("{:?}", e));
-            }
-        }
+        let create_query = get_create_query_for_an_object(
+            Item::Record(project_id.clone()),
+            to_value(self)?,
+            Return::NONE,
+        );
 
-        let project = response.unwrap();
+        let relate_query =
+            get_relate_query_with_content(user, project_id, "create_project".to_string(), None);
 
-        if project.len() == 0 {
-            println!("Error: {:?}", "No blog returned");
-                return Err(format!("{:?}", "No blog returned"));
-        }
-        else {
-            Self::relate_user_with_project(db.clone(), project.get(0).unwrap().clone().id.unwrap(), user.unwrap()).await?;
-        }
+        transaction(vec![create_query, relate_query])
+            .execute_transaction(&db)
+            .await?;
 
         Ok(())
     }
 
-    async fn relate_user_with_project(
-        db: Arc<Surreal<Client>>,
-        project_id: Thing,
-        user_id: Thing,
-    ) -> Result<(), String> {
-        let query =
-            get_relate_query_with_content(user_id, project_id, "create_project".to_string(), None);
-
-        let response = db.query(query).await;
-
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-            Ok(_) => Ok(()),
-        }
-    }
-
     pub async fn get_projects_by_user_id(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<Vec<Self>, String> {
-        let query = get_select_query(
-            Item::Record {
-                tb: user_id.tb,
-                id: user_id.id.to_string(),
-            },
-            Column::Specific(vec!["->create_project->project.* as projects".to_string()]),
-            None,
-            None,
-            None,
-            None,
-            None,
-        );
-
-        let response = db.query(query).await;
+    ) -> Result<Vec<Self>, AppError> {
+        let query = Select::from_record(user_id)
+            .field_as(
+                graph().out("create_project").to("project").all(),
+                "projects",
+            )
+            .build();
 
         #[derive(serde::Deserialize, serde::Serialize, Debug)]
         struct Projects {
             projects: Vec<Project>,
         }
 
-        match response {
-            Ok(mut response) => {
-                let projects: Result<Option<Projects>, surrealdb::Error> = response.take(0);
-
-                match projects {
-                    Ok(projects) => Ok(projects.unwrap().projects),
-                    Err(e) => {
-                        println!("Error: {:?}", e);
-                        Err(format!("{:?}", e))
-                    }
-                }
-            }
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-        }
+        let projects: Option<Projects> = query.execute(&db).await?.take(0)?;
+
+        Ok(projects
+            .map(|projects| projects.projects)
+            .unwrap_or_default())
     }
 
     pub async fn delete_a_project_belongs_to_user(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         project_id: String,
         user_id: Thing,
-    ) -> Result<(), String> {
-        let condition = vec![(
-            Expression::EdgeExpression(
-                "<-create_project<-(user WHERE id = ".to_string() + &user_id.to_string() + ")",
-            ),
-            ExpressionConnector::End,
-        )];
-
-        let delete_query = get_delete_query_with_conditions(
-            "project:".to_string() + &project_id,
-            condition,
+    ) -> Result<(), AppError> {
+        let query = get_delete_query_with_conditions(
+            Item::Record(record("project", project_id)),
+            graph()
+                .in_("create_project")
+                .from("user")
+                .field()
+                .contains(user_id),
             Some(Return::Before),
         );
 
-        let response = db.query(delete_query).await;
-
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-            Ok(mut response) => {
-                let project: Result<Vec<Self>, surrealdb::Error> = response.take(0);
-                match project {
-                    Ok(project) => {
-                        if project.len() == 0 {
-                            return Err("Project with given id not found".to_string());
-                        }
-                        Ok(())
-                    }
-                    Err(e) => {
-                        println!("Error: {:?}", e);
-                        Err(format!("{:?}", e))
-                    }
-                }
-            }
+        let project: Vec<Self> = query.execute(&db).await?.take(0)?;
+
+        match project.len() {
+            0 => Err(AppError::not_found("Project with given id was not found")),
+            _ => Ok(()),
         }
     }
 
     pub async fn update_project_of_user_by_id(
         &self,
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         user_id: Thing,
-    ) -> Result<(), String> {
-        let project_json_string = serde_json::to_string(self).unwrap();
-
-        let update_query = "UPDATE ".to_string()
-            + &self.get_project_id().as_ref().unwrap().to_string()
-            + " CONTENT "
-            + &project_json_string
-            + " WHERE <-create_project<-( user WHERE id = "
-            + &user_id.to_string()
-            + " )";
-
-        let response = db.query(update_query).await;
-
-        match response {
-            Err(e) => {
-                println!("Error: {:?}", e);
-                Err(format!("{:?}", e))
-            }
-            Ok(mut response) => {
-                let project: Result<Vec<Self>, surrealdb::Error> = response.take(0);
-                if project.unwrap().len() == 0 {
-                    Err("Project with given id was not found".to_string())
-                } else {
-                    Ok(())
-                }
-            }
+    ) -> Result<(), AppError> {
+        let project_id = self
+            .get_project_id()
+            .as_ref()
+            .ok_or(AppError::invalid_input("Project id is required"))?;
+
+        let update_query = get_update_query_for_merge_or_content(
+            Item::Record(project_id.clone()),
+            UpdateOperator::Content,
+            to_value(self)?,
+            Some(
+                graph()
+                    .in_("create_project")
+                    .from("user")
+                    .field()
+                    .contains(user_id),
+            ),
+            None,
+        );
+
+        let project: Vec<Self> = update_query.execute(&db).await?.take(0)?;
+
+        match project.len() {
+            0 => Err(AppError::not_found("Project with given id was not found")),
+            _ => Ok(()),
         }
     }
 }
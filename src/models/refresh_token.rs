@@ -0,0 +1,185 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing, Value},
+    Surreal,
+};
+
+use crate::{
+    config::JwtConfig,
+    errors::{authentication_errors::AuthenticationError, AppError},
+    services::{
+        query_builder::{
+            field, get_create_query_for_an_object, DatabaseObject, Item, Query, Return, Select,
+        },
+        token::{random_hex, sha256_hex},
+    },
+};
+
+// opaque token handed out next to the access jwt and exchanged for a new pair at /api/token/refresh
+// only the sha256 hash is stored, every token can be used once
+// tokens rotated from the same login share a family, using a token twice revokes the whole family
+// the family is the id of the session the tokens were issued for
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RefreshToken {
+    id: Thing,
+    family: String,
+    user: Thing,
+    club: Option<Thing>,
+    expires_at: Datetime,
+    used_at: Option<Datetime>,
+    revoked: bool,
+}
+
+// result of a successful rotation
+pub struct Rotation {
+    // user the token was issued to
+    pub user: Thing,
+    // club the user signed in to, for tokens issued by a club login
+    pub club: Option<Thing>,
+    pub family: String,
+    pub refresh_token: String,
+}
+
+impl RefreshToken {
+    // issues the first token of a family
+    pub async fn issue(
+        db: &Surreal<Any>,
+        config: &JwtConfig,
+        user: Thing,
+        club: Option<Thing>,
+        family: String,
+    ) -> Result<String, AppError> {
+        Self::create(db, config, user, club, family).await
+    }
+
+    // exchanges a token for a new one of the same family
+    pub async fn rotate(
+        db: &Surreal<Any>,
+        config: &JwtConfig,
+        token: &str,
+    ) -> Result<Rotation, AppError> {
+        let stored: Option<Self> = Select::from("refresh_token")
+            .where_(field("token_hash").eq(sha256_hex(token)))
+            .limit(1)
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let stored = stored.ok_or(AuthenticationError::InvalidToken {
+            msg: "Invalid refresh token".to_string(),
+        })?;
+
+        if stored.revoked || stored.used_at.is_some() {
+            Self::revoke_family(db, &stored.family).await?;
+            return Err(reused());
+        }
+
+        if stored.expires_at.0 < Utc::now() {
+            return Err(AuthenticationError::ExpiredToken {
+                msg: "Refresh token has expired, please log in again".to_string(),
+            }
+            .into());
+        }
+
+        // marks the token as used, only one of two concurrent refreshes gets it
+        let mut claim = Query::raw("UPDATE ");
+        claim
+            .push_value(stored.id.clone())
+            .push(" SET used_at = time::now() WHERE used_at = NONE AND revoked = false");
+
+        let claimed: Option<Self> = claim.execute(db).await?.take(0)?;
+
+        if claimed.is_none() {
+            Self::revoke_family(db, &stored.family).await?;
+            return Err(reused());
+        }
+
+        let refresh_token = Self::create(
+            db,
+            config,
+            stored.user.clone(),
+            stored.club.clone(),
+            stored.family.clone(),
+        )
+        .await?;
+
+        Ok(Rotation {
+            user: stored.user,
+            club: stored.club,
+            family: stored.family,
+            refresh_token,
+        })
+    }
+
+    pub async fn revoke_family(db: &Surreal<Any>, family: &str) -> Result<(), AppError> {
+        Self::revoke_family_query(family)
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub fn revoke_family_query(family: &str) -> Query {
+        let mut query = Query::raw("UPDATE refresh_token SET revoked = true WHERE family = ");
+        query.push_value(family);
+        query
+    }
+
+    pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+        Query::raw("DELETE refresh_token WHERE expires_at < time::now()")
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn create(
+        db: &Surreal<Any>,
+        config: &JwtConfig,
+        user: Thing,
+        club: Option<Thing>,
+        family: String,
+    ) -> Result<String, AppError> {
+        let token = random_hex(32);
+        let expires_at = Utc::now() + Duration::seconds(config.refresh_lifetime_seconds);
+
+        get_create_query_for_an_object(
+            Item::Table("refresh_token".to_string()),
+            DatabaseObject {
+                keys: vec![
+                    "token_hash".to_string(),
+                    "family".to_string(),
+                    "user".to_string(),
+                    "club".to_string(),
+                    "expires_at".to_string(),
+                    "revoked".to_string(),
+                ],
+                values: vec![
+                    sha256_hex(&token).into(),
+                    family.into(),
+                    user.into(),
+                    club.map(Value::from).unwrap_or(Value::None),
+                    Datetime::from(expires_at).into(),
+                    false.into(),
+                ],
+            },
+            Return::NONE,
+        )
+        .execute(db)
+        .await?
+        .check()?;
+
+        Ok(token)
+    }
+}
+
+fn reused() -> AppError {
+    AuthenticationError::InvalidToken {
+        msg: "Refresh token has already been used, please log in again".to_string(),
+    }
+    .into()
+}
@@ -0,0 +1,114 @@
+use chrono::{TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    errors::AppError,
+    services::query_builder::{
+        get_update_query_for_merge_or_content, record, to_value, transaction, Item, Query, Select,
+        UpdateOperator,
+    },
+};
+
+// access token revoked before it expired, the record id is the jti of the token
+// the revocation is only kept until the token would have expired anyway
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RevokedToken {
+    expires_at: Datetime,
+}
+
+impl RevokedToken {
+    pub async fn revoke(db: &Surreal<Any>, jti: String, expires_at: usize) -> Result<(), AppError> {
+        let expires_at = Utc
+            .timestamp_opt(expires_at as i64, 0)
+            .single()
+            .ok_or(AppError::internal("Token has an invalid expiry"))?;
+
+        get_update_query_for_merge_or_content(
+            Item::Record(record("revoked_token", jti)),
+            UpdateOperator::Content,
+            to_value(RevokedToken {
+                expires_at: Datetime::from(expires_at),
+            })?,
+            None,
+            None,
+        )
+        .execute(db)
+        .await?
+        .check()?;
+
+        Ok(())
+    }
+
+    // whether the token was revoked itself, its session has ended
+    // or it was issued before its user logged out everywhere
+    pub async fn is_revoked(
+        db: &Surreal<Any>,
+        jti: String,
+        user: Thing,
+        session_id: String,
+        issued_at: usize,
+    ) -> Result<bool, AppError> {
+        let mut query = Select::from_record(record("revoked_token", jti))
+            .value("id")
+            .build();
+        query.append(
+            Select::from_record(user)
+                .value("tokens_valid_after")
+                .build(),
+        );
+        query.append(
+            Select::from_record(record("session", session_id))
+                .value("revoked")
+                .build(),
+        );
+
+        let mut response = query.execute(db).await?;
+        let revoked: Vec<Thing> = response.take(0)?;
+        let valid_after: Vec<Option<Datetime>> = response.take(1)?;
+        let session_revoked: Vec<bool> = response.take(2)?;
+
+        // iat only has whole seconds, tokens issued in the second of the logout are rejected as well
+        let issued_before_logout = valid_after
+            .into_iter()
+            .flatten()
+            .next()
+            .is_some_and(|valid_after| (issued_at as i64) <= valid_after.0.timestamp());
+
+        // a session that does not exist anymore has expired
+        let session_ended = session_revoked.first().copied().unwrap_or(true);
+
+        Ok(!revoked.is_empty() || issued_before_logout || session_ended)
+    }
+
+    // invalidates every access and refresh token issued to the user until now
+    pub async fn revoke_all_issued_to(db: &Surreal<Any>, user: Thing) -> Result<(), AppError> {
+        let mut valid_after = Query::raw("UPDATE ");
+        valid_after
+            .push_value(user.clone())
+            .push(" SET tokens_valid_after = time::now()");
+
+        let mut refresh_tokens =
+            Query::raw("UPDATE refresh_token SET revoked = true WHERE user = ");
+        refresh_tokens.push_value(user);
+
+        transaction(vec![valid_after, refresh_tokens])
+            .execute_transaction(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+        Query::raw("DELETE revoked_token WHERE expires_at < time::now()")
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+}
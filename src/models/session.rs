@@ -0,0 +1,288 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{header, request::Parts},
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing, Value},
+    Surreal,
+};
+
+use crate::{
+    config::{Config, JwtConfig},
+    errors::AppError,
+    models::refresh_token::RefreshToken,
+    services::{
+        ip_range,
+        query_builder::{
+            field, get_create_query_for_an_object, record, transaction, DatabaseObject, Item,
+            OrderBy, Query, Return, Select,
+        },
+        token::random_hex,
+    },
+};
+
+// a login on one device, the id is shared by the refresh token family and the sid claim
+// of the access tokens issued for it, ending the session revokes all of them
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Session {
+    id: Thing,
+    user: Thing,
+    club: Option<Thing>,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    created_at: Datetime,
+    last_seen: Datetime,
+}
+
+// device a request comes from, as far as the request tells
+pub struct Device {
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Device
+where
+    S: Send + Sync,
+    Arc<Config>: FromRef<S>,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, AppError> {
+        let config = Arc::<Config>::from_ref(state);
+
+        // every x-forwarded-for header, in the order the proxies added them
+        let forwarded_for = parts
+            .headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| {
+                client_ip(address.ip(), &forwarded_for, &config.server.trusted_proxies)
+            });
+
+        Ok(Device {
+            user_agent: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string()),
+            ip,
+        })
+    }
+}
+
+// the peer is the client, unless it is one of the trusted proxies
+// then the client is the rightmost x-forwarded-for address that is not a trusted proxy,
+// the addresses left of it were sent by the client and can be anything
+fn client_ip(peer: IpAddr, forwarded_for: &str, trusted_proxies: &[String]) -> String {
+    let mut client = peer.to_string();
+
+    if !ip_range::contains(trusted_proxies, peer) {
+        return client;
+    }
+
+    for hop in forwarded_for
+        .rsplit(',')
+        .map(str::trim)
+        .filter(|hop| !hop.is_empty())
+    {
+        match hop.parse::<IpAddr>() {
+            Ok(ip) if ip_range::contains(trusted_proxies, ip) => client = ip.to_string(),
+            // the first hop that is not a trusted proxy, a hop that is no address matches no allowlist
+            _ => return hop.to_string(),
+        }
+    }
+
+    client
+}
+
+impl Session {
+    // records a new session and returns its id
+    pub async fn start(
+        db: &Surreal<Any>,
+        config: &JwtConfig,
+        user: Thing,
+        club: Option<Thing>,
+        device: Device,
+    ) -> Result<String, AppError> {
+        let id = random_hex(16);
+
+        get_create_query_for_an_object(
+            Item::Record(record("session", id.clone())),
+            DatabaseObject {
+                keys: vec![
+                    "user".to_string(),
+                    "club".to_string(),
+                    "user_agent".to_string(),
+                    "ip".to_string(),
+                    "expires_at".to_string(),
+                ],
+                values: vec![
+                    user.into(),
+                    club.map(Value::from).unwrap_or(Value::None),
+                    device.user_agent.map(Value::from).unwrap_or(Value::None),
+                    device.ip.map(Value::from).unwrap_or(Value::None),
+                    expires_at(config).into(),
+                ],
+            },
+            Return::NONE,
+        )
+        .execute(db)
+        .await?
+        .check()?;
+
+        Ok(id)
+    }
+
+    // called when the tokens of the session are refreshed
+    pub async fn touch(db: &Surreal<Any>, config: &JwtConfig, id: &str) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(record("session", id.to_string()))
+            .push(" SET last_seen = time::now(), expires_at = ")
+            .push_value(expires_at(config));
+
+        query.execute(db).await?.check()?;
+
+        Ok(())
+    }
+
+    // sessions of the user that have not ended, the most recently used first
+    pub async fn list_for(db: &Surreal<Any>, user: Thing) -> Result<Vec<Self>, AppError> {
+        let sessions: Vec<Self> = Select::from("session")
+            .where_(
+                field("user")
+                    .eq(user)
+                    .and(field("revoked").eq(false))
+                    .and(field("expires_at").gt(Datetime::default())),
+            )
+            .order_by(OrderBy::Descending(vec!["last_seen".to_string()]))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        Ok(sessions)
+    }
+
+    // every session of the user that is still kept, ended ones included, the most recent first
+    pub async fn history_for(db: &Surreal<Any>, user: Thing) -> Result<Vec<Self>, AppError> {
+        let sessions: Vec<Self> = Select::from("session")
+            .where_(field("user").eq(user))
+            .order_by(OrderBy::Descending(vec!["created_at".to_string()]))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        Ok(sessions)
+    }
+
+    // ends a session of the user, the tokens issued for it stop working
+    pub async fn end(db: &Surreal<Any>, user: Thing, id: String) -> Result<(), AppError> {
+        let session: Option<Thing> = Select::from_record(record("session", id.clone()))
+            .value("id")
+            .where_(field("user").eq(user).and(field("revoked").eq(false)))
+            .execute(db)
+            .await?
+            .take(0)?;
+
+        let session = session.ok_or(AppError::not_found("Session not found"))?;
+
+        let mut end_session = Query::raw("UPDATE ");
+        end_session.push_value(session).push(" SET revoked = true");
+
+        transaction(vec![end_session, RefreshToken::revoke_family_query(&id)])
+            .execute_transaction(db)
+            .await?;
+
+        Ok(())
+    }
+
+    // ends every session of the user
+    pub async fn end_all(db: &Surreal<Any>, user: Thing) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE session SET revoked = true WHERE user = ");
+        query.push_value(user);
+        query.execute(db).await?.check()?;
+
+        Ok(())
+    }
+
+    // ends every session of the user but the given one, e.g. the one changing the password
+    pub async fn end_all_except(
+        db: &Surreal<Any>,
+        user: Thing,
+        kept: String,
+    ) -> Result<(), AppError> {
+        let mut end_sessions = Query::raw("UPDATE session SET revoked = true WHERE user = ");
+        end_sessions
+            .push_value(user.clone())
+            .push(" AND id != ")
+            .push_value(record("session", kept.clone()));
+
+        let mut refresh_tokens =
+            Query::raw("UPDATE refresh_token SET revoked = true WHERE user = ");
+        refresh_tokens
+            .push_value(user)
+            .push(" AND family != ")
+            .push_value(kept);
+
+        transaction(vec![end_sessions, refresh_tokens])
+            .execute_transaction(db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+        Query::raw("DELETE session WHERE expires_at < time::now()")
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> String {
+        self.id.id.to_raw()
+    }
+
+    pub fn get_club(&self) -> Option<String> {
+        self.club.as_ref().map(|club| club.id.to_raw())
+    }
+
+    pub fn get_user_agent(&self) -> Option<String> {
+        self.user_agent.clone()
+    }
+
+    pub fn get_ip(&self) -> Option<String> {
+        self.ip.clone()
+    }
+
+    pub fn get_created_at(&self) -> Datetime {
+        self.created_at.clone()
+    }
+
+    pub fn get_last_seen(&self) -> Datetime {
+        self.last_seen.clone()
+    }
+}
+
+// a session lasts as long as its refresh token
+fn expires_at(config: &JwtConfig) -> Datetime {
+    Datetime::from(Utc::now() + Duration::seconds(config.refresh_lifetime_seconds))
+}
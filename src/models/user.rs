@@ -3,28 +3,34 @@
 use std::{sync::Arc, vec};
 
 use chrono::{DateTime, NaiveDate, Utc};
-use dotenvy_macro::dotenv;
 use serde::{Deserialize, Serialize};
 use simple_collection_macros::bmap;
 
-use axum::http::StatusCode;
-
 use surrealdb::{
-    engine::remote::ws::Client,
+    engine::any::Any,
     opt::PatchOp,
-    sql::{
-        statements::{CreateStatement, SelectStatement, UpdateStatement},
-        Array, Cond, Data, Datetime, Expression, Field, Fields, Ident, Idiom, Limit, Number,
-        Object, Operator, Output, Part, Strand, Table, Thing, Value, Values,
-    },
+    sql::{Datetime, Object, Strand, Thing, Value},
     Surreal,
 };
 
+use crate::errors::{validation_errors::ValidationError, AppError};
+use crate::models::user_type::UserType;
 use crate::services::{
     password,
-    query_builder::{self, get_select_query, Column, ExpressionConnector, Item, OrderBy},
+    query_builder::{
+        self, field, get_create_query_for_an_object, get_relate_query_with_content,
+        get_update_query_for_merge_or_content, get_update_query_with_set_opertor, new_record,
+        record, to_value, transaction, Condition, DatabaseObject, Item,
+        OrderBy, Query, Return, Select, UpdateObjectForSet, UpdateOperator, UpdateSetOperator,
+    },
 };
 
+// failed logins in a row before the account is locked
+const MAX_LOGIN_ATTEMPTS: i32 = 5;
+
+// length of the first, second and every further lockout in a row
+const LOCKOUT_DURATIONS_SECONDS: [i64; 3] = [60, 5 * 60, 60 * 60];
+
 // model for user
 // __________________________________
 #[derive(Serialize, Deserialize, Default, Debug)]
@@ -33,8 +39,13 @@ pub struct User {
     name: Option<String>,
     username: Option<String>,
     password: Option<String>,
-    locked_flag: Option<bool>,
-    user_type: Option<String>,
+    // set after too many failed logins, logging in is refused until then
+    locked_until: Option<Datetime>,
+    // number of lockouts in a row, each one lasts longer
+    lockouts: Option<i32>,
+    // locked by an administrator until they unlock it
+    locked_by_admin: Option<bool>,
+    user_type: Option<UserType>,
     email: Option<String>,
     registration_date: Option<Datetime>,
     email_verification_flag: Option<bool>,
@@ -99,74 +110,77 @@ impl User {
         }
     }
 
-    pub async fn get_user_by_id(db: Arc<Surreal<Client>>, user_id: String) -> Result<Self, String> {
-        let response: Result<Option<Self>, surrealdb::Error> = db.select(("user", user_id)).await;
-        match response {
-            Ok(user) => Ok(user.unwrap()),
-            Err(e) => Err(e.to_string()),
-        }
+    pub async fn get_user_by_id(db: Arc<Surreal<Any>>, user_id: String) -> Result<Self, AppError> {
+        let user: Option<Self> = db.select(("user", user_id)).await?;
+        user.ok_or(AppError::not_found("User could not be found"))
     }
 
     // returns the surrealQl query for creating a user
     // __________________________________
-    pub async fn get_create_user_query(
-        self,
-        user_type: String,
-    ) -> Result<CreateStatement, StatusCode> {
-        match (self.username.clone(), self.password.clone()) {
-            (None, _) | (_, None) => Err(StatusCode::BAD_REQUEST)?,
-            (_, _) => {}
+    pub async fn get_create_user_query(self, user_type: UserType) -> Result<Query, AppError> {
+        match (
+            self.username.clone(),
+            self.password.clone(),
+            self.name.clone(),
+            self.email.clone(),
+        ) {
+            (None, _, _, _) | (_, None, _, _) | (_, _, None, _) | (_, _, _, None) => {
+                return Err(AppError::invalid_input(
+                    "name, username, email and password are required",
+                ));
+            }
+            (_, _, _, _) => {}
         }
 
-        Ok(CreateStatement {
-            what: Values(vec![Value::Table(Table("user".to_string()))]),
-            data: Some(Data::ContentExpression(Value::Object(Object(bmap!(
-                "username".to_string() => Value::Strand(Strand(self.username.unwrap())),
-                "name".to_string() => Value::Strand(Strand(self.name.unwrap())),
-                "password".to_string() => Value::Strand(Strand(password::hash_password(self.password.unwrap())?)),
-                "locked_flag".to_string() => Value::Bool(false),
-                "user_type".to_string() => Value::Strand(Strand(user_type)),
-                "email".to_string() => Value::Strand(Strand(self.email.unwrap())),
-                "email_verification_flag".to_string() => Value::Bool(false),
-                "invalid_login_attempts".to_string() => Value::Number(Number::Int(0)),
-                "registration_date".to_string() => Value::Datetime(Datetime::default()),
-            ))))),
-            output: Some(Output::Null),
-            only: false,
-            timeout: None,
-            parallel: false,
-        })
+        Ok(get_create_query_for_an_object(
+            Item::Table("user".to_string()),
+            DatabaseObject {
+                keys: vec![
+                    "username".to_string(),
+                    "name".to_string(),
+                    "password".to_string(),
+                    "user_type".to_string(),
+                    "email".to_string(),
+                    "email_verification_flag".to_string(),
+                    "invalid_login_attempts".to_string(),
+                    "registration_date".to_string(),
+                ],
+                values: vec![
+                    self.username.unwrap().into(),
+                    self.name.unwrap().into(),
+                    password::hash_password(self.password.unwrap())?.into(),
+                    user_type.to_string().into(),
+                    self.email.unwrap().into(),
+                    false.into(),
+                    Value::from(0),
+                    Datetime::default().into(),
+                ],
+            },
+            Return::NONE,
+        ))
     }
 
     // updates the university email verification flag
     // __________________________________
     pub async fn update_university_email_verification(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         email: String,
-    ) -> Result<(), StatusCode> {
-        let _response = db
-            .query(UpdateStatement {
-                what: Values(vec![Value::Table(Table("user".to_string()))]),
-                data: Some(Data::SetExpression(vec![(
-                    Idiom(vec![Part::Field(Ident(
-                        "university_email_verification_flag".to_string(),
-                    ))]),
-                    Operator::Equal,
-                    Value::Bool(true),
-                )])),
-                cond: Some(Cond(Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident(
-                        "university_email".to_string(),
-                    ))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Strand(Strand(email)),
-                })))),
-                output: None,
-                only: false,
-                timeout: None,
-                parallel: false,
-            })
-            .await;
+    ) -> Result<(), AppError> {
+        get_update_query_with_set_opertor(
+            Item::Table("user".to_string()),
+            UpdateObjectForSet {
+                fields: vec![(
+                    "university_email_verification_flag".to_string(),
+                    UpdateSetOperator::Equal,
+                    true.into(),
+                )],
+            },
+            Some(field("university_email").eq(email)),
+            None,
+        )
+        .execute(&db)
+        .await?
+        .check()?;
 
         Ok(())
     }
@@ -175,18 +189,20 @@ impl User {
     // __________________________________
     pub async fn update_university_details(
         user_id: Thing,
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         university: Option<String>,
         university_email: Option<String>,
-    ) -> Result<(), StatusCode> {
+    ) -> Result<(), AppError> {
         match (university.clone(), university_email.clone()) {
             (None, _) | (_, None) => {
-                return Err(StatusCode::BAD_REQUEST);
+                return Err(AppError::invalid_input(
+                    "university and university email are required",
+                ));
             }
             (_, _) => {}
         }
 
-        let response: Result<Option<serde_json::Value>, surrealdb::Error> = db
+        let _response: Option<serde_json::Value> = db
             .update(("user", user_id.id))
             .patch(PatchOp::replace("/university", university.unwrap()))
             .patch(PatchOp::replace(
@@ -197,55 +213,26 @@ impl User {
                 "/university_email_verification_flag",
                 false,
             ))
-            .await;
+            .await?;
 
-        match response {
-            Ok(_) => Ok(()),
-            Err(e) => {
-                println!("{:?} ", e);
-                Ok(())
-            }
-        }
+        Ok(())
     }
 
     // returns the user from the database
     // __________________________________
     pub async fn retrieve_user_from_database_by_username(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         username: String,
-    ) -> Result<Self, StatusCode> {
-        let mut response = db
-            .query(SelectStatement {
-                expr: Fields(vec![Field::All], true),
-                what: Values(vec![Value::Table(Table("user".to_string()))]),
-                cond: Some(Cond(Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident("username".to_string()))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Strand(Strand(username)),
-                })))),
-                group: None,
-                order: None,
-                limit: Some(Limit(Value::Number(Number::Int(1)))),
-                start: None,
-                omit: None,
-                only: false,
-                explain: None,
-                with: None,
-                fetch: None,
-                version: None,
-                split: None,
-                timeout: None,
-                parallel: false,
-            })
-            .await
-            .unwrap();
-
-        let users: Option<Self> = response.take(0).unwrap();
-
-        match users {
-            Some(user) => Ok(user),
-            None => Err(StatusCode::NOT_FOUND),
-        }
+    ) -> Result<Self, AppError> {
+        let mut response = Select::from("user")
+            .where_(field("username").eq(username))
+            .limit(1)
+            .execute(&db)
+            .await?;
+
+        let users: Option<Self> = response.take(0)?;
+
+        users.ok_or(AppError::not_found("User could not be found"))
     }
 
     // gets the stored password
@@ -254,48 +241,162 @@ impl User {
         self.password.clone()
     }
 
-    // updates the invalid login attempts and locked account
+    // counts a failed login, locks the account after too many in a row
+    // returns the time the account is locked until, if it has just been locked
     // __________________________________
-    pub async fn update_login_attempts(
-        self,
-        db: Arc<Surreal<Client>>,
-        new_invalid_login_attempts: i32,
-    ) -> () {
+    pub async fn record_failed_login(
+        &self,
+        db: Arc<Surreal<Any>>,
+    ) -> Result<Option<DateTime<Utc>>, AppError> {
         #[derive(serde::Deserialize)]
-        struct LoginAttemptUpdateResult {}
-
-        let _response: Option<LoginAttemptUpdateResult> = match new_invalid_login_attempts {
-            0..=4 => db
-                .update(("user", self.id.unwrap().id))
-                .patch(PatchOp::replace(
-                    "/invalid_login_attempts",
-                    new_invalid_login_attempts,
-                ))
-                .await
-                .unwrap_or(None),
-            5 => db
-                .update(("user", self.id.unwrap().id))
-                .patch(PatchOp::replace(
-                    "/invalid_login_attempts",
-                    new_invalid_login_attempts,
-                ))
-                .patch(PatchOp::replace("/locked_flag", true))
-                .await
-                .unwrap_or(None),
-            _ => None,
-        };
-    }
-
-    // returns whether the user is locked or not
+        struct LoginAttempts {
+            invalid_login_attempts: i32,
+            locked_until: Option<Datetime>,
+        }
+
+        // counted and locked in one statement, so of concurrent attempts only the one reaching
+        // the limit locks the account, and only once
+        // each assignment only reads fields assigned after it, i.e. the values before the update
+        let now = Utc::now();
+        let mut query = Query::raw("UPDATE ");
+        query.push_value(self.get_id()).push(" SET locked_until = ");
+
+        // longer with every lockout in a row, the last duration for all further ones
+        for (lockouts, duration) in LOCKOUT_DURATIONS_SECONDS.iter().enumerate().rev() {
+            query
+                .push("IF invalid_login_attempts + 1 >= ")
+                .push_value(MAX_LOGIN_ATTEMPTS)
+                .push(" AND (lockouts OR 0) >= ")
+                .push_value(lockouts as i64)
+                .push(" THEN ")
+                .push_value(Datetime::from(now + chrono::Duration::seconds(*duration)))
+                .push(" ELSE ");
+        }
+
+        query
+            .push("locked_until END, lockouts = IF invalid_login_attempts + 1 >= ")
+            .push_value(MAX_LOGIN_ATTEMPTS)
+            .push(" THEN (lockouts OR 0) + 1 ELSE lockouts END")
+            .push(", invalid_login_attempts = IF invalid_login_attempts + 1 >= ")
+            .push_value(MAX_LOGIN_ATTEMPTS)
+            .push(" THEN 0 ELSE invalid_login_attempts + 1 END RETURN AFTER");
+
+        let attempts: Option<LoginAttempts> = query.execute(&db).await?.take(0)?;
+        let attempts = attempts.ok_or(AppError::not_found("User could not be found"))?;
+
+        // the count starts again with the lockout
+        if attempts.invalid_login_attempts > 0 {
+            return Ok(None);
+        }
+
+        Ok(attempts.locked_until.map(|locked_until| locked_until.0))
+    }
+
+    // a successful login or an unlock starts counting from scratch
     // __________________________________
-    pub fn is_user_locked(&self) -> bool {
-        self.locked_flag.unwrap()
+    pub async fn reset_login_attempts(
+        db: Arc<Surreal<Any>>,
+        user_id: Thing,
+    ) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(user_id)
+            .push(" SET invalid_login_attempts = 0, lockouts = 0, locked_until = NONE");
+        query.execute(&db).await?.check()?;
+
+        Ok(())
+    }
+
+    // returns the time the account is locked until, none if it is not locked
+    // __________________________________
+    pub fn get_locked_until(&self) -> Option<DateTime<Utc>> {
+        self.locked_until
+            .as_ref()
+            .map(|locked_until| locked_until.0)
+            .filter(|locked_until| *locked_until > Utc::now())
+    }
+
+    // returns whether an administrator has locked the account
+    // __________________________________
+    pub fn is_locked_by_admin(&self) -> bool {
+        self.locked_by_admin.unwrap_or(false)
+    }
+
+    // locks or unlocks the account on behalf of an administrator
+    // unlocking also lifts a lockout after failed logins
+    // __________________________________
+    pub async fn set_locked_by_admin(
+        db: Arc<Surreal<Any>>,
+        user_id: Thing,
+        locked: bool,
+    ) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(user_id)
+            .push(" SET locked_by_admin = ")
+            .push_value(locked);
+
+        if !locked {
+            query.push(", invalid_login_attempts = 0, lockouts = 0, locked_until = NONE");
+        }
+
+        query.push(" RETURN id");
+
+        let updated: Option<serde_json::Value> = query.execute(&db).await?.take(0)?;
+        updated
+            .map(|_| ())
+            .ok_or(AppError::not_found("User could not be found"))
+    }
+
+    // makes an existing undergraduate or company account an administrator
+    // __________________________________
+    pub async fn promote_to_admin(db: &Surreal<Any>, username: String) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE user SET user_type = ");
+        query
+            .push_value(UserType::Admin.to_string())
+            .push(" WHERE username = ")
+            .push_value(username)
+            .push(" AND user_type INSIDE ")
+            .push_value(vec![
+                Value::from(UserType::Undergraduate.to_string()),
+                Value::from(UserType::Company.to_string()),
+            ])
+            .push(" RETURN id");
+
+        let promoted: Vec<serde_json::Value> = query.execute(db).await?.take(0)?;
+
+        match promoted.is_empty() {
+            true => Err(AppError::not_found(
+                "No undergraduate or company account with this username",
+            )),
+            false => Ok(()),
+        }
+    }
+
+    // hashes and stores a new password
+    // __________________________________
+    pub async fn set_password(
+        db: Arc<Surreal<Any>>,
+        user_id: Thing,
+        new_password: String,
+    ) -> Result<(), AppError> {
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(user_id)
+            .push(" SET password = ")
+            .push_value(password::hash_password(new_password)?)
+            .push(" RETURN id");
+
+        let updated: Option<serde_json::Value> = query.execute(&db).await?.take(0)?;
+        updated
+            .map(|_| ())
+            .ok_or(AppError::not_found("User could not be found"))
     }
 
     // returns user type
     // __________________________________
-    pub fn get_user_type(&self) -> String {
-        self.user_type.as_ref().unwrap().clone()
+    pub fn get_user_type(&self) -> Option<UserType> {
+        self.user_type
     }
 
     // returns user id
@@ -305,40 +406,42 @@ impl User {
     }
 
     pub fn get_club_officials(&self) -> Option<Vec<ClubOfficial>> {
-        if self.user_type.clone().unwrap() == "club" {
+        if self.get_user_type() == Some(UserType::Club) {
             self.officials.clone()
         } else {
             None
         }
     }
 
+    // role of the given user in this club account, none if they are not an official
+    pub fn get_club_role(&self, user_id: &Thing) -> Option<String> {
+        self.get_club_officials()?
+            .into_iter()
+            .find(|club_official| &club_official.get_user_id() == user_id)
+            .map(|club_official| club_official.get_role())
+    }
+
     // returns whether the user is verified or not
     // __________________________________
     pub async fn update_email_verification(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         email: String,
-    ) -> Result<(), StatusCode> {
-        let _response = db
-            .query(UpdateStatement {
-                what: Values(vec![Value::Table(Table("user".to_string()))]),
-                data: Some(Data::SetExpression(vec![(
-                    Idiom(vec![Part::Field(Ident(
-                        "email_verification_flag".to_string(),
-                    ))]),
-                    Operator::Equal,
-                    Value::Bool(true),
-                )])),
-                cond: Some(Cond(Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident("email".to_string()))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Strand(Strand(email)),
-                })))),
-                output: None,
-                only: false,
-                timeout: None,
-                parallel: false,
-            })
-            .await;
+    ) -> Result<(), AppError> {
+        get_update_query_with_set_opertor(
+            Item::Table("user".to_string()),
+            UpdateObjectForSet {
+                fields: vec![(
+                    "email_verification_flag".to_string(),
+                    UpdateSetOperator::Equal,
+                    true.into(),
+                )],
+            },
+            Some(field("email").eq(email)),
+            None,
+        )
+        .execute(&db)
+        .await?
+        .check()?;
 
         Ok(())
     }
@@ -346,41 +449,18 @@ impl User {
     // returns user by email
     // __________________________________
     pub async fn get_user_by_email(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         email: String,
-    ) -> Result<Self, StatusCode> {
-        let mut response = db
-            .query(SelectStatement {
-                expr: Fields(vec![Field::All], true),
-                what: Values(vec![Value::Table(Table("user".to_string()))]),
-                cond: Some(Cond(Value::Expression(Box::from(Expression::Binary {
-                    l: Value::Idiom(Idiom(vec![Part::Field(Ident("email".to_string()))])),
-                    o: surrealdb::sql::Operator::Equal,
-                    r: Value::Strand(Strand(email)),
-                })))),
-                group: None,
-                order: None,
-                limit: Some(Limit(Value::Number(Number::Int(1)))),
-                start: None,
-                omit: None,
-                only: false,
-                explain: None,
-                with: None,
-                fetch: None,
-                version: None,
-                split: None,
-                timeout: None,
-                parallel: false,
-            })
-            .await
-            .unwrap();
-
-        let users: Option<Self> = response.take(0).unwrap();
-
-        match users {
-            Some(user) => Ok(user),
-            None => Err(StatusCode::NOT_FOUND),
-        }
+    ) -> Result<Self, AppError> {
+        let mut response = Select::from("user")
+            .where_(field("email").eq(email))
+            .limit(1)
+            .execute(&db)
+            .await?;
+
+        let users: Option<Self> = response.take(0)?;
+
+        users.ok_or(AppError::not_found("User could not be found"))
     }
 
     // returns user email
@@ -404,104 +484,52 @@ impl User {
     // returns user name by email or username
     // __________________________________
     pub async fn get_user_by_email_or_username(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         email: Option<String>,
         username: Option<String>,
-    ) -> Result<Self, StatusCode> {
+    ) -> Result<Self, AppError> {
         match (email.clone(), username.clone()) {
             (None, None) => {
-                return Err(StatusCode::BAD_REQUEST);
+                return Err(AppError::invalid_input("email or username is required"));
             }
             (_, _) => {}
         }
 
-        let query = query_builder::get_select_query(
-            Item::Table("user".to_string()),
-            Column::All,
-            Some(vec![
-                (
-                    query_builder::Expression::EqualTo(
-                        "username".to_string(),
-                        format!("\"{}\"", username.unwrap_or("".to_string())),
-                    ),
-                    ExpressionConnector::Or,
-                ),
-                (
-                    query_builder::Expression::EqualTo(
-                        "email".to_string(),
-                        format!("\"{}\"", email.unwrap_or("".to_string())),
-                    ),
-                    ExpressionConnector::End,
-                ),
-            ]),
-            None,
-            None,
-            None,
-            None,
-        );
-
-        println!("{:?}", query);
-
-        let response = db
-            .query(query)
-            .await;
+        let mut response = Select::from("user")
+            .where_(
+                field("username")
+                    .eq(username.unwrap_or_default())
+                    .or(field("email").eq(email.unwrap_or_default())),
+            )
+            .limit(1)
+            .execute(&db)
+            .await?;
 
-        println!("{:?}", response);
+        let user: Option<Self> = response.take(0)?;
 
-        match response {
-            Err(_) => {
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
-            }
-            Ok(_) => {}
-        }
-
-        let mut response = response.unwrap();
-
-        let user: Option<Self> = response.take(0).unwrap();
-
-        match user {
-            Some(user) => Ok(user),
-            None => Err(StatusCode::NOT_FOUND),
-        }
+        user.ok_or(AppError::not_found("User could not be found"))
     }
 
     pub async fn get_club_account_from_email_or_name(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         email: Option<String>,
         name: Option<String>,
-    ) -> Option<Self> {
-        db.query(get_select_query(
-            Item::Table("user".to_string()),
-            Column::All,
-            Some(vec![
-                (
-                    crate::services::query_builder::Expression::EqualTo(
-                        "email".to_string(),
-                        format!("'{}'", email.unwrap()),
-                    ),
-                    ExpressionConnector::Or,
-                ),
-                (
-                    crate::services::query_builder::Expression::EqualTo(
-                        "name".to_string(),
-                        format!("'{}'", name.unwrap()),
-                    ),
-                    ExpressionConnector::End,
-                ),
-            ]),
-            None,
-            None,
-            None,
-            None,
-        ))
-        .await
-        .unwrap()
-        .take(0)
-        .unwrap()
+    ) -> Result<Option<Self>, AppError> {
+        let mut response = Select::from("user")
+            .where_(
+                field("email")
+                    .eq(email.unwrap_or_default())
+                    .or(field("name").eq(name.unwrap_or_default())),
+            )
+            .limit(1)
+            .execute(&db)
+            .await?;
+
+        Ok(response.take(0)?)
     }
 
     pub async fn create_a_club_account(
-        db: Arc<Surreal<Client>>,
+        db: Arc<Surreal<Any>>,
         username: String,
         name: String,
         email: String,
@@ -509,44 +537,60 @@ impl User {
         creator: Thing,
         club_verification_file: String,
         profile_pic: Option<String>,
-    ) -> Result<serde_json::Value, String> {
-        let create_club_query = CreateStatement {
-            what: Values(vec![Value::Table(Table("user".to_string()))]),
-            data: Some(Data::ContentExpression(Value::Object(Object(bmap!(
-                "username".to_string() => Value::Strand(Strand(username)),
-                "name".to_string() => Value::Strand(Strand(name)),
-                "user_type".to_string() => Value::Strand(Strand("club".to_string())),
-                "email".to_string() => Value::Strand(Strand(email)),
-                "email_verification_flag".to_string() => Value::Bool(false),
-                "registration_date".to_string() => Value::Datetime(Datetime::default()),
-                "club_type".to_string() => Value::Strand(Strand(club_type)),
-                "club_verification_file".to_string() => Value::Strand(Strand(club_verification_file)),
-                "club_verification_flag".to_string() => Value::Bool(false),
-                "profile_pic".to_string() => Value::Strand(Strand(profile_pic.unwrap_or("".to_string()))),
-                "officials".to_string() => Value::Array(Array (vec![Value::Object(Object(bmap!(
-                    "user_id".to_string() => Value::Thing(creator),
-                    "role".to_string() => Value::Strand(Strand("admin".to_string())),
-                )))])),
-            ))))),
-            output: Some(Output::Fields(Fields(
-                vec![Field::Single {
-                    expr: Value::Idiom(Idiom(vec![Part::Field(Ident("email".to_string()))])),
-                    alias: None,
-                }],
-                false,
-            ))),
-            only: false,
-            timeout: None,
-            parallel: false,
-        };
-
-        match db.query(create_club_query).await {
-            Ok(mut response) => {
-                let club: Option<serde_json::Value> = response.take(0).unwrap();
-                Ok(club.unwrap())
-            }
-            Err(e) => Err(e.to_string()),
-        }
+    ) -> Result<Thing, AppError> {
+        let officials = vec![ClubOfficial {
+            user_id: creator.clone(),
+            role: "admin".to_string(),
+        }];
+
+        // the id is chosen up front so the club can be linked to its creator in the same transaction
+        let club_id = new_record("user");
+
+        let create_club_query = get_create_query_for_an_object(
+            Item::Record(club_id.clone()),
+            DatabaseObject {
+                keys: vec![
+                    "username".to_string(),
+                    "name".to_string(),
+                    "user_type".to_string(),
+                    "email".to_string(),
+                    "email_verification_flag".to_string(),
+                    "registration_date".to_string(),
+                    "club_type".to_string(),
+                    "club_verification_file".to_string(),
+                    "club_verification_flag".to_string(),
+                    "profile_pic".to_string(),
+                    "officials".to_string(),
+                ],
+                values: vec![
+                    username.into(),
+                    name.into(),
+                    UserType::Club.to_string().into(),
+                    email.into(),
+                    false.into(),
+                    Datetime::default().into(),
+                    club_type.into(),
+                    club_verification_file.into(),
+                    false.into(),
+                    profile_pic.unwrap_or_default().into(),
+                    to_value(officials)?,
+                ],
+            },
+            Return::NONE,
+        );
+
+        let relate_query = get_relate_query_with_content(
+            creator,
+            club_id.clone(),
+            "create_club".to_string(),
+            None,
+        );
+
+        transaction(vec![create_club_query, relate_query])
+            .execute_transaction(&db)
+            .await?;
+
+        Ok(club_id)
     }
 }
 
@@ -554,7 +598,7 @@ impl User {
 // __________________________________
 impl Into<serde_json::Value> for User {
     fn into(self) -> serde_json::Value {
-        serde_json::to_value(self).unwrap()
+        serde_json::to_value(self).unwrap_or_default()
     }
 }
 
@@ -573,11 +617,10 @@ pub struct Profile {
 
 pub async fn update_user_profile_query(
     user_id: String,
-    user_type: String,
+    user_type: UserType,
     profile_details: Profile,
-) -> Result<UpdateStatement, StatusCode> {
-    println!("{:?}", profile_details);
-
+    map_api_key: &str,
+) -> Result<Query, AppError> {
     // fields array to pass all optional fields
     let mut fields = bmap!();
 
@@ -622,26 +665,27 @@ pub async fn update_user_profile_query(
     }
 
     // only update dob or address if user type is undergraduate or company respectively
-    match user_type.as_str() {
-        "undergraduate" => match profile_details.date_of_birth {
+    match user_type {
+        UserType::Undergraduate => match profile_details.date_of_birth {
             None => {}
             _ => {
+                let date_of_birth = NaiveDate::parse_from_str(
+                    &profile_details.date_of_birth.unwrap(),
+                    "%d-%m-%Y",
+                )
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .ok_or(ValidationError::InvalidDate {
+                    msg: "Date of birth should be in the format dd-mm-yyyy".to_string(),
+                })?;
+
                 fields.insert(
                     "date_of_birth".to_string(),
-                    Value::Datetime(Datetime(DateTime::<Utc>::from_utc(
-                        NaiveDate::parse_from_str(
-                            &profile_details.date_of_birth.unwrap(),
-                            "%d-%m-%Y",
-                        )
-                        .expect("Invalid date format")
-                        .and_hms_opt(0, 0, 0)
-                        .unwrap(),
-                        Utc,
-                    ))),
+                    Value::Datetime(Datetime(DateTime::<Utc>::from_utc(date_of_birth, Utc))),
                 );
             }
         },
-        "company" => match profile_details.address {
+        UserType::Company => match profile_details.address {
             None => {}
             _ => {
                 fields.insert(
@@ -653,7 +697,7 @@ pub async fn update_user_profile_query(
                     Value::Strand(Strand(
                         format!(
                             "https://www.google.com/maps/embed/v1/place?key={}&q={}",
-                            dotenv!("MAP_API_KEY"),
+                            map_api_key,
                             profile_details.address.unwrap()
                         )
                         .replace(" ", "%20"),
@@ -664,17 +708,13 @@ pub async fn update_user_profile_query(
         _ => {}
     }
 
-    println!("{:?}", fields);
-
-    Ok(UpdateStatement {
-        what: Values(vec![Value::Thing(Thing{tb:"user".to_string(), id:surrealdb::sql::Id::String(user_id)})]),
-        data: Some(Data::MergeExpression(Value::Object(Object(fields)))), // optional fields passed here
-        cond: None,
-        output: None,
-        only: false,
-        timeout: None,
-        parallel: false,
-    })
+    Ok(get_update_query_for_merge_or_content(
+        Item::Record(record("user", user_id)),
+        UpdateOperator::Merge,
+        Value::Object(Object(fields)), // optional fields passed here
+        None,
+        None,
+    ))
 }
 
 // Getting the profile using the username/email and getting the associated profile
@@ -685,7 +725,7 @@ pub struct UserRequest {
     email: Option<String>,
 }
 
-pub async fn get_select_user_query(user_request: UserRequest) -> Result<String, StatusCode> {
+pub async fn get_select_user_query(user_request: UserRequest) -> Result<Query, AppError> {
     let cond_type: String;
     let cond_value: String;
 
@@ -695,7 +735,7 @@ pub async fn get_select_user_query(user_request: UserRequest) -> Result<String,
         None => match user_request.email.clone() {
             // if email is also not present then return bad request
             None => {
-                return Err(StatusCode::BAD_REQUEST);
+                return Err(AppError::invalid_input("username or email is required"));
             }
             // if email is present, continue with email
             _ => {
@@ -710,45 +750,17 @@ pub async fn get_select_user_query(user_request: UserRequest) -> Result<String,
             cond_value = user_request.username.unwrap();
         }
     }
-    // Ok(get_select_query(table_name, column_names, condition, group_by, order_by, limit, start))
-
-    Ok(get_select_query(
-        Item::Table("user".to_string()),
-        Column::All,
-        Some(vec![(
-            crate::services::query_builder::Expression::EqualTo(
-                cond_type,
-                format!("'{}'", cond_value),
-            ),
-            ExpressionConnector::End,
-        )]),
-        None,
-        None,
-        None,
-        None,
-    ))
-}
 
-//  delete user
-// _________________________________________________________
-// pub async fn get_delete_user_query(user_id: String) -> Result<UpdateStatement, StatusCode> {
-//     let mut fields = bmap!();
-
-//     fields.insert("locked_flag".to_string(), Value::True);
-
-//     Ok(UpdateStatement {
-//         what: Values(vec![Value::Table(Table("user".to_string()))]),
-//         data: Some(Data::MergeExpression(Value::Object(Object(fields)))), // optional fields passed here
-//         cond: Some(Cond(Value::Expression(Box::from(Expression {
-//             l: Value::Idiom(Idiom(vec![Part::Field(Ident("id".to_string()))])),
-//             o: surrealdb::sql::Operator::Equal,
-//             r: Value::Strand(Strand(format!("user:{}", user_id))),
-//         })))),
-//         output: None,
-//         timeout: None,
-//         parallel: false,
-//     })
-// }
+    // deactivated accounts are hidden from other users
+    Ok(Select::from("user")
+        .where_(
+            field(&cond_type)
+                .eq(cond_value)
+                .and(field("deactivated").ne(true)),
+        )
+        .limit(1)
+        .build())
+}
 
 // get all profiles
 // _________________________________________________________
@@ -762,42 +774,46 @@ pub struct SelectUsersParam {
     start: Option<i32>,
 }
 
-pub async fn get_all_users_query(user_param: SelectUsersParam) -> Result<String, StatusCode> {
-    let mut condition: Vec<(
-        crate::services::query_builder::Expression,
-        ExpressionConnector,
-    )> = vec![];
-
-    // check whether user type is present or not
-    match user_param.user_type {
-        // if user type is not present then check whether user id is present or not
-        None => {}
-        // if user type is present, continue with user type and check whether user id is present or not
-        _ => {
-            condition.push((
-                crate::services::query_builder::Expression::EqualTo(
-                    "user_type".to_string(),
-                    format!("'{}'", user_param.user_type.unwrap()),
-                ),
-                ExpressionConnector::End,
-            ));
+pub async fn get_all_users_query(user_param: SelectUsersParam) -> Result<Query, AppError> {
+    // fields to order by come from the request and end up in the statement itself
+    let order_by_fields = user_param
+        .order_by_asc
+        .iter()
+        .chain(user_param.order_by_desc.iter())
+        .flatten();
+
+    for name in order_by_fields {
+        if !query_builder::is_field_name(name) {
+            return Err(AppError::invalid_input(format!(
+                "{} is not a field that can be ordered by",
+                name
+            )));
         }
     }
 
-    Ok(get_select_query(
-        Item::Table("user".to_string()),
-        Column::All,
-        Some(condition),
-        None,
-        // check whether order by asc or desc is present or not
-        match user_param.order_by_asc {
-            None => match user_param.order_by_desc {
-                None => None,
-                _ => Some(OrderBy::Descending(user_param.order_by_desc.unwrap())),
-            },
-            _ => Some(OrderBy::Ascending(user_param.order_by_asc.unwrap())),
-        },
-        user_param.limit,
-        user_param.start,
-    ))
+    // only filter by user type when it is present
+    let mut conditions = vec![field("deactivated").ne(true)];
+
+    if let Some(user_type) = user_param.user_type {
+        conditions.push(field("user_type").eq(user_type));
+    }
+
+    let mut query = Select::from("user").where_some(Condition::all(conditions));
+
+    // check whether order by asc or desc is present or not
+    match (user_param.order_by_asc, user_param.order_by_desc) {
+        (Some(fields), _) => query = query.order_by(OrderBy::Ascending(fields)),
+        (None, Some(fields)) => query = query.order_by(OrderBy::Descending(fields)),
+        (None, None) => {}
+    }
+
+    if let Some(limit) = user_param.limit {
+        query = query.limit(limit.into());
+    }
+
+    if let Some(start) = user_param.start {
+        query = query.start(start.into());
+    }
+
+    Ok(query.build())
 }
@@ -2,10 +2,16 @@ use async_trait::async_trait;
 use axum::{ extract::FromRequestParts, http::request::Parts };
 use surrealdb::sql::{ Thing, Id };
 
+use crate::errors::{ authentication_errors::AuthenticationError, AppError };
+use crate::models::user_type::UserType;
+
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
 pub struct Claim {
     user_id: String,
-    user_type: String,
+    user_type: UserType,
+    jti: String,
+    session_id: String,
+    expires_at: usize,
 }
 
 impl Claim {
@@ -13,21 +19,53 @@ impl Claim {
         self.user_id.clone()
     }
 
-    pub fn get_user_type(&self) -> String {
-        self.user_type.clone()
+    pub fn get_user_type(&self) -> UserType {
+        self.user_type
+    }
+
+    // id of the token the claim was read from
+    pub fn get_jti(&self) -> String {
+        self.jti.clone()
+    }
+
+    // session the token was issued for
+    pub fn get_session_id(&self) -> String {
+        self.session_id.clone()
+    }
+
+    pub fn get_expires_at(&self) -> usize {
+        self.expires_at
     }
 
     pub fn from(claim: crate::services::jwt::Claim) -> Self {
         Self {
             user_id: claim.get_id(),
             user_type: claim.get_user_type(),
+            jti: claim.get_jti(),
+            session_id: claim.get_session_id(),
+            expires_at: claim.get_expires_at(),
+        }
+    }
+
+    // claim of a request made with an api key, the key stands in for the token and the session
+    pub fn for_api_key(user_id: String, user_type: UserType, key_id: String) -> Self {
+        Self {
+            user_id,
+            user_type,
+            jti: key_id.clone(),
+            session_id: key_id,
+            // api keys do not expire, they are revoked
+            expires_at: 0,
         }
     }
 
     pub fn to_owned(&self) -> Self {
         Self {
             user_id: self.user_id.clone(),
-            user_type: self.user_type.clone(),
+            user_type: self.user_type,
+            jti: self.jti.clone(),
+            session_id: self.session_id.clone(),
+            expires_at: self.expires_at,
         }
     }
 
@@ -41,10 +79,15 @@ impl Claim {
 
 #[async_trait]
 impl<S: Send + Sync> FromRequestParts<S> for Claim {
-    type Rejection = String;
+    type Rejection = AppError;
 
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, String> {
-        let claim = parts.extensions.get::<Claim>().ok_or("Missing claim")?;
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, AppError> {
+        let claim = parts
+            .extensions
+            .get::<Claim>()
+            .ok_or(AuthenticationError::InvalidToken {
+                msg: "Missing claim".to_string(),
+            })?;
 
         Ok(claim.to_owned())
     }
@@ -0,0 +1,75 @@
+use std::{fmt, str::FromStr};
+
+use crate::errors::AppError;
+
+// kinds of accounts, stored in lowercase in the user_type field and in the jwt
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UserType {
+    Undergraduate,
+    Company,
+    Club,
+    // moderates users, clubs and content through /api/admin
+    Admin,
+}
+
+impl UserType {
+    // clubs are created by an undergraduate through /api/club/create, not through registration
+    // administrators are promoted from the command line
+    pub fn is_self_registrable(&self) -> bool {
+        matches!(self, UserType::Undergraduate | UserType::Company)
+    }
+}
+
+impl FromStr for UserType {
+    type Err = AppError;
+
+    fn from_str(input: &str) -> Result<UserType, Self::Err> {
+        match input {
+            "undergraduate" => Ok(UserType::Undergraduate),
+            "company" => Ok(UserType::Company),
+            "club" => Ok(UserType::Club),
+            "admin" => Ok(UserType::Admin),
+            _ => Err(AppError::invalid_input(format!(
+                "{} is not a valid user type",
+                input
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for UserType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UserType::Undergraduate => write!(f, "undergraduate"),
+            UserType::Company => write!(f, "company"),
+            UserType::Club => write!(f, "club"),
+            UserType::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+// actions that are limited to some kinds of accounts
+// posting jobs and verifying content have no routes yet
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    CreateClub,
+    CreateProject,
+    PostJob,
+    VerifyContent,
+    ManageApiKeys,
+}
+
+impl Permission {
+    // the permission matrix, every user type not listed is refused
+    pub fn user_types(&self) -> &'static [UserType] {
+        match self {
+            Permission::CreateClub => &[UserType::Undergraduate],
+            Permission::CreateProject => &[UserType::Undergraduate],
+            Permission::PostJob => &[UserType::Company],
+            Permission::VerifyContent => &[UserType::Admin],
+            Permission::ManageApiKeys => &[UserType::Company],
+        }
+    }
+}
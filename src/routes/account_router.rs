@@ -0,0 +1,27 @@
+use crate::handlers::{
+    account_handlers::{
+        cancel_account_deletion, deactivate_account, delete_account, reactivate_account,
+    },
+    data_export_handlers::{download_data_export, get_data_export, start_data_export},
+};
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::state::AppState;
+
+pub fn get_account_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/account/deactivate", post(deactivate_account))
+        .route("/api/account/reactivate", post(reactivate_account))
+        .route("/api/account/delete", post(delete_account))
+        .route("/api/account/delete/cancel", post(cancel_account_deletion))
+        .route("/api/account/export", post(start_data_export))
+        .route("/api/account/export/:export_id", get(get_data_export))
+        .route(
+            "/api/account/export/:export_id/download",
+            get(download_data_export),
+        )
+}
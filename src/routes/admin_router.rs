@@ -0,0 +1,53 @@
+use crate::{handlers::admin_handlers, middlewares};
+
+use axum::{
+    middleware,
+    routing::{delete, get, post},
+    Router,
+};
+
+use crate::state::AppState;
+
+pub fn get_admin_router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/users", get(admin_handlers::list_users))
+        .route("/api/admin/users/:user_id", get(admin_handlers::get_user))
+        .route(
+            "/api/admin/users/:user_id/lock",
+            post(admin_handlers::lock_user),
+        )
+        .route(
+            "/api/admin/users/:user_id/unlock",
+            post(admin_handlers::unlock_user),
+        )
+        .route(
+            "/api/admin/clubs/pending",
+            get(admin_handlers::get_clubs_pending_verification),
+        )
+        .route(
+            "/api/admin/clubs/:club_id/approve",
+            post(admin_handlers::approve_club),
+        )
+        .route(
+            "/api/admin/clubs/:club_id/reject",
+            post(admin_handlers::reject_club),
+        )
+        .route(
+            "/api/admin/posts/:post_id",
+            delete(admin_handlers::remove_post),
+        )
+        .route(
+            "/api/admin/posts/:post_id/comments/:comment_id",
+            delete(admin_handlers::remove_comment),
+        )
+        .route(
+            "/api/admin/blogs/:blog_id",
+            delete(admin_handlers::remove_blog),
+        )
+        .route("/api/admin/stats", get(admin_handlers::get_stats))
+        // every route above is for administrators only
+        .route_layer(middleware::from_fn_with_state(
+            state,
+            middlewares::admin::require_admin,
+        ))
+}
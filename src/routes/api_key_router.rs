@@ -0,0 +1,16 @@
+use crate::handlers::api_key_handlers::{create_api_key, get_api_keys, revoke_api_key};
+
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::{middlewares::role::require_role, models::user_type::Permission, state::AppState};
+
+// keys are managed with a user token, an api key can not reach these routes
+pub fn get_api_key_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/keys", get(get_api_keys).post(create_api_key))
+        .route("/api/keys/:key_id", delete(revoke_api_key))
+        .route_layer(require_role(Permission::ManageApiKeys.user_types()))
+}
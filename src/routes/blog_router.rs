@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use crate::handlers::blog_handlers::{
     create_a_blog, delete_a_blog_of_the_user, get_blogs_of_the_user_by_user_id, update_blog_content,
 };
@@ -8,9 +6,10 @@ use axum::{
     routing::{delete, get, post, put},
     Router,
 };
-use surrealdb::{engine::remote::ws::Client, Surreal};
 
-pub fn get_blog_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_blog_router() -> Router<AppState> {
     Router::new()
         .route("/api/blog/create", post(create_a_blog))
         .route("/api/blog/get", get(get_blogs_of_the_user_by_user_id))
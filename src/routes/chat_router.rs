@@ -5,7 +5,6 @@ use axum::Json;
 use axum::{routing::get, Extension, Router};
 use reqwest::StatusCode;
 use serde_json::{json, Value};
-use surrealdb::{engine::remote::ws::Client, Surreal};
 
 use tracing_subscriber::{prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt};
 
@@ -13,7 +12,9 @@ use crate::handlers::chat::group_chat_handlers;
 use crate::handlers::chat::personal_chat_handlers::{self, startpersonalchat};
 use crate::models::chat::{People, PersonalChat};
 
-pub fn get_chat_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_chat_router() -> Router<AppState> {
     let personal_chat_web_socket_extension =
         Arc::new(crate::services::websocket::get_personal_chat_websocket_extension());
     let group_chat_web_socket_extension =
@@ -33,11 +34,13 @@ pub fn get_chat_router() -> Router<Arc<Surreal<Client>>> {
 }
 
 // function to initialize tracing subscriber
+// the router can be built more than once (e.g. in tests), so an existing subscriber is kept
 pub fn tracing_subscriber_init() {
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "example_chat=trace".into()),
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "ufora_backend=info,example_chat=trace".into()),
         ))
         .with(tracing_subscriber::fmt::layer())
-        .init();
+        .try_init()
+        .ok();
 }
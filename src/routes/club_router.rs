@@ -1,11 +1,10 @@
-use std::sync::Arc;
-
 use crate::{
     handlers::{
         club_handlers::{club_middleware_check, create_a_club_account, verify_club_email},
         login_handlers::club_login,
     },
-    middlewares,
+    middlewares::{self, role::require_role},
+    models::user_type::Permission,
 };
 
 use axum::{
@@ -13,15 +12,21 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use surrealdb::{engine::remote::ws::Client, Surreal};
 
-pub fn get_club_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_club_router(state: AppState) -> Router<AppState> {
     Router::new()
         .route("/api/club/middleware", get(club_middleware_check))
-        .layer(middleware::from_fn(
+        .layer(middleware::from_fn_with_state(
+            state,
             middlewares::club_auth::validate_club_token,
         ))
         .route("/api/club/login/:club_id", post(club_login))
-        .route("/api/club/create", post(create_a_club_account))
+        .route(
+            "/api/club/create",
+            post(create_a_club_account)
+                .route_layer(require_role(Permission::CreateClub.user_types())),
+        )
         .route("/api/club/email/verification", post(verify_club_email))
 }
@@ -0,0 +1,26 @@
+use crate::handlers::email_handlers::{
+    confirm_email_change, request_email_change, revert_email_change, show_email_change_revert,
+};
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::state::AppState;
+
+// routes of the signed in user
+pub fn get_email_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/account/email", post(request_email_change))
+        .route("/api/account/email/confirm", post(confirm_email_change))
+}
+
+// opened from the link in the email sent to the old address, without signing in
+// the link only shows a page, the change is undone by the post it sends
+pub fn get_email_revert_router() -> Router<AppState> {
+    Router::new().route(
+        "/api/account/email/revert",
+        get(show_email_change_revert).post(revert_email_change),
+    )
+}
@@ -1,11 +1,10 @@
-use std::sync::Arc;
-
 use crate::handlers::forgot_password_handlers;
 
 use axum::{ Router, routing::post };
-use surrealdb::{ Surreal, engine::remote::ws::Client };
 
-pub fn get_forgot_password_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_forgot_password_router() -> Router<AppState> {
     Router::new()
         .route(
             "/api/password/reset/otp/request",
@@ -1,16 +1,16 @@
-use std::sync::Arc;
-
 use crate::handlers::logout_handlers;
 
 use axum::{
     Router, 
     routing::post
 };
-use surrealdb::{Surreal, engine::remote::ws::Client};
 
-pub fn get_logout_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_logout_router() -> Router<AppState> {
     Router::new()
         .route("/api/logout", post(logout_handlers::logout))
+        .route("/api/logout/all", post(logout_handlers::logout_everywhere))
     
     
-}
\ No newline at end of file
+}
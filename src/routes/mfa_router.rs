@@ -0,0 +1,12 @@
+use crate::handlers::mfa_handlers;
+
+use axum::{routing::post, Router};
+
+use crate::state::AppState;
+
+pub fn get_mfa_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/mfa/enroll", post(mfa_handlers::enroll))
+        .route("/api/mfa/confirm", post(mfa_handlers::confirm))
+        .route("/api/mfa/disable", post(mfa_handlers::disable))
+}
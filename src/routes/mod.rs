@@ -1,39 +1,53 @@
+mod account_router;
+mod admin_router;
+mod api_key_router;
 mod blog_router;
 mod chat_router;
 mod club_router;
+mod email_router;
 mod forgot_password_router;
 mod login_router;
 mod logout_router;
+mod mfa_router;
+mod password_router;
 mod post_router;
 mod profile_router;
 mod project_router;
 mod registration_router;
+mod session_router;
 mod test_route;
+mod token_router;
+mod unlock_router;
 mod verification_router;
 
-use std::sync::Arc;
-
 use axum::{http::Method, middleware, Router};
 use tower_cookies::CookieManagerLayer;
 use tower_http::cors::{Any, CorsLayer};
 
-use crate::middlewares;
+use crate::{middlewares, state::AppState};
 
+use account_router::get_account_router;
+use admin_router::get_admin_router;
+use api_key_router::get_api_key_router;
 use blog_router::get_blog_router;
 use chat_router::get_chat_router;
 use club_router::get_club_router;
+use email_router::{get_email_revert_router, get_email_router};
 use forgot_password_router::get_forgot_password_router;
 use login_router::get_login_router;
 use logout_router::get_logout_router;
+use mfa_router::get_mfa_router;
+use password_router::get_password_router;
 use post_router::get_post_router;
 use profile_router::get_profile_router;
 use project_router::get_project_router;
 use registration_router::get_registration_router;
+use session_router::get_session_router;
+use token_router::get_token_router;
+use unlock_router::get_unlock_router;
 use verification_router::get_verification_router;
 
-use surrealdb::{engine::remote::ws::Client, Surreal};
-
-pub fn get_router() -> Router<Arc<Surreal<Client>>> {
+pub fn get_router(state: AppState) -> Router {
     let cors = CorsLayer::new()
         .allow_methods(vec![Method::GET, Method::POST])
         .allow_origin(Any);
@@ -42,7 +56,7 @@ pub fn get_router() -> Router<Arc<Surreal<Client>>> {
         //merge test router -> for testing new features before adding
         .merge(test_route::get_test_router())
         // merge club router
-        .merge(get_club_router())
+        .merge(get_club_router(state.clone()))
         // merge project router
         .merge(get_project_router())
         // merge blog router
@@ -51,14 +65,42 @@ pub fn get_router() -> Router<Arc<Surreal<Client>>> {
         .merge(get_post_router())
         // merge logout router
         .merge(get_logout_router())
+        // merge session router
+        .merge(get_session_router())
+        // merge mfa router
+        .merge(get_mfa_router())
+        // merge password router -> change the password of the signed in user
+        .merge(get_password_router())
+        // merge account router -> deactivation and deletion of the signed in user
+        .merge(get_account_router())
+        // merge email router -> change the email address of the signed in user
+        .merge(get_email_router())
+        // merge api key router -> keys of integrations, companies only
+        .merge(get_api_key_router())
         // merge profile router
         .merge(get_profile_router())
         // merge chat router -> with authorization
         .merge(get_chat_router())
+        // merge admin router -> administrators only
+        .merge(get_admin_router(state.clone()))
         // layer to validate jwt -> check whether user has access
-        .layer(middleware::from_fn(middlewares::auth::validate_jwt))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            middlewares::auth::validate_jwt,
+        ))
+        // layer to accept api keys -> runs before validate_jwt, limited to the scopes of the key
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            middlewares::api_key::validate_api_key,
+        ))
         // merge login router
         .merge(get_login_router())
+        // merge token router -> the access token may already have expired, and the jwks
+        .merge(get_token_router())
+        // merge unlock router -> locked accounts can not log in
+        .merge(get_unlock_router())
+        // merge email revert router -> opened from the link sent to the old address
+        .merge(get_email_revert_router())
         // merge forgot password router
         .merge(get_forgot_password_router())
         // merge registration router
@@ -69,4 +111,6 @@ pub fn get_router() -> Router<Arc<Surreal<Client>>> {
         .layer(CookieManagerLayer::new())
         // layer to allow cors
         .layer(cors)
+        // share the database, configuration and mailer with all handlers
+        .with_state(state)
 }
@@ -0,0 +1,9 @@
+use crate::handlers::password_handlers::change_password;
+
+use axum::{routing::post, Router};
+
+use crate::state::AppState;
+
+pub fn get_password_router() -> Router<AppState> {
+    Router::new().route("/api/password/change", post(change_password))
+}
@@ -1,16 +1,16 @@
-use std::sync::Arc;
-
 use crate::handlers::post_handlers::{
-    add_or_remove_reaction_to_a_post, create_post, delete_post_by_id, add_a_comment
+    add_or_remove_reaction_to_a_post, create_post, delete_post_by_id, add_a_comment,
+    delete_a_comment,
 };
 
 use axum::{
     routing::{delete, patch, post},
     Router,
 };
-use surrealdb::{engine::remote::ws::Client, Surreal};
 
-pub fn get_post_router() -> Router<Arc<Surreal<Client>>> {
+use crate::state::AppState;
+
+pub fn get_post_router() -> Router<AppState> {
     Router::new()
         .route("/api/post/create", post(create_post))
         .route("/api/post/delete/:post_id", delete(delete_post_by_id))
@@ -22,4 +22,8 @@ pub fn get_post_router() -> Router<Arc<Surreal<Client>>> {
             "/api/post/comment/add/:post_id",
             patch(add_a_comment),
         )
+        .route(
+            "/api/post/comment/delete/:post_id/:comment_id",
+            delete(delete_a_comment),
+        )
 }
@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use crate::handlers::project_handlers::{
     create_a_project, delete_a_project_of_the_user, get_projects_of_the_user_by_user_id,
     update_project_content,
@@ -9,11 +7,16 @@ use axum::{
     routing::{delete, get, post, put},
     Router,
 };
-use surrealdb::{engine::remote::ws::Client, Surreal};
 
-pub fn get_project_router() -> Router<Arc<Surreal<Client>>> {
+use crate::{middlewares::role::require_role, models::user_type::Permission, state::AppState};
+
+pub fn get_project_router() -> Router<AppState> {
     Router::new()
-        .route("/api/project/create", post(create_a_project))
+        .route(
+            "/api/project/create",
+            post(create_a_project)
+                .route_layer(require_role(Permission::CreateProject.user_types())),
+        )
         .route("/api/project/get", get(get_projects_of_the_user_by_user_id))
         .route(
             "/api/project/delete/:project_id",
@@ -0,0 +1,14 @@
+use crate::handlers::session_handlers::{end_session, get_sessions};
+
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::state::AppState;
+
+pub fn get_session_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/sessions", get(get_sessions))
+        .route("/api/sessions/:session_id", delete(end_session))
+}
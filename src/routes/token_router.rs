@@ -0,0 +1,15 @@
+use crate::handlers::token_handlers;
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::state::AppState;
+
+pub fn get_token_router() -> Router<AppState> {
+    Router::new()
+        .route("/api/token/refresh", post(token_handlers::refresh_tokens))
+        // public keys for services verifying ufora tokens
+        .route("/.well-known/jwks.json", get(token_handlers::get_jwks))
+}
@@ -0,0 +1,14 @@
+use crate::handlers::unlock_handlers;
+
+use axum::{routing::post, Router};
+
+use crate::state::AppState;
+
+pub fn get_unlock_router() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/account/unlock/request",
+            post(unlock_handlers::request_unlock_otp),
+        )
+        .route("/api/account/unlock", post(unlock_handlers::unlock_account))
+}
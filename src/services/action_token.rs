@@ -0,0 +1,200 @@
+use chrono::{DateTime, Duration, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use surrealdb::{engine::any::Any, sql::Datetime, Surreal};
+
+use crate::{
+    config::SecurityConfig,
+    errors::AppError,
+    services::{
+        query_builder::{
+            field, get_create_query_for_an_object, record, to_value, DatabaseObject, Item, Query,
+            Return, Select,
+        },
+        token::random_hex,
+    },
+};
+
+// what an action token allows, a token issued for one purpose is rejected for every other
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionPurpose {
+    // verifying the email address of a user with an otp
+    EmailVerification,
+    // verifying the email address of a new club account with an otp
+    ClubEmailVerification,
+    // verifying the otp sent for a forgotten password
+    PasswordResetOtp,
+    // setting a new password after the otp was verified
+    PasswordReset,
+    // undoing a change of the email address, sent to the old address
+    EmailChangeRevert,
+    // exchanging the pending token of a login with two-factor authentication, see jwt::MfaPendingClaim
+    MfaLogin,
+}
+
+// signed payload of an action token
+// the nonce is the id of the action_token record, so every token can be used once
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ActionToken {
+    purpose: ActionPurpose,
+    // email, user id or record id the token was issued for
+    subject: String,
+    nonce: String,
+    // unix time
+    expires_at: i64,
+}
+
+// issues a token for the subject, valid for the given time
+// the token is <payload>.<signature>, both base64url encoded, signed with HMAC-SHA256
+pub async fn issue(
+    db: &Surreal<Any>,
+    config: &SecurityConfig,
+    purpose: ActionPurpose,
+    subject: &str,
+    lifetime: Duration,
+) -> Result<String, AppError> {
+    let expires_at = Utc::now() + lifetime;
+    let token = ActionToken {
+        purpose,
+        subject: subject.to_string(),
+        nonce: random_hex(16),
+        expires_at: expires_at.timestamp(),
+    };
+
+    remember(db, purpose, &token.nonce, expires_at).await?;
+
+    let payload = serde_json::to_vec(&token).map_err(|e| AppError::internal(e.to_string()))?;
+
+    Ok(format!(
+        "{}.{}",
+        BASE64URL_NOPAD.encode(&payload),
+        BASE64URL_NOPAD.encode(&sign(config, &payload))
+    ))
+}
+
+// checks the signature, the purpose and the expiry of a token without using it up
+pub fn verify(
+    config: &SecurityConfig,
+    token: &str,
+    purpose: ActionPurpose,
+) -> Result<ActionToken, AppError> {
+    let invalid = || AppError::invalid_input("Invalid token");
+
+    let (payload, signature) = token.split_once('.').ok_or_else(invalid)?;
+    let payload = BASE64URL_NOPAD
+        .decode(payload.as_bytes())
+        .map_err(|_| invalid())?;
+    let signature = BASE64URL_NOPAD
+        .decode(signature.as_bytes())
+        .map_err(|_| invalid())?;
+
+    // compared in constant time by the mac
+    mac(config)
+        .chain_update(&payload)
+        .verify_slice(&signature)
+        .map_err(|_| invalid())?;
+
+    let token: ActionToken = serde_json::from_slice(&payload).map_err(|_| invalid())?;
+
+    if token.purpose != purpose {
+        return Err(invalid());
+    }
+
+    if token.expires_at <= Utc::now().timestamp() {
+        return Err(AppError::invalid_input("Token has expired"));
+    }
+
+    Ok(token)
+}
+
+impl ActionToken {
+    pub fn get_subject(&self) -> String {
+        self.subject.clone()
+    }
+
+    // marks the token as used, only the first of two concurrent uses succeeds
+    pub async fn consume(&self, db: &Surreal<Any>) -> Result<(), AppError> {
+        consume_nonce(db, &self.nonce).await
+    }
+}
+
+// records the nonce of a single use token until it expires
+// also used for tokens signed elsewhere, e.g. the jti of a pending two-factor login
+pub async fn remember(
+    db: &Surreal<Any>,
+    purpose: ActionPurpose,
+    nonce: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), AppError> {
+    get_create_query_for_an_object(
+        Item::Record(record("action_token", nonce.to_string())),
+        DatabaseObject {
+            keys: vec!["purpose".to_string(), "expires_at".to_string()],
+            values: vec![to_value(purpose)?, Datetime::from(expires_at).into()],
+        },
+        Return::NONE,
+    )
+    .execute(db)
+    .await?
+    .check()?;
+
+    Ok(())
+}
+
+// uses up a nonce recorded with remember
+// the expiry check also keeps the update from creating a record for a nonce that was never recorded
+pub async fn consume_nonce(db: &Surreal<Any>, nonce: &str) -> Result<(), AppError> {
+    let mut query = Query::raw("UPDATE ");
+    query
+        .push_value(record("action_token", nonce.to_string()))
+        .push(" SET used_at = time::now() WHERE used_at = NONE AND expires_at > time::now()");
+
+    let used: Option<serde_json::Value> = query.execute(db).await?.take(0)?;
+
+    used.map(|_| ())
+        .ok_or(AppError::invalid_input("Token has already been used"))
+}
+
+// fails like consume_nonce would, without using up the nonce
+pub async fn ensure_unused(db: &Surreal<Any>, nonce: &str) -> Result<(), AppError> {
+    let unused: Option<serde_json::Value> =
+        Select::from_record(record("action_token", nonce.to_string()))
+            .value("id")
+            .where_(
+                field("used_at")
+                    .is_none()
+                    .and(field("expires_at").gt(Datetime::default())),
+            )
+            .execute(db)
+            .await?
+            .take(0)?;
+
+    unused
+        .map(|_| ())
+        .ok_or(AppError::invalid_input("Token has already been used"))
+}
+
+pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+    Query::raw("DELETE action_token WHERE expires_at < time::now()")
+        .execute(db)
+        .await?
+        .check()?;
+
+    Ok(())
+}
+
+fn mac(config: &SecurityConfig) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(config.encryption_key.as_bytes())
+        .expect("hmac accepts keys of any length")
+}
+
+fn sign(config: &SecurityConfig, payload: &[u8]) -> Vec<u8> {
+    mac(config)
+        .chain_update(payload)
+        .finalize()
+        .into_bytes()
+        .to_vec()
+}
@@ -1,48 +1,110 @@
-use axum::http::StatusCode;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use lettre::message::Mailbox;
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 
-use dotenvy_macro::dotenv;
-
-// function to send email
-pub async fn send_email(
-    receiver_email: &str, // should be in the format "Receiver <user_email>"
-    subject: String,
-    body: String,
-) -> Result<(), StatusCode> {
-    // create email
-    let email = Message::builder()
-        .from(
-            ("Ufora <".to_string() + dotenv!("APP_EMAIL") + ">")
-                .parse()
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
-        )
-        .to(receiver_email.parse().map_err(|e| {
-            println!("{:?}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?)
-        .subject(subject)
-        .body(body)
-        .unwrap();
-
-    // email credentials for gmail
-    let credentials = Credentials::new(
-        dotenv!("APP_EMAIL").to_string(),
-        dotenv!("APP_EMAIL_PASSWORD").to_string(),
-    );
-
-    // create mailer
-    let mailer: AsyncSmtpTransport<Tokio1Executor> =
-        AsyncSmtpTransport::<Tokio1Executor>::relay("smtp.gmail.com")
-            .unwrap()
-            .credentials(credentials)
-            .build();
-
-    match mailer.send(email).await {
-        Ok(_) => Ok(()),
-        Err(e) => {
-            println!("{:?}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+use crate::{config::SmtpConfig, errors::AppError};
+
+// email handed over to a transport
+#[derive(Debug, Clone)]
+pub struct OutgoingEmail {
+    pub from: Mailbox,
+    pub to: Mailbox,
+    pub subject: String,
+    pub body: String,
+}
+
+// delivers emails, smtp in production and a capturing transport in tests
+#[async_trait]
+pub trait EmailTransport: Send + Sync {
+    async fn deliver(&self, email: OutgoingEmail) -> Result<(), String>;
+}
+
+// transport sending emails through an smtp relay
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+#[async_trait]
+impl EmailTransport for SmtpTransport {
+    async fn deliver(&self, email: OutgoingEmail) -> Result<(), String> {
+        // create email
+        let message = Message::builder()
+            .from(email.from)
+            .to(email.to)
+            .subject(email.subject)
+            .body(email.body)
+            .map_err(|e| e.to_string())?;
+
+        self.transport
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+// mailer shared through the router state
+#[derive(Clone)]
+pub struct Mailer {
+    sender: Mailbox,
+    transport: Arc<dyn EmailTransport>,
+}
+
+impl Mailer {
+    // creates a mailer sending through the given transport
+    pub fn new(sender: Mailbox, transport: Arc<dyn EmailTransport>) -> Self {
+        Self { sender, transport }
+    }
+
+    // creates a mailer for the smtp relay given in the configuration
+    pub fn from_config(config: &SmtpConfig) -> Result<Self, String> {
+        let sender = ("Ufora <".to_string() + &config.email + ">")
+            .parse()
+            .map_err(|e: lettre::address::AddressError| e.to_string())?;
+
+        // email credentials for the relay
+        let credentials = Credentials::new(config.email.clone(), config.password.clone());
+
+        let mut transport = AsyncSmtpTransport::<Tokio1Executor>::relay(&config.relay)
+            .map_err(|e| e.to_string())?
+            .credentials(credentials);
+
+        if let Some(port) = config.port {
+            transport = transport.port(port);
         }
+
+        Ok(Self::new(
+            sender,
+            Arc::new(SmtpTransport {
+                transport: transport.build(),
+            }),
+        ))
+    }
+
+    // function to send email
+    pub async fn send_email(
+        &self,
+        receiver_email: &str, // should be in the format "Receiver <user_email>"
+        subject: String,
+        body: String,
+    ) -> Result<(), AppError> {
+        let receiver = receiver_email
+            .parse()
+            .map_err(|_| AppError::invalid_input("Invalid email address"))?;
+
+        let email = OutgoingEmail {
+            from: self.sender.clone(),
+            to: receiver,
+            subject,
+            body,
+        };
+
+        self.transport
+            .deliver(email)
+            .await
+            .map_err(|e| AppError::internal(format!("email could not be sent: {}", e)))
     }
 }
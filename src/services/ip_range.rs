@@ -0,0 +1,38 @@
+use std::net::IpAddr;
+
+// an address (10.0.0.1) or a range in cidr notation (10.0.0.0/24)
+pub fn parse(entry: &str) -> Option<(IpAddr, u32)> {
+    let (address, prefix) = match entry.trim().split_once('/') {
+        Some((address, prefix)) => (address, Some(prefix.parse::<u32>().ok()?)),
+        None => (entry.trim(), None),
+    };
+
+    let address: IpAddr = address.parse().ok()?;
+    let max_prefix = if address.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(max_prefix);
+
+    (prefix <= max_prefix).then_some((address, prefix))
+}
+
+// whether the address is one of the entries or in one of their ranges
+// entries that do not parse match nothing
+pub fn contains(entries: &[String], ip: IpAddr) -> bool {
+    entries
+        .iter()
+        .filter_map(|entry| parse(entry))
+        .any(|(network, prefix)| in_range(ip, network, prefix))
+}
+
+fn in_range(ip: IpAddr, network: IpAddr, prefix: u32) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
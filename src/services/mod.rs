@@ -20,3 +20,18 @@ pub mod merge_json;
 
 // websocket service
 pub mod websocket;
+
+// ownership and visibility checks
+pub mod policy;
+
+// random identifiers and hashes for tokens
+pub mod token;
+
+// signed, expiring, single use tokens for verification and password reset flows
+pub mod action_token;
+
+// time based one time passwords for two-factor authentication
+pub mod totp;
+
+// ip addresses and cidr ranges, for allowlists and trusted proxies
+pub mod ip_range;
@@ -1,19 +1,248 @@
-use axum::http::StatusCode;
-use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use chrono::{Duration, Utc};
 use rand::Rng;
+use surrealdb::{engine::any::Any, sql::Datetime, Surreal};
+
+use crate::{
+    config::Config,
+    errors::AppError,
+    services::{
+        email::Mailer,
+        query_builder::{
+            field, get_update_query_for_merge_or_content, record, to_value, Item, Query, Return,
+            Select, UpdateOperator,
+        },
+        token::{constant_time_eq, hmac_sha256_hex},
+    },
+    state::AppState,
+};
+
+// flows that send an otp, an otp sent for one purpose is not accepted for another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpPurpose {
+    EmailVerification,
+    ClubEmailVerification,
+    PasswordReset,
+    AccountUnlock,
+    EmailChange,
+}
+
+impl OtpPurpose {
+    fn as_str(&self) -> &'static str {
+        match self {
+            OtpPurpose::EmailVerification => "email_verification",
+            OtpPurpose::ClubEmailVerification => "club_email_verification",
+            OtpPurpose::PasswordReset => "password_reset",
+            OtpPurpose::AccountUnlock => "account_unlock",
+            OtpPurpose::EmailChange => "email_change",
+        }
+    }
+
+    // subject of the email and what the otp is for, as in "OTP for ... is"
+    fn email_text(&self) -> (&'static str, &'static str) {
+        match self {
+            OtpPurpose::EmailVerification => {
+                ("OTP for your registration", "your email verification")
+            }
+            OtpPurpose::ClubEmailVerification => (
+                "OTP for your club account registration",
+                "your club account email verification",
+            ),
+            OtpPurpose::PasswordReset => ("OTP for reset password", "resetting your password"),
+            OtpPurpose::AccountUnlock => ("OTP to unlock your account", "unlocking your account"),
+            OtpPurpose::EmailChange => (
+                "OTP to change your email address",
+                "changing your email address",
+            ),
+        }
+    }
+}
+
+// otp as stored, the record id is <purpose>:<address>
+// only a keyed hash of the code is kept
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+struct StoredOtp {
+    code_hash: String,
+    attempts: i64,
+    created_at: Datetime,
+    expires_at: Datetime,
+}
+
+// sends one time passwords by email and checks them
+// handlers extract it like the other parts of the state, State<OtpService>
+#[derive(Clone)]
+pub struct OtpService {
+    db: Arc<Surreal<Any>>,
+    config: Arc<Config>,
+    mailer: Mailer,
+}
+
+impl FromRef<AppState> for OtpService {
+    fn from_ref(state: &AppState) -> Self {
+        OtpService {
+            db: state.db.clone(),
+            config: state.config.clone(),
+            mailer: state.mailer.clone(),
+        }
+    }
+}
+
+impl OtpService {
+    // emails a new otp to the address, replacing the one sent before for the same purpose
+    // a new otp can only be asked for once the cooldown since the last one has passed
+    pub async fn send(&self, purpose: OtpPurpose, address: &str) -> Result<(), AppError> {
+        let now = Utc::now();
+        let code = get_an_otp();
+
+        // stored before it is sent, and only when the cooldown has passed, so of concurrent
+        // requests one gets the email and every code that is emailed can be verified
+        let resendable = field("created_at")
+            .is_none()
+            .or(field("created_at").lte(Datetime::from(
+                now - Duration::seconds(self.config.otp.resend_cooldown_seconds),
+            )))
+            .or(field("expires_at").lte(Datetime::from(now)));
+
+        let stored: Option<StoredOtp> = get_update_query_for_merge_or_content(
+            Item::Record(otp_record(purpose, address)),
+            UpdateOperator::Content,
+            to_value(StoredOtp {
+                code_hash: self.hash(purpose, address, &code),
+                attempts: 0,
+                created_at: Datetime::from(now),
+                expires_at: Datetime::from(now + Duration::minutes(self.config.otp.expiry_minutes)),
+            })?,
+            Some(resendable),
+            Some(Return::After),
+        )
+        .execute(&self.db)
+        .await?
+        .take(0)?;
+
+        if stored.is_none() {
+            let resend_at = self
+                .get(purpose, address)
+                .await?
+                .map(|stored| {
+                    stored.created_at.0 + Duration::seconds(self.config.otp.resend_cooldown_seconds)
+                })
+                .unwrap_or(now);
+
+            return Err(AppError::too_many_requests(
+                "An OTP has just been sent, please wait before asking for a new one",
+                (resend_at - now).num_seconds().max(1),
+            ));
+        }
 
-// struct for storing otp
-#[derive(serde::Serialize,serde::Deserialize,Debug)]
-pub struct OTP {
-    pub otp:String,
-    pub created_at:DateTime<Utc>,
-    pub expires_at:DateTime<Utc>
+        let (subject, description) = purpose.email_text();
+
+        let sent = self
+            .mailer
+            .send_email(
+                ("Receiver <".to_string() + address + ">").as_ref(),
+                subject.to_string(),
+                format!(
+                    "OTP for {} is {}. Please do not share this OTP with anyone.",
+                    description, code
+                ),
+            )
+            .await;
+
+        // an otp that never arrived does not hold back the next request
+        if let Err(e) = sent {
+            self.remove(purpose, address).await?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    // checks the code, a correct code is used up
+    // after too many wrong codes the otp is dropped and a new one has to be asked for
+    pub async fn verify(
+        &self,
+        purpose: OtpPurpose,
+        address: &str,
+        code: &str,
+    ) -> Result<(), AppError> {
+        // the attempt is counted in the same statement that checks the limit, so concurrent
+        // guesses can not all pass the check before any of them is counted
+        let mut query = Query::raw("UPDATE ");
+        query
+            .push_value(otp_record(purpose, address))
+            .push(" SET attempts += 1 WHERE attempts < ")
+            .push_value(self.config.otp.max_attempts)
+            .push(" AND expires_at > time::now() RETURN AFTER");
+
+        let counted: Option<StoredOtp> = query.execute(&self.db).await?.take(0)?;
+
+        // expired, used up or never sent
+        let Some(stored) = counted else {
+            self.remove(purpose, address).await?;
+            return Err(invalid_otp());
+        };
+
+        let code_hash = self.hash(purpose, address, code.trim());
+
+        if !constant_time_eq(code_hash.as_bytes(), stored.code_hash.as_bytes()) {
+            return Err(invalid_otp());
+        }
+
+        // a second request with the same code finds nothing to delete and is refused
+        let mut query = Query::raw("DELETE ");
+        query
+            .push_value(otp_record(purpose, address))
+            .push(" RETURN BEFORE");
+
+        let removed: Option<StoredOtp> = query.execute(&self.db).await?.take(0)?;
+        removed.map(|_| ()).ok_or_else(invalid_otp)
+    }
+
+    pub async fn remove_expired(db: &Surreal<Any>) -> Result<(), AppError> {
+        Query::raw("DELETE otp WHERE expires_at < time::now()")
+            .execute(db)
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn get(&self, purpose: OtpPurpose, address: &str) -> Result<Option<StoredOtp>, AppError> {
+        Ok(Select::from_record(otp_record(purpose, address))
+            .execute(&self.db)
+            .await?
+            .take(0)?)
+    }
+
+    async fn remove(&self, purpose: OtpPurpose, address: &str) -> Result<(), AppError> {
+        let mut query = Query::raw("DELETE ");
+        query.push_value(otp_record(purpose, address));
+        query.execute(&self.db).await?.check()?;
+
+        Ok(())
+    }
+
+    // keyed with the server secret and bound to the purpose and address,
+    // a leaked table does not give away the six digit codes
+    fn hash(&self, purpose: OtpPurpose, address: &str, code: &str) -> String {
+        hmac_sha256_hex(
+            self.config.security.encryption_key.as_bytes(),
+            format!("{}:{}:{}", purpose.as_str(), address, code).as_bytes(),
+        )
+    }
 }
 
-// function to generate otp
-pub fn get_an_otp() -> Result<String,StatusCode> {
+// six digits, 000000 to 999999
+pub fn get_an_otp() -> String {
+    format!("{:06}", rand::thread_rng().gen_range(0..1_000_000))
+}
 
-    let otp = rand::thread_rng().gen_range(100000..999999);
+fn otp_record(purpose: OtpPurpose, address: &str) -> surrealdb::sql::Thing {
+    record("otp", format!("{}:{}", purpose.as_str(), address))
+}
 
-    Ok(otp.to_string())
+fn invalid_otp() -> AppError {
+    AppError::invalid_input("Invalid or expired OTP")
 }
@@ -1,11 +1,127 @@
-use axum::http::StatusCode;
+use validator::{ValidationError as FieldError, ValidationErrors};
+
+use crate::{
+    config::PasswordConfig,
+    errors::{validation_errors::ValidationError, AppError},
+};
 
 // function to hash password
-pub fn hash_password(password:String) -> Result<String,StatusCode> {
-    bcrypt::hash(password, 14).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+pub fn hash_password(password:String) -> Result<String,AppError> {
+    bcrypt::hash(password, 14).map_err(|e| AppError::internal(e.to_string()))
 }
 
 // function to verify password
-pub fn verify_password(password:String,hash:String) -> Result<bool,StatusCode> {
-    bcrypt::verify(password, hash.as_str()).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
-}
\ No newline at end of file
+pub fn verify_password(password:String,hash:String) -> Result<bool,AppError> {
+    bcrypt::verify(password, hash.as_str()).map_err(|e| AppError::internal(e.to_string()))
+}
+
+// passwords from public lists of the most common and breached ones, lowercase, one per line
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+// parts of the username or email shorter than this are not looked for in the password
+const MIN_IDENTITY_LENGTH: usize = 3;
+
+// checks a new password against the password policy
+// identities are the username and email of the account, the password must not contain them
+// every rule that is broken is reported under the password field
+pub fn check_policy(
+    policy: &PasswordConfig,
+    password: &str,
+    identities: &[&str],
+) -> Result<(), AppError> {
+    let mut errors = ValidationErrors::new();
+    let mut broken = |code: &'static str, message: String| {
+        let mut error = FieldError::new(code);
+        error.message = Some(message.into());
+        errors.add("password", error);
+    };
+
+    if password.chars().count() < policy.min_length {
+        broken(
+            "too_short",
+            format!(
+                "Password must be at least {} characters long",
+                policy.min_length
+            ),
+        );
+    }
+
+    if password.len() > policy.max_length {
+        broken(
+            "too_long",
+            format!("Password must be at most {} bytes long", policy.max_length),
+        );
+    }
+
+    #[allow(clippy::type_complexity)]
+    let classes: [(bool, fn(char) -> bool, &'static str, &str); 4] = [
+        (
+            policy.require_lowercase,
+            char::is_lowercase,
+            "lowercase",
+            "a lowercase letter",
+        ),
+        (
+            policy.require_uppercase,
+            char::is_uppercase,
+            "uppercase",
+            "an uppercase letter",
+        ),
+        (
+            policy.require_digit,
+            |c| c.is_ascii_digit(),
+            "digit",
+            "a digit",
+        ),
+        (
+            policy.require_symbol,
+            |c| !c.is_alphanumeric() && !c.is_whitespace(),
+            "symbol",
+            "a symbol",
+        ),
+    ];
+
+    for (required, matches, code, name) in classes {
+        if required && !password.chars().any(matches) {
+            broken(code, format!("Password must contain {}", name));
+        }
+    }
+
+    if policy.reject_common && is_common(password) {
+        broken("common", "Password is too common".to_string());
+    }
+
+    if contains_identity(password, identities) {
+        broken(
+            "contains_identity",
+            "Password must not contain your username or email".to_string(),
+        );
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(ValidationError::InvalidPassword { errors }.into()),
+    }
+}
+
+// also catches common passwords with digits or symbols added at the end, e.g. Password123!
+fn is_common(password: &str) -> bool {
+    let password = password.to_lowercase();
+    let stem = password.trim_end_matches(|c: char| !c.is_alphabetic());
+
+    COMMON_PASSWORDS
+        .lines()
+        .any(|common| common == password || common == stem)
+}
+
+// the whole username or email and the part of the email before the @
+fn contains_identity(password: &str, identities: &[&str]) -> bool {
+    let password = password.to_lowercase();
+
+    identities
+        .iter()
+        .flat_map(|identity| [*identity, identity.split('@').next().unwrap_or_default()])
+        .map(|part| part.trim().to_lowercase())
+        .filter(|part| part.chars().count() >= MIN_IDENTITY_LENGTH)
+        .any(|part| password.contains(&part))
+}
@@ -0,0 +1,147 @@
+use std::str::FromStr;
+
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::errors::{authorization_errors::AuthorizationError, AppError};
+use crate::models::{
+    blog::Blog,
+    post::{AccessLevel, Comment, Post},
+    project::Project,
+    user_claim::Claim,
+};
+use crate::services::query_builder::{graph, record, Select};
+
+// content owned by the user who created it
+// ownership is the create_* edge from the user to the record
+pub trait Owned {
+    const TABLE: &'static str;
+    const OWNER_EDGE: &'static str;
+    // name used in error messages
+    const NAME: &'static str;
+}
+
+impl Owned for Post {
+    const TABLE: &'static str = "post";
+    const OWNER_EDGE: &'static str = "create_post";
+    const NAME: &'static str = "post";
+}
+
+impl Owned for Blog {
+    const TABLE: &'static str = "blog";
+    const OWNER_EDGE: &'static str = "create_blog";
+    const NAME: &'static str = "blog";
+}
+
+impl Owned for Project {
+    const TABLE: &'static str = "project";
+    const OWNER_EDGE: &'static str = "create_project";
+    const NAME: &'static str = "project";
+}
+
+// what the policy needs to know about a record
+#[derive(serde::Deserialize, Debug)]
+struct Access {
+    #[serde(default)]
+    owners: Vec<Thing>,
+    access_level: Option<String>,
+    // none until the first comment
+    comments: Option<Vec<Comment>>,
+    // content of a deactivated account
+    hidden: Option<bool>,
+}
+
+// decides what the user behind a claim may do with posts, blogs, projects and comments
+// every check returns the record id to act on, or AccessDeniedError
+pub struct Policy<'a> {
+    db: &'a Surreal<Any>,
+    user: Thing,
+}
+
+impl<'a> Policy<'a> {
+    pub fn new(db: &'a Surreal<Any>, claim: &Claim) -> Self {
+        Self {
+            db,
+            user: claim.get_surrealdb_thing(),
+        }
+    }
+
+    // only the creator may update or delete a record
+    pub async fn can_modify<T: Owned>(&self, id: String) -> Result<Thing, AppError> {
+        let record_id = record(T::TABLE, id);
+        let access = self.access::<T>(record_id.clone()).await?;
+
+        match self.owns(&access) {
+            true => Ok(record_id),
+            false => Err(denied(format!(
+                "You don't have access to modify this {}",
+                T::NAME
+            ))),
+        }
+    }
+
+    // public posts are visible to everyone, other posts only to their creator
+    // NOTE: friends only posts are treated like private ones until there are friendships
+    pub async fn can_view_post(&self, id: String) -> Result<Thing, AppError> {
+        let record_id = record(Post::TABLE, id);
+        let access = self.access::<Post>(record_id.clone()).await?;
+
+        let access_level = access
+            .access_level
+            .as_deref()
+            .and_then(|access_level| AccessLevel::from_str(access_level).ok());
+
+        match (access_level, self.owns(&access)) {
+            (Some(AccessLevel::Public), _) | (_, true) => Ok(record_id),
+            _ => Err(denied("You don't have access to this post".to_string())),
+        }
+    }
+
+    // a comment can be removed by its author and by the creator of the post
+    pub async fn can_remove_comment(
+        &self,
+        post_id: String,
+        comment_id: &str,
+    ) -> Result<Thing, AppError> {
+        let record_id = record(Post::TABLE, post_id);
+        let access = self.access::<Post>(record_id.clone()).await?;
+
+        let comment = access
+            .comments
+            .iter()
+            .flatten()
+            .find(|comment| comment.id == comment_id)
+            .ok_or(AppError::not_found("Comment not found"))?;
+
+        match comment.user == self.user || self.owns(&access) {
+            true => Ok(record_id),
+            false => Err(denied(
+                "You don't have access to remove this comment".to_string(),
+            )),
+        }
+    }
+
+    fn owns(&self, access: &Access) -> bool {
+        access.owners.contains(&self.user)
+    }
+
+    // hidden content is not found by anyone but its creator
+    async fn access<T: Owned>(&self, record_id: Thing) -> Result<Access, AppError> {
+        let access: Option<Access> = Select::from_record(record_id)
+            .fields(&["access_level", "comments", "hidden"])
+            .field_as(graph().in_(T::OWNER_EDGE).from("user"), "owners")
+            .execute(self.db)
+            .await?
+            .take(0)?;
+
+        access
+            .filter(|access| !access.hidden.unwrap_or(false) || self.owns(access))
+            .ok_or(AppError::not_found(format!(
+                "The {} was not found",
+                T::NAME
+            )))
+    }
+}
+
+fn denied(msg: String) -> AppError {
+    AuthorizationError::AccessDeniedError { msg }.into()
+}
@@ -1,146 +1,237 @@
 use core::fmt;
+use std::{collections::BTreeMap, ops::Not};
+
+use serde::Serialize;
+use surrealdb::{
+    engine::any::Any,
+    sql::{self, Id, Object, Thing, Value},
+    Response, Surreal,
+};
+
+// Query holds a SurrealQL statement together with the values it refers to
+// values are never written into the statement, every value is bound to a $pN placeholder
+// and sent to the database separately with db.query(..).bind(..)
+// NOTE: only identifiers chosen by the code (tables, fields, edges) belong in the statement text
+#[derive(Debug, Default, Clone)]
+pub struct Query {
+    statement: String,
+    bindings: BTreeMap<String, Value>,
+}
+
+impl Query {
+    pub fn new() -> Query {
+        Query::default()
+    }
 
-use surrealdb::sql::Thing;
+    // starts a query with a trusted piece of SurrealQL
+    pub fn raw(sql: &str) -> Query {
+        let mut query = Query::new();
+        query.push(sql);
+        query
+    }
 
-// Item enum specifies whether the query is for a table or a record
-// for a table, Item::Table("table_name".to_string()) is used
-// for a record, Item::Record{tb: "table_name".to_string(), id: "record_id".to_string()} is used
-#[derive(Debug)]
-pub enum Item {
-    Table(String),
-    Record { tb: String, id: String },
-}
+    // appends a trusted piece of SurrealQL to the statement
+    pub fn push(&mut self, sql: &str) -> &mut Query {
+        self.statement.push_str(sql);
+        self
+    }
 
-impl fmt::Display for Item {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Item::Table(table_name) => write!(f, "{}", table_name),
-            Item::Record { tb, id } => write!(f, "{}:{}", tb, id),
+    // binds a value and returns the placeholder that refers to it
+    pub fn bind(&mut self, value: impl Into<Value>) -> String {
+        let name = format!("p{}", self.bindings.len());
+        self.bindings.insert(name.clone(), value.into());
+        format!("${}", name)
+    }
+
+    // binds a value and appends its placeholder to the statement
+    pub fn push_value(&mut self, value: impl Into<Value>) -> &mut Query {
+        let placeholder = self.bind(value);
+        self.push(&placeholder)
+    }
+
+    // appends another query as a separate statement
+    // placeholders of the other query are renumbered so they don't clash with ours
+    pub fn append(&mut self, other: Query) -> &mut Query {
+        let offset = self.bindings.len();
+
+        if !self.statement.trim().is_empty() {
+            match self.statement.trim_end().ends_with(';') {
+                true => self.statement.push(' '),
+                false => self.statement.push_str("; "),
+            }
         }
+
+        self.statement
+            .push_str(&renumber_placeholders(&other.statement, offset));
+
+        for (index, (_, value)) in other.into_sorted_bindings().into_iter().enumerate() {
+            self.bindings.insert(format!("p{}", index + offset), value);
+        }
+
+        self
     }
-}
 
-// Column enum specifies whether the query is for all columns or specific columns
-// for all columns, Column::All is used
-// for specific columns, Column::Specific(vec![String::from("column_name_1"),String::from("column_name_2")]) is used
-#[derive(Debug)]
-pub enum Column {
-    All,
-    Specific(Vec<String>),
-}
+    // the statement and its bindings as they are sent, for checking what a builder produced
+    #[allow(dead_code)]
+    pub fn statement(&self) -> &str {
+        &self.statement
+    }
 
-impl fmt::Display for Column {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Column::All => write!(f, "Select * "),
-            Column::Specific(column_names) => {
-                let mut column_names_string = String::from("Select ");
-                for column_name in column_names {
-                    column_names_string.push_str(column_name);
-                    column_names_string.push_str(", ");
+    #[allow(dead_code)]
+    pub fn bindings(&self) -> &BTreeMap<String, Value> {
+        &self.bindings
+    }
+
+    // runs the statement with all of its bindings
+    pub async fn execute(self, db: &Surreal<Any>) -> Result<Response, surrealdb::Error> {
+        db.query(self.statement).bind(self.bindings).await
+    }
+
+    // runs a query built with transaction and reports the statement that failed
+    // the other statements of a failed transaction only report that they were not executed,
+    // so the first error is not necessarily the one that caused the rollback
+    pub async fn execute_transaction(self, db: &Surreal<Any>) -> Result<(), surrealdb::Error> {
+        let mut response = self.execute(db).await?;
+        let mut not_executed = None;
+
+        for index in 0..response.num_statements() {
+            if let Err(error) = response.take::<Value>(index) {
+                match error.to_string().contains(NOT_EXECUTED) {
+                    true => {
+                        not_executed.get_or_insert(error);
+                    }
+                    false => return Err(error),
                 }
-                column_names_string.pop();
-                column_names_string.pop();
-                write!(f, "{}", column_names_string)
             }
         }
+
+        match not_executed {
+            None => Ok(()),
+            Some(error) => Err(error),
+        }
     }
-}
 
-// LogicalOperator enum specifies the type of logical operator used in the query
-// for and, LogicalOperator::And is used
-// for or, LogicalOperator::Or is used
-pub enum ExpressionConnector {
-    And,
-    Or,
-    End,
+    // bindings ordered by placeholder number (p10 comes after p9)
+    fn into_sorted_bindings(self) -> Vec<(usize, Value)> {
+        let mut bindings: Vec<(usize, Value)> = self
+            .bindings
+            .into_iter()
+            .filter_map(|(name, value)| Some((name[1..].parse().ok()?, value)))
+            .collect();
+
+        bindings.sort_by_key(|(index, _)| *index);
+
+        bindings
+    }
 }
 
-impl fmt::Display for ExpressionConnector {
+impl fmt::Display for Query {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            ExpressionConnector::And => write!(f, " AND "),
-            ExpressionConnector::Or => write!(f, " OR "),
-            ExpressionConnector::End => write!(f, " "),
-        }
+        write!(f, "{}", self.statement)
     }
 }
 
-// Expression enum specifies the type of expression used in the query
-// for equal to, Expression::EqualTo(String::from("column_name"),String::from("value")) is used
-// for not equal to, Expression::NotEqualTo(String::from("column_name"),String::from("value")) is used
-// for greater than, Expression::GreaterThan(String::from("column_name"),String::from("value")) is used
-// for less than, Expression::LessThan(String::from("column_name"),String::from("value")) is used
-// for greater than or equal to, Expression::GreaterThanOrEqualTo(String::from("column_name"),String::from("value")) is used
-// for less than or equal to, Expression::LessThanOrEqualTo(String::from("column_name"),String::from("value")) is used
-// for is None, Expression::IsNone(String::from("column_name")) is used
-// for is not None, Expression::IsNotNone(String::from("column_name")) is used
-#[derive(Debug)]
-pub enum Expression {
-    EqualTo(String, String),
-    NotEqualTo(String, String),
-    GreaterThan(String, String),
-    LessThan(String, String),
-    GreaterThanOrEqualTo(String, String),
-    LessThanOrEqualTo(String, String),
-    IsNone(String),
-    IsNotNone(String),
-    EdgeExpression(String),
-}
-
-impl fmt::Display for Expression {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Expression::EqualTo(column_name, value) => write!(f, " {} = {} ", column_name, value),
-            Expression::NotEqualTo(column_name, value) => {
-                write!(f, " {} != {} ", column_name, value)
-            }
-            Expression::GreaterThan(column_name, value) => {
-                write!(f, " {} > {} ", column_name, value)
-            }
-            Expression::LessThan(column_name, value) => write!(f, " {} < {} ", column_name, value),
-            Expression::GreaterThanOrEqualTo(column_name, value) => {
-                write!(f, " {} >= {} ", column_name, value)
-            }
-            Expression::LessThanOrEqualTo(column_name, value) => {
-                write!(f, " {} <= {} ", column_name, value)
-            }
-            Expression::IsNone(column_name) => write!(f, " {} = None ", column_name),
-            Expression::IsNotNone(column_name) => write!(f, " {} != None ", column_name),
-            Expression::EdgeExpression(edge_condition) => write!(f, " {} ", edge_condition),
+// part of the error given to statements skipped because their transaction failed
+const NOT_EXECUTED: &str = "not executed due to a failed transaction";
+
+// combines queries into a single transaction, either every statement is applied or none
+// run it with Query::execute_transaction
+pub fn transaction(queries: Vec<Query>) -> Query {
+    let mut transaction = Query::raw("BEGIN TRANSACTION;");
+
+    for query in queries {
+        transaction.append(query);
+    }
+
+    transaction.append(Query::raw("COMMIT TRANSACTION;"));
+
+    transaction
+}
+
+// shifts every $pN placeholder in a statement by offset
+fn renumber_placeholders(statement: &str, offset: usize) -> String {
+    let mut renumbered = String::with_capacity(statement.len());
+    let mut rest = statement;
+
+    while let Some(position) = rest.find("$p") {
+        renumbered.push_str(&rest[..position + 2]);
+        rest = &rest[position + 2..];
+
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+
+        if let Ok(index) = rest[..digits].parse::<usize>() {
+            renumbered.push_str(&(index + offset).to_string());
         }
+
+        rest = &rest[digits..];
     }
+
+    renumbered.push_str(rest);
+
+    renumbered
 }
 
-// GroupBy enum specifies whether the query is for all columns or specific columns
-// for all columns, GroupBy::All is used
-// for specific columns, GroupBy::Specific(vec![String::from("column_name_1"),String::from("column_name_2")]) is used
-pub enum Group {
-    All,
-    Specific(Vec<String>),
+// whether a name can be used as a field in a statement
+// use it before passing field names that come from a request (e.g. order by) to the builder
+pub fn is_field_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.ends_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
 }
 
-impl fmt::Display for Group {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+// converts a serializable struct into a value that can be bound
+pub fn to_value(value: impl Serialize) -> Result<Value, surrealdb::Error> {
+    sql::to_value(value).map_err(surrealdb::Error::Db)
+}
+
+// returns a new random record id for a table
+// used to refer to a record before it is created, e.g. to relate it in the same transaction
+pub fn new_record(table_name: &str) -> Thing {
+    Thing {
+        tb: table_name.to_string(),
+        id: Id::rand(),
+    }
+}
+
+// returns the record id of a table with a known id
+pub fn record(table_name: &str, record_id: String) -> Thing {
+    Thing {
+        tb: table_name.to_string(),
+        id: Id::String(record_id),
+    }
+}
+
+// Item enum specifies whether the query is for a table or a record
+// for a table, Item::Table("table_name".to_string()) is used
+// for a record, Item::Record(thing) is used, the record id is bound as a value
+#[derive(Debug)]
+pub enum Item {
+    Table(String),
+    Record(Thing),
+}
+
+impl Item {
+    fn write_to(self, query: &mut Query) {
         match self {
-            Group::All => write!(f, " GROUP ALL"),
-            Group::Specific(column_names) => {
-                let mut column_names_string = String::from(" GROUP ");
-                for column_name in column_names {
-                    column_names_string.push_str(column_name);
-                    column_names_string.push_str(", ");
-                }
-                column_names_string.pop();
-                column_names_string.pop();
-                write!(f, "{} ", column_names_string)
-            }
-        }
+            Item::Table(table_name) => query.push(&table_name),
+            Item::Record(thing) => query.push_value(thing),
+        };
     }
 }
 
+// writes the WHERE clause of a condition built with field(..) and graph()
+fn write_condition(query: &mut Query, condition: Condition) {
+    query.push(" WHERE ");
+    condition.write_to(query);
+}
+
 // OrderBy enum specifies whether the query is for ascending or descending order
 // for ascending order, OrderBy::Ascending(vec![String::from("column_name_1"),String::from("column_name_2")]) is used
 // for descending order, OrderBy::Descending(vec![String::from("column_name_1"),String::from("column_name_2")]) is used
+// NOTE: column names are part of the statement, check names from a request with is_field_name
 #[derive(Debug)]
 pub enum OrderBy {
     Ascending(Vec<String>),
@@ -151,24 +242,10 @@ impl fmt::Display for OrderBy {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             OrderBy::Ascending(column_names) => {
-                let mut column_names_string = String::from(" ORDER BY ");
-                for column_name in column_names {
-                    column_names_string.push_str(column_name);
-                    column_names_string.push_str(", ");
-                }
-                column_names_string.pop();
-                column_names_string.pop();
-                write!(f, "{}", column_names_string + " ASC")
+                write!(f, " ORDER BY {} ASC", column_names.join(", "))
             }
             OrderBy::Descending(column_names) => {
-                let mut column_names_string = String::from(" ORDER BY ");
-                for column_name in column_names {
-                    column_names_string.push_str(column_name);
-                    column_names_string.push_str(", ");
-                }
-                column_names_string.pop();
-                column_names_string.pop();
-                write!(f, "{}", column_names_string + " DESC")
+                write!(f, " ORDER BY {} DESC", column_names.join(", "))
             }
         }
     }
@@ -195,105 +272,25 @@ impl fmt::Display for Return {
             Return::Difference => write!(f, " RETURN DIFF"),
             Return::Before => write!(f, " RETURN BEFORE"),
             Return::After => write!(f, " RETURN AFTER"),
-            Return::Fields { fields } => {
-                let mut fields_string = String::from(" RETURN ");
-                for field in fields {
-                    fields_string.push_str(field);
-                    fields_string.push_str(", ");
-                }
-                fields_string.pop();
-                fields_string.pop();
-                write!(f, "{}", fields_string)
-            }
+            Return::Fields { fields } => write!(f, " RETURN {}", fields.join(", ")),
         }
     }
 }
 
-// to get a select query with known parameters
-// NOTE: if no condition is provided, pass None
-// NOTE: last expression connector is always ExpressionConnector::End
-// NOTE: if no group_by is provided, pass None
-// NOTE: if no order_by is provided, pass None
-// NOTE: if no limit is provided, pass None
-// NOTE: if no start is provided, pass None
-pub fn get_select_query(
-    table_name: Item,
-    column_names: Column,
-    condition: Option<Vec<(Expression, ExpressionConnector)>>,
-    group_by: Option<Group>,
-    order_by: Option<OrderBy>,
-    limit: Option<i32>, // limit is the number of records to be returned
-    start: Option<i32>, // start is the number of records to be skipped
-) -> String {
-    let mut query = String::new();
-
-    query.push_str(&column_names.to_string());
-
-    query.push_str(" FROM ");
-
-    query.push_str(&table_name.to_string());
-
-    if let Some(condition) = condition {
-        if condition.len() > 0 {
-            query.push_str(" WHERE ");
-            for (expression_1, expression_2) in condition {
-                query.push_str(&expression_1.to_string());
-                query.push_str(&expression_2.to_string());
-            }
-        } else {
-            println!("No condition provided")
-        }
-    }
-
-    if let Some(group_by) = group_by {
-        query.push_str(&group_by.to_string());
-    }
-
-    if let Some(order_by) = order_by {
-        query.push_str(&order_by.to_string());
-    }
-
-    if let Some(limit) = limit {
-        query.push_str(&format!(" LIMIT {}", limit));
-    }
-
-    if let Some(start) = start {
-        query.push_str(&format!(" START {}", start));
-    }
-
-    query
-}
-
 pub fn get_insert_query_by_fields(
     table_name: String,
     column_names: Vec<String>,
-    values: Vec<String>,
-) -> String {
-    let mut query = String::new();
+    values: Vec<Value>,
+) -> Query {
+    let mut query = Query::raw("INSERT INTO ");
 
-    query.push_str("INSERT INTO ");
-    query.push_str(&table_name);
-    query.push_str(" (");
+    query.push(&table_name);
+    query.push(&format!(" ({}) VALUES (", column_names.join(", ")));
 
-    for column_name in column_names {
-        query.push_str(&column_name);
-        query.push_str(", ");
-    }
-
-    query.pop();
-    query.pop();
-
-    query.push_str(") VALUES (");
-
-    for value in values {
-        query.push_str(&value);
-        query.push_str(", ");
-    }
+    let placeholders: Vec<String> = values.into_iter().map(|value| query.bind(value)).collect();
 
-    query.pop();
-    query.pop();
-
-    query.push_str(")");
+    query.push(&placeholders.join(", "));
+    query.push(")");
 
     query
 }
@@ -301,11 +298,11 @@ pub fn get_insert_query_by_fields(
 // representation of a JSON object to be inserted into the database
 // keys and values are stored in separate vectors
 // keys and values are stored in the same order as they are inserted
-// NOTE: if a value to be stored as string wrap it around single quotes inside double quotes
+// the whole object is bound as a single value, so values are never escaped by hand
 #[derive(Debug)]
 pub struct DatabaseObject {
     pub keys: Vec<String>,
-    pub values: Vec<String>,
+    pub values: Vec<Value>,
 }
 
 impl DatabaseObject {
@@ -320,39 +317,26 @@ impl DatabaseObject {
         self.keys.push(key);
     }
 
-    pub fn add_value(&mut self, value: String) {
-        self.values.push(value);
+    pub fn add_value(&mut self, value: impl Into<Value>) {
+        self.values.push(value.into());
     }
 
     pub fn get_keys(&self) -> Vec<String> {
         self.keys.clone()
     }
 
-    pub fn get_values(&self) -> Vec<String> {
+    pub fn get_values(&self) -> Vec<Value> {
         self.values.clone()
     }
 }
 
-impl fmt::Display for DatabaseObject {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.keys.len() != self.values.len() {
+impl From<DatabaseObject> for Value {
+    fn from(object: DatabaseObject) -> Value {
+        if object.keys.len() != object.values.len() {
             panic!("Keys and values are not of the same length");
         }
 
-        let mut query = String::new();
-
-        query.push_str("{");
-
-        for (key, value) in self.keys.iter().zip(self.values.iter()) {
-            query.push_str(&format!("{}: {}, ", key, value));
-        }
-
-        query.pop();
-        query.pop();
-
-        query.push_str("}");
-
-        write!(f, "{}", query)
+        Value::Object(Object(object.keys.into_iter().zip(object.values).collect()))
     }
 }
 
@@ -378,21 +362,20 @@ impl fmt::Display for UpdateSetOperator {
 // fields is a vector of tuples of the form (field_name, update_operator, value)
 #[derive(Debug)]
 pub struct UpdateObjectForSet {
-    pub fields: Vec<(String, UpdateSetOperator, String)>,
+    pub fields: Vec<(String, UpdateSetOperator, Value)>,
 }
 
-impl fmt::Display for UpdateObjectForSet {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut query = String::new();
-
-        for (field_name, update_operator, value) in self.fields.iter() {
-            query.push_str(&format!("{} {} {}, ", field_name, update_operator, value));
-        }
-
-        query.pop();
-        query.pop();
+impl UpdateObjectForSet {
+    fn write_to(self, query: &mut Query) {
+        let assignments: Vec<String> = self
+            .fields
+            .into_iter()
+            .map(|(field_name, update_operator, value)| {
+                format!("{}{}{}", field_name, update_operator, query.bind(value))
+            })
+            .collect();
 
-        write!(f, "{}", query)
+        query.push(&assignments.join(", "));
     }
 }
 
@@ -415,17 +398,14 @@ impl fmt::Display for UpdateOperator {
 // to insert a single json object into a table
 pub fn get_insert_query_for_an_object(
     table_name: String,
-    object: DatabaseObject,
+    object: impl Into<Value>,
     result: Return,
-) -> String {
-    let mut query = String::new();
+) -> Query {
+    let mut query = Query::raw("INSERT INTO ");
 
-    query.push_str("INSERT INTO ");
-    query.push_str(&table_name);
-    query.push_str(" ");
-    query.push_str(&object.to_string());
+    query.push(&table_name).push(" ").push_value(object);
 
-    query.push_str(&result.to_string());
+    query.push(&result.to_string());
 
     query
 }
@@ -435,61 +415,43 @@ pub fn get_insert_query_for_an_array_of_objects(
     table_name: String,
     objects: Vec<DatabaseObject>,
     result: Return,
-) -> String {
-    let mut query = String::new();
+) -> Query {
+    let mut query = Query::raw("INSERT INTO ");
 
-    query.push_str("INSERT INTO ");
-    query.push_str(&table_name);
-    query.push_str(" [");
+    let objects: Vec<Value> = objects.into_iter().map(Value::from).collect();
 
-    for object in objects {
-        query.push_str(&object.to_string());
-        query.push_str(", ");
-    }
-
-    query.pop();
-    query.pop();
+    query.push(&table_name).push(" ").push_value(objects);
 
-    query.push_str("]");
-
-    query.push_str(&result.to_string());
+    query.push(&result.to_string());
 
     query
 }
 
 // to delete a specific record from a table with known record id
-pub fn get_delete_query_for_specific_record(table_name: String, record_id: String) -> String {
-    let mut query = String::new();
+pub fn get_delete_query_for_specific_record(table_name: String, record_id: String) -> Query {
+    let mut query = Query::raw("DELETE ");
+
+    query.push_value(record(&table_name, record_id));
 
-    query.push_str("DELETE ");
-    query.push_str(&table_name);
-    query.push_str(":");
-    query.push_str(&record_id);
+    query.push(&Return::Before.to_string());
 
     query
 }
 
 // to delete records from a table with conditions
-// conditions follow same syntax as in get_select_query
 pub fn get_delete_query_with_conditions(
-    table_name: String,
-    condition: Vec<(Expression, ExpressionConnector)>,
+    table_name: Item,
+    condition: Condition,
     result: Option<Return>,
-) -> String {
-    let mut query = String::new();
+) -> Query {
+    let mut query = Query::raw("DELETE ");
 
-    query.push_str("DELETE ");
-    query.push_str(&table_name);
-    query.push_str(" WHERE ");
+    table_name.write_to(&mut query);
 
-    for (expression_1, expression_2) in condition {
-        query.push_str(&expression_1.to_string());
-        query.push_str(&expression_2.to_string());
-    }
+    write_condition(&mut query, condition);
 
-    match result {
-        None => {}
-        Some(result) => query.push_str(&result.to_string()),
+    if let Some(result) = result {
+        query.push(&result.to_string());
     }
 
     query
@@ -498,18 +460,16 @@ pub fn get_delete_query_with_conditions(
 // to create a record with a specific id or auto generated one
 pub fn get_create_query_for_an_object(
     table_name: Item,
-    object: DatabaseObject,
+    object: impl Into<Value>,
     result: Return,
-) -> String {
-    let mut query = String::from("CREATE ");
-
-    query.push_str(&table_name.to_string());
+) -> Query {
+    let mut query = Query::raw("CREATE ");
 
-    query.push_str(" CONTENT ");
+    table_name.write_to(&mut query);
 
-    query.push_str(&object.to_string());
+    query.push(" CONTENT ").push_value(object);
 
-    query.push_str(&result.to_string());
+    query.push(&result.to_string());
 
     query
 }
@@ -519,18 +479,15 @@ pub fn get_relate_query_with_content(
     to: Thing,
     relation_name: String,
     content: Option<DatabaseObject>,
-) -> String {
-    let mut query = String::from("Relate ");
+) -> Query {
+    let mut query = Query::raw("RELATE ");
 
-    query.push_str(&from.to_string());
-    query.push_str("->");
-    query.push_str(&relation_name);
-    query.push_str("->");
-    query.push_str(&to.to_string());
+    query.push_value(from);
+    query.push(&format!("->{}->", relation_name));
+    query.push_value(to);
 
-    match content {
-        None => {}
-        Some(content) => query.push_str(&content.to_string()),
+    if let Some(content) = content {
+        query.push(" CONTENT ").push_value(content);
     }
 
     query
@@ -539,25 +496,23 @@ pub fn get_relate_query_with_content(
 pub fn get_update_query_with_set_opertor(
     table: Item,
     update_object: UpdateObjectForSet,
-    condition: Option<Vec<(Expression, ExpressionConnector)>>,
+    condition: Option<Condition>,
     result: Option<Return>,
-) -> String {
-    let mut query = String::from("UPDATE ");
+) -> Query {
+    let mut query = Query::raw("UPDATE ");
 
-    query.push_str(&table.to_string());
+    table.write_to(&mut query);
 
-    query.push_str(" SET ");
+    query.push(" SET ");
 
-    query.push_str(&update_object.to_string());
+    update_object.write_to(&mut query);
 
-    for (expression_1, expression_2) in condition.unwrap() {
-        query.push_str(&expression_1.to_string());
-        query.push_str(&expression_2.to_string());
+    if let Some(condition) = condition {
+        write_condition(&mut query, condition);
     }
 
-    match result {
-        None => {}
-        Some(result) => query.push_str(&result.to_string()),
+    if let Some(result) = result {
+        query.push(&result.to_string());
     }
 
     query
@@ -566,21 +521,409 @@ pub fn get_update_query_with_set_opertor(
 pub fn get_update_query_for_merge_or_content(
     table: Item,
     update_operator: UpdateOperator,
-    update_object: DatabaseObject,
+    update_object: impl Into<Value>,
+    condition: Option<Condition>,
     result: Option<Return>,
-) -> String {
-    let mut query = String::from("UPDATE ");
+) -> Query {
+    let mut query = Query::raw("UPDATE ");
+
+    table.write_to(&mut query);
 
-    query.push_str(&table.to_string());
+    query.push(&update_operator.to_string());
 
-    query.push_str(&update_operator.to_string());
+    query.push_value(update_object);
 
-    query.push_str(&update_object.to_string());
+    if let Some(condition) = condition {
+        write_condition(&mut query, condition);
+    }
 
-    match result {
-        None => {}
-        Some(result) => query.push_str(&result.to_string()),
+    if let Some(result) = result {
+        query.push(&result.to_string());
     }
 
     query
 }
+
+// fluent builder for select queries
+// conditions are built from fields and can be nested with and / or / not, e.g.
+// Select::from("post")
+//     .where_(field("access_level").eq("public").or(field("caption").contains("news")))
+//     .order_by(OrderBy::Descending(vec!["time".to_string()]))
+//     .limit(10)
+//     .build()
+// NOTE: tables, fields and edges are part of the statement, values are always bound
+
+// a field (or any other idiom chosen by the code) used on the left side of a condition
+#[derive(Debug, Clone)]
+pub struct Field(String);
+
+pub fn field(name: &str) -> Field {
+    Field(name.to_string())
+}
+
+// the comparisons cover what SurrealQL offers, not every one of them is used yet
+#[allow(dead_code)]
+impl Field {
+    fn compare(self, operator: &'static str, value: impl Into<Value>) -> Condition {
+        Condition::Compare {
+            left: self.0,
+            operator,
+            value: value.into(),
+        }
+    }
+
+    pub fn eq(self, value: impl Into<Value>) -> Condition {
+        self.compare("=", value)
+    }
+
+    pub fn ne(self, value: impl Into<Value>) -> Condition {
+        self.compare("!=", value)
+    }
+
+    pub fn gt(self, value: impl Into<Value>) -> Condition {
+        self.compare(">", value)
+    }
+
+    pub fn gte(self, value: impl Into<Value>) -> Condition {
+        self.compare(">=", value)
+    }
+
+    pub fn lt(self, value: impl Into<Value>) -> Condition {
+        self.compare("<", value)
+    }
+
+    pub fn lte(self, value: impl Into<Value>) -> Condition {
+        self.compare("<=", value)
+    }
+
+    // field is one of the given values
+    pub fn in_(self, values: Vec<Value>) -> Condition {
+        self.compare("IN", values)
+    }
+
+    // field is an array containing the value
+    pub fn contains(self, value: impl Into<Value>) -> Condition {
+        self.compare("CONTAINS", value)
+    }
+
+    // field is an array containing any of the values
+    pub fn contains_any(self, values: Vec<Value>) -> Condition {
+        self.compare("CONTAINSANY", values)
+    }
+
+    // field is inside the given array
+    pub fn inside(self, values: Vec<Value>) -> Condition {
+        self.compare("INSIDE", values)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_none(self) -> Condition {
+        self.compare("=", Value::None)
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    pub fn is_not_none(self) -> Condition {
+        self.compare("!=", Value::None)
+    }
+}
+
+// graph traversal from the current record, e.g. graph().out("create_post").to("post") is ->create_post->post
+#[derive(Debug, Clone, Default)]
+pub struct Graph(String);
+
+pub fn graph() -> Graph {
+    Graph::default()
+}
+
+impl Graph {
+    // follows outgoing edges (->edge)
+    pub fn out(mut self, edge: &str) -> Graph {
+        self.0.push_str("->");
+        self.0.push_str(edge);
+        self
+    }
+
+    // follows incoming edges (<-edge)
+    pub fn in_(mut self, edge: &str) -> Graph {
+        self.0.push_str("<-");
+        self.0.push_str(edge);
+        self
+    }
+
+    // records on the other side of the last outgoing edge
+    pub fn to(self, table: &str) -> Graph {
+        self.out(table)
+    }
+
+    // records on the other side of the last incoming edge
+    pub fn from(self, table: &str) -> Graph {
+        self.in_(table)
+    }
+
+    // every field of the records at the end of the traversal
+    pub fn all(self) -> String {
+        self.0 + ".*"
+    }
+
+    // the traversal used as a field of a condition
+    pub fn field(self) -> Field {
+        Field(self.0)
+    }
+}
+
+impl fmt::Display for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// condition of a WHERE clause
+#[derive(Debug, Clone)]
+pub enum Condition {
+    Compare {
+        left: String,
+        operator: &'static str,
+        value: Value,
+    },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn and(self, other: Condition) -> Condition {
+        Condition::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Condition) -> Condition {
+        Condition::Or(Box::new(self), Box::new(other))
+    }
+
+    // every condition holds, None if there are no conditions
+    pub fn all(conditions: Vec<Condition>) -> Option<Condition> {
+        conditions.into_iter().reduce(Condition::and)
+    }
+
+    // any of the conditions holds, None if there are no conditions
+    #[allow(dead_code)]
+    pub fn any(conditions: Vec<Condition>) -> Option<Condition> {
+        conditions.into_iter().reduce(Condition::or)
+    }
+
+    // nested conditions are wrapped in brackets so (a OR b) AND c keeps its meaning
+    fn write_to(self, query: &mut Query) {
+        match self {
+            Condition::Compare {
+                left,
+                operator,
+                value,
+            } => {
+                query.push(&format!("{} {} ", left, operator));
+                query.push_value(value);
+            }
+            Condition::And(left, right) => Condition::write_pair(query, *left, "AND", *right),
+            Condition::Or(left, right) => Condition::write_pair(query, *left, "OR", *right),
+            Condition::Not(condition) => {
+                query.push("!(");
+                condition.write_to(query);
+                query.push(")");
+            }
+        }
+    }
+
+    fn write_pair(query: &mut Query, left: Condition, connector: &str, right: Condition) {
+        query.push("(");
+        left.write_to(query);
+        query.push(&format!(" {} ", connector));
+        right.write_to(query);
+        query.push(")");
+    }
+}
+
+// !condition negates it
+impl Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Condition {
+        Condition::Not(Box::new(self))
+    }
+}
+
+// builder for a SELECT statement
+#[derive(Debug)]
+pub struct Select {
+    fields: Vec<String>,
+    value: bool,
+    from: Item,
+    condition: Option<Condition>,
+    split: Vec<String>,
+    group: Option<Vec<String>>,
+    order: Option<OrderBy>,
+    limit: Option<i64>,
+    start: Option<i64>,
+    fetch: Vec<String>,
+}
+
+impl Select {
+    // selects from a table
+    #[allow(clippy::should_implement_trait)]
+    pub fn from(table: &str) -> Select {
+        Select::from_item(Item::Table(table.to_string()))
+    }
+
+    // selects from a single record, useful for graph traversals starting at it
+    pub fn from_record(record: Thing) -> Select {
+        Select::from_item(Item::Record(record))
+    }
+
+    fn from_item(from: Item) -> Select {
+        Select {
+            fields: vec![],
+            value: false,
+            from,
+            condition: None,
+            split: vec![],
+            group: None,
+            order: None,
+            limit: None,
+            start: None,
+            fetch: vec![],
+        }
+    }
+
+    // selects the given fields instead of *
+    pub fn fields(mut self, fields: &[&str]) -> Select {
+        self.fields
+            .extend(fields.iter().map(|field| field.to_string()));
+        self
+    }
+
+    // selects an expression (e.g. a graph traversal) under a name
+    pub fn field_as(mut self, expression: impl fmt::Display, alias: &str) -> Select {
+        self.fields.push(format!("{} AS {}", expression, alias));
+        self
+    }
+
+    // selects the values of a single field instead of objects (SELECT VALUE)
+    pub fn value(mut self, field: impl fmt::Display) -> Select {
+        self.value = true;
+        self.fields = vec![field.to_string()];
+        self
+    }
+
+    // adds a condition, conditions added more than once must all hold
+    pub fn where_(mut self, condition: Condition) -> Select {
+        self.condition = match self.condition {
+            None => Some(condition),
+            Some(existing) => Some(existing.and(condition)),
+        };
+        self
+    }
+
+    // adds a condition when there is one
+    pub fn where_some(self, condition: Option<Condition>) -> Select {
+        match condition {
+            None => self,
+            Some(condition) => self.where_(condition),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn split(mut self, field: &str) -> Select {
+        self.split.push(field.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn group_by(mut self, fields: &[&str]) -> Select {
+        self.group = Some(fields.iter().map(|field| field.to_string()).collect());
+        self
+    }
+
+    pub fn group_all(mut self) -> Select {
+        self.group = Some(vec![]);
+        self
+    }
+
+    // NOTE: column names are part of the statement, check names from a request with is_field_name
+    pub fn order_by(mut self, order: OrderBy) -> Select {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Select {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn start(mut self, start: i64) -> Select {
+        self.start = Some(start);
+        self
+    }
+
+    // replaces record links in the given fields with the records they point to
+    #[allow(dead_code)]
+    pub fn fetch(mut self, fields: &[&str]) -> Select {
+        self.fetch
+            .extend(fields.iter().map(|field| field.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Query {
+        let mut query = Query::raw("SELECT ");
+
+        if self.value {
+            query.push("VALUE ");
+        }
+
+        match self.fields.is_empty() {
+            true => query.push("*"),
+            false => query.push(&self.fields.join(", ")),
+        };
+
+        query.push(" FROM ");
+        self.from.write_to(&mut query);
+
+        if let Some(condition) = self.condition {
+            query.push(" WHERE ");
+            condition.write_to(&mut query);
+        }
+
+        if !self.split.is_empty() {
+            query.push(&format!(" SPLIT {}", self.split.join(", ")));
+        }
+
+        match self.group {
+            None => {}
+            Some(fields) if fields.is_empty() => {
+                query.push(" GROUP ALL");
+            }
+            Some(fields) => {
+                query.push(&format!(" GROUP BY {}", fields.join(", ")));
+            }
+        }
+
+        if let Some(order) = self.order {
+            query.push(&order.to_string());
+        }
+
+        if let Some(limit) = self.limit {
+            query.push(" LIMIT ").push_value(limit);
+        }
+
+        if let Some(start) = self.start {
+            query.push(" START ").push_value(start);
+        }
+
+        if !self.fetch.is_empty() {
+            query.push(&format!(" FETCH {}", self.fetch.join(", ")));
+        }
+
+        query
+    }
+
+    // builds and runs the query
+    pub async fn execute(self, db: &Surreal<Any>) -> Result<Response, surrealdb::Error> {
+        self.build().execute(db).await
+    }
+}
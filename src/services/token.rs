@@ -0,0 +1,30 @@
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+// random identifier of the given number of bytes, hex encoded
+pub fn random_hex(length: usize) -> String {
+    let bytes: Vec<u8> = (0..length).map(|_| rand::thread_rng().gen()).collect();
+    to_hex(&bytes)
+}
+
+// hex encoded sha256 hash, used to store tokens without storing the tokens themselves
+pub fn sha256_hex(token: &str) -> String {
+    to_hex(&Sha256::digest(token.as_bytes()))
+}
+
+// hex encoded HMAC-SHA256 of the message, a hash only the holder of the key can compute
+pub fn hmac_sha256_hex(key: &[u8], message: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(message);
+    to_hex(&mac.finalize().into_bytes())
+}
+
+// compares without returning early, so the time taken does not tell how much matched
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,86 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha1::Sha1;
+
+use crate::services::token::constant_time_eq;
+
+// time based one time passwords (RFC 6238) as used by authenticator apps:
+// HMAC-SHA1, 6 digits, a new code every 30 seconds
+
+const ISSUER: &str = "Ufora";
+const DIGITS: u32 = 6;
+const STEP_SECONDS: i64 = 30;
+// codes of the previous and the next step are accepted too, clocks drift
+const ALLOWED_SKEW_STEPS: i64 = 1;
+
+// new random secret, base32 encoded as authenticator apps expect it
+pub fn generate_secret() -> String {
+    let bytes: [u8; 20] = rand::thread_rng().gen();
+    BASE32_NOPAD.encode(&bytes)
+}
+
+// uri shown as a qr code to add the account to an authenticator app
+pub fn otpauth_uri(account: &str, secret: &str) -> String {
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        encode(ISSUER),
+        encode(account),
+        secret,
+        encode(ISSUER),
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+// code for the step the given unix time falls in, none if the secret is not valid base32
+// only the tests need it, they stand in for the authenticator app
+#[cfg(test)]
+pub fn code_at(secret: &str, time: i64) -> Option<String> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    Some(code_for_step(&key, time.div_euclid(STEP_SECONDS)))
+}
+
+// checks a code against the steps around the given unix time
+// returns the step the code belongs to, so that it can not be used again
+pub fn verify(secret: &str, code: &str, time: i64) -> Option<i64> {
+    let key = BASE32_NOPAD.decode(secret.as_bytes()).ok()?;
+    let step = time.div_euclid(STEP_SECONDS);
+
+    (step - ALLOWED_SKEW_STEPS..=step + ALLOWED_SKEW_STEPS)
+        .find(|step| constant_time_eq(code_for_step(&key, *step).as_bytes(), code.as_bytes()))
+}
+
+fn code_for_step(key: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    // dynamic truncation
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        hash[offset] & 0x7f,
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]);
+
+    format!(
+        "{:0width$}",
+        value % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+// percent encoding for the label and issuer of the otpauth uri
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{config::Config, services::email::Mailer};
+
+// state shared with every router and handler
+// handlers extract only the parts they need, e.g. State<Arc<Surreal<Any>>>
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Arc<Surreal<Any>>,
+    pub config: Arc<Config>,
+    pub mailer: Mailer,
+}
+
+impl FromRef<AppState> for Arc<Surreal<Any>> {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<Config> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for Mailer {
+    fn from_ref(state: &AppState) -> Self {
+        state.mailer.clone()
+    }
+}
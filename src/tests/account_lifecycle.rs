@@ -0,0 +1,247 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use surrealdb::sql::Thing;
+
+use super::harness::{TestApp, TestResponse, TEST_PASSWORD};
+use crate::models::account::Account;
+
+async fn profile(app: &TestApp, username: &str, token: &str) -> TestResponse {
+    app.request(
+        Method::GET,
+        "/api/profile/retrieveProfile",
+        Some(json!({ "username": username })),
+        Some(token),
+    )
+    .await
+}
+
+async fn create_post(app: &TestApp, token: &str, username: &str) -> String {
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+            Some(token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE out FROM create_post WHERE in.username = $username")
+        .bind(("username", username))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    ids[0].id.to_raw()
+}
+
+async fn comment(app: &TestApp, token: &str, post_id: &str) -> TestResponse {
+    app.patch(
+        &format!("/api/post/comment/add/{}", post_id),
+        Some(json!({ "text": "nice" })),
+        Some(token),
+    )
+    .await
+}
+
+async fn request_deletion(app: &TestApp, token: &str, password: &str) -> TestResponse {
+    app.post(
+        "/api/account/delete",
+        json!({ "password": password }),
+        Some(token),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn a_deactivated_account_is_hidden_until_it_is_reactivated() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let kamal = app.signed_in("kamal").await;
+    let post_id = create_post(&app, &nimal, "nimal").await;
+
+    let response = app
+        .post("/api/account/deactivate", json!({}), Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = profile(&app, "nimal", &kamal).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // the public post can no longer be found by others, its creator still finds it
+    let response = comment(&app, &kamal, &post_id).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app
+        .patch(
+            &format!("/api/post/reaction/{}", post_id),
+            None,
+            Some(&kamal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = comment(&app, &nimal, &post_id).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .request(
+            Method::GET,
+            "/api/profile/allProfiles",
+            Some(json!({})),
+            Some(&kamal),
+        )
+        .await;
+    let usernames: Vec<&str> = response
+        .body
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["username"].as_str().unwrap())
+        .collect();
+    assert_eq!(usernames, vec!["kamal"]);
+
+    let response = app
+        .post("/api/account/reactivate", json!({}), Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = profile(&app, "nimal", &kamal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = comment(&app, &kamal, &post_id).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn a_deletion_can_be_cancelled_during_the_grace_period() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let kamal = app.signed_in("kamal").await;
+
+    let response = request_deletion(&app, &nimal, "Wrong-Password-1").await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = request_deletion(&app, &nimal, TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body["deletion_scheduled_for"].is_string());
+    let email = app.mail.last_email_to("nimal@ufora.test").unwrap();
+    assert_eq!(email.subject, "Your Ufora account will be deleted");
+
+    // the account is hidden right away but only deleted after the grace period
+    let response = profile(&app, "nimal", &kamal).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(Account::delete_due(&app.db, &app.mailer).await.unwrap(), 0);
+
+    let response = app
+        .post("/api/account/reactivate", json!({}), Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post("/api/account/delete/cancel", json!({}), Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = profile(&app, "nimal", &kamal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .post("/api/account/delete/cancel", json!({}), Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn a_deleted_account_leaves_no_trace_of_its_owner() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let kamal = app.signed_in("kamal").await;
+    create_post(&app, &nimal, "nimal").await;
+    let kamal_post = create_post(&app, &kamal, "kamal").await;
+
+    let response = app
+        .patch(
+            &format!("/api/post/reaction/{}", kamal_post),
+            None,
+            Some(&nimal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app
+        .patch(
+            &format!("/api/post/comment/add/{}", kamal_post),
+            Some(json!({ "text": "nice" })),
+            Some(&nimal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = request_deletion(&app, &nimal, TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // the grace period is over
+    app.db
+        .query(
+            "UPDATE user SET deletion_scheduled_for = <datetime> '2020-01-01T00:00:00Z' \
+             WHERE username = 'nimal'",
+        )
+        .await
+        .unwrap();
+    assert_eq!(Account::delete_due(&app.db, &app.mailer).await.unwrap(), 1);
+
+    let email = app.mail.last_email_to("nimal@ufora.test").unwrap();
+    assert_eq!(email.subject, "Your Ufora account has been deleted");
+
+    let response = app.get("/api/sessions", Some(&nimal)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": "nimal", "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let users: Vec<Value> = app
+        .db
+        .query("SELECT username, email, password, name FROM user WHERE deleted_at != NONE")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(users.len(), 1);
+    assert!(users[0]["username"]
+        .as_str()
+        .unwrap()
+        .starts_with("deleted-"));
+    assert!(users[0]["email"]
+        .as_str()
+        .unwrap()
+        .ends_with("@deleted.invalid"));
+    assert!(users[0]["password"].is_null());
+    assert!(users[0]["name"].is_null());
+
+    // only the post of kamal is left, without the reaction and comment of nimal
+    let posts: Vec<Value> = app
+        .db
+        .query("SELECT reactions, comments FROM post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(posts, vec![json!({ "reactions": [], "comments": [] })]);
+
+    let edges: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM create_post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(edges.len(), 1);
+
+    // kamal is not affected
+    let response = profile(&app, "kamal", &kamal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
@@ -0,0 +1,128 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::harness::{TestApp, TEST_PASSWORD};
+
+const EMAIL: &str = "nimal@ufora.test";
+const NEW_PASSWORD: &str = "Battery-Staple-7";
+
+// asks for a password reset otp, returns the token sent back with it
+async fn request_reset_otp(app: &TestApp) -> String {
+    let response = app
+        .post(
+            "/api/password/reset/otp/request",
+            json!({ "email": EMAIL }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["OTPSent"]["token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn verify_reset_otp(app: &TestApp, token: &str) -> (StatusCode, serde_json::Value) {
+    let otp = app.last_otp_sent_to(EMAIL);
+    let response = app
+        .post(
+            "/api/password/reset/otp/verify",
+            json!({ "token": token, "otp": otp }),
+            None,
+        )
+        .await;
+
+    (response.status, response.body)
+}
+
+async fn reset_password(app: &TestApp, password_reset_token: &str) -> StatusCode {
+    app.post(
+        "/api/password/reset",
+        json!({
+            "password": NEW_PASSWORD,
+            "confirm_password": NEW_PASSWORD,
+            "password_reset_token": password_reset_token,
+        }),
+        None,
+    )
+    .await
+    .status
+}
+
+#[tokio::test]
+async fn a_password_can_be_reset_once_per_token() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let token = request_reset_otp(&app).await;
+    let (status, body) = verify_reset_otp(&app, &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let password_reset_token = body["OTPVerified"]["password_reset_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    assert_eq!(
+        reset_password(&app, &password_reset_token).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        reset_password(&app, &password_reset_token).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": "nimal", "password": NEW_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": "nimal", "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn a_token_is_only_accepted_for_its_purpose() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    // the token of the otp step does not allow setting a password
+    let token = request_reset_otp(&app).await;
+    assert_eq!(reset_password(&app, &token).await, StatusCode::BAD_REQUEST);
+
+    // nor does a token of the email verification
+    let response = app
+        .post("/api/otp/request", json!({ "email": EMAIL }), None)
+        .await;
+    let token = response.body["token"].as_str().unwrap();
+    assert_eq!(reset_password(&app, token).await, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn a_tampered_token_is_rejected() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let token = request_reset_otp(&app).await;
+    let (payload, signature) = token.split_once('.').unwrap();
+    let mut signature = signature.to_string();
+    let first = if signature.starts_with('A') { "B" } else { "A" };
+    signature.replace_range(..1, first);
+
+    let (status, _) = verify_reset_otp(&app, &format!("{}.{}", payload, signature)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // the untouched token still works
+    let (status, body) = verify_reset_otp(&app, &token).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
@@ -0,0 +1,274 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use surrealdb::sql::Thing;
+
+use super::harness::{TestApp, TEST_PASSWORD};
+
+async fn user_id(app: &TestApp, username: &str) -> String {
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM user WHERE username = $username")
+        .bind(("username", username))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    ids[0].id.to_raw()
+}
+
+async fn create_club(app: &TestApp, token: &str, username: &str) -> String {
+    let response = app
+        .post(
+            "/api/club/create",
+            json!({
+                "username": username,
+                "name": format!("{} club", username),
+                "club_type": "sports",
+                "email": format!("{}@ufora.test", username),
+                "club_verification_file": "letter.pdf",
+            }),
+            Some(token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    user_id(app, username).await
+}
+
+fn usernames(users: &Value) -> Vec<&str> {
+    users
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|user| user["username"].as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn only_administrators_can_use_the_admin_api() {
+    let app = TestApp::spawn().await;
+    let student = app.signed_in("nimal").await;
+    let admin = app.signed_in_as_admin("root").await;
+
+    let response = app.get("/api/admin/stats", None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.get("/api/admin/stats", Some(&student)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "INSUFFICIENT_PERMISSIONS");
+
+    let response = app.get("/api/admin/stats", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["users"]["undergraduate"], 1);
+    assert_eq!(response.body["users"]["admin"], 1);
+
+    // a token issued before the administrator was demoted stops working
+    app.db
+        .query("UPDATE user SET user_type = 'undergraduate' WHERE username = 'root'")
+        .await
+        .unwrap();
+    let response = app.get("/api/admin/stats", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn users_can_be_searched() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+    app.signed_in("kamal").await;
+    app.signed_in_as("acme", "company").await;
+    let admin = app.signed_in_as_admin("root").await;
+
+    let response = app.get("/api/admin/users?search=MAL", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(usernames(&response.body), vec!["kamal", "nimal"]);
+    assert!(response.body[0].get("password").is_none());
+
+    let response = app
+        .get("/api/admin/users?user_type=company", Some(&admin))
+        .await;
+    assert_eq!(usernames(&response.body), vec!["acme"]);
+
+    let response = app
+        .get("/api/admin/users?limit=1&start=1", Some(&admin))
+        .await;
+    assert_eq!(usernames(&response.body), vec!["kamal"]);
+}
+
+#[tokio::test]
+async fn a_locked_user_can_not_log_in_until_unlocked() {
+    let app = TestApp::spawn().await;
+    let student = app.signed_in("nimal").await;
+    let admin = app.signed_in_as_admin("root").await;
+    let nimal = user_id(&app, "nimal").await;
+
+    let response = app
+        .post(
+            &format!("/api/admin/users/{}/lock", nimal),
+            json!({}),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // the tokens issued before stop working
+    let response = app.get("/api/sessions", Some(&student)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let login = json!({ "username": "nimal", "password": TEST_PASSWORD });
+    let response = app.post("/api/login", login.clone(), None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.body["code"], "INACTIVE_USER");
+
+    let response = app.get("/api/admin/users?locked=true", Some(&admin)).await;
+    assert_eq!(usernames(&response.body), vec!["nimal"]);
+
+    let response = app
+        .post(
+            &format!("/api/admin/users/{}/unlock", nimal),
+            json!({}),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.post("/api/login", login, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn club_verification_files_can_be_approved_or_rejected() {
+    let app = TestApp::spawn().await;
+    let student = app.signed_in("nimal").await;
+    let admin = app.signed_in_as_admin("root").await;
+    let chess = create_club(&app, &student, "chess").await;
+    let drama = create_club(&app, &student, "drama").await;
+
+    let response = app.get("/api/admin/clubs/pending", Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(usernames(&response.body), vec!["chess", "drama"]);
+
+    let response = app
+        .post(
+            &format!("/api/admin/clubs/{}/approve", chess),
+            json!({}),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .post(
+            &format!("/api/admin/clubs/{}/reject", drama),
+            json!({ "reason": "The letter is not signed" }),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.get("/api/admin/clubs/pending", Some(&admin)).await;
+    assert_eq!(response.body, json!([]));
+
+    let response = app
+        .get(&format!("/api/admin/users/{}", chess), Some(&admin))
+        .await;
+    assert_eq!(response.body["club_verification_flag"], true);
+
+    // only clubs can be verified
+    let nimal = user_id(&app, "nimal").await;
+    let response = app
+        .post(
+            &format!("/api/admin/clubs/{}/approve", nimal),
+            json!({}),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn posts_blogs_and_comments_can_be_removed() {
+    let app = TestApp::spawn().await;
+    let student = app.signed_in("nimal").await;
+    let admin = app.signed_in_as_admin("root").await;
+
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+            Some(&student),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    let post_id = ids[0].id.to_raw();
+
+    let response = app
+        .patch(
+            &format!("/api/post/comment/add/{}", post_id),
+            Some(json!({ "text": "spam" })),
+            Some(&student),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let comment_ids: Vec<Vec<String>> = app
+        .db
+        .query("SELECT VALUE comments.id FROM post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    let comment_uri = format!(
+        "/api/admin/posts/{}/comments/{}",
+        post_id, comment_ids[0][0]
+    );
+
+    let response = app.delete(&comment_uri, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.delete(&comment_uri, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let post_uri = format!("/api/admin/posts/{}", post_id);
+    let response = app.delete(&post_uri, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let response = app.delete(&post_uri, Some(&admin)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .post(
+            "/api/blog/create",
+            json!({
+                "title": "A blog title",
+                "content": {
+                    "time": 1694000000000_i64,
+                    "blocks": [{ "id": "b1", "type": "paragraph", "data": { "text": "hello" } }],
+                    "version": "2.27.2",
+                },
+            }),
+            Some(&student),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM blog")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    let response = app
+        .delete(
+            &format!("/api/admin/blogs/{}", ids[0].id.to_raw()),
+            Some(&admin),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
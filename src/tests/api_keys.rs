@@ -0,0 +1,254 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TestResponse};
+
+async fn create_key(app: &TestApp, token: &str, body: Value) -> TestResponse {
+    app.post("/api/keys", body, Some(token)).await
+}
+
+async fn with_key(
+    app: &TestApp,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+    key: &str,
+) -> TestResponse {
+    app.request_with_headers(method, uri, body, &[("x-api-key", key)])
+        .await
+}
+
+// a request with the key over a connection from the given address
+async fn with_key_from(
+    app: &TestApp,
+    key: &str,
+    peer: &str,
+    forwarded_for: Option<&str>,
+) -> TestResponse {
+    let mut headers = vec![("x-api-key", key)];
+    if let Some(forwarded_for) = forwarded_for {
+        headers.push(("x-forwarded-for", forwarded_for));
+    }
+
+    app.request_from(peer, Method::GET, "/api/blog/get", None, &headers)
+        .await
+}
+
+// a key for blogs:read from 203.0.113.0/24 only
+async fn allowlisted_key(app: &TestApp, token: &str) -> String {
+    let response = create_key(
+        app,
+        token,
+        json!({ "name": "bot", "scopes": ["blogs:read"], "allowed_ips": ["203.0.113.0/24"] }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["key"].as_str().unwrap().to_string()
+}
+
+fn new_post() -> Value {
+    json!({
+        "caption": "We are hiring",
+        "access_level": "public",
+        "content": "Interns for the summer",
+    })
+}
+
+#[tokio::test]
+async fn keys_only_reach_the_routes_of_their_scopes() {
+    let app = TestApp::spawn().await;
+    let acme = app.signed_in_as("acme", "company").await;
+
+    let response = create_key(
+        &app,
+        &acme,
+        json!({ "name": "careers site", "scopes": ["posts:write"] }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let key = response.body["key"].as_str().unwrap().to_string();
+
+    let response = with_key(
+        &app,
+        Method::POST,
+        "/api/post/create",
+        Some(new_post()),
+        &key,
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // the post belongs to the owner of the key
+    let owners: Vec<String> = app
+        .db
+        .query("SELECT VALUE in.username FROM create_post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(owners, vec!["acme".to_string()]);
+
+    let response = with_key(&app, Method::GET, "/api/blog/get", None, &key).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    // keys can not create further keys
+    let response = with_key(
+        &app,
+        Method::POST,
+        "/api/keys",
+        Some(json!({ "name": "another", "scopes": ["posts:write"] })),
+        &key,
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app.get("/api/keys", Some(&acme)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let keys = response.body.as_array().unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0]["name"], "careers site");
+    assert!(keys[0].get("key").is_none());
+    assert!(keys[0].get("key_hash").is_none());
+    assert!(!keys[0]["last_used_at"].is_null());
+
+    let response = with_key(
+        &app,
+        Method::GET,
+        "/api/blog/get",
+        None,
+        "ufora_nothing_here",
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn only_companies_manage_keys_with_known_scopes() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let acme = app.signed_in_as("acme", "company").await;
+
+    let response = create_key(
+        &app,
+        &nimal,
+        json!({ "name": "bot", "scopes": ["posts:write"] }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = create_key(
+        &app,
+        &acme,
+        json!({ "name": "bot", "scopes": ["users:delete"] }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = create_key(&app, &acme, json!({ "name": "bot", "scopes": [] })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn revoked_keys_stop_working() {
+    let app = TestApp::spawn().await;
+    let acme = app.signed_in_as("acme", "company").await;
+    let globex = app.signed_in_as("globex", "company").await;
+
+    let response = create_key(
+        &app,
+        &acme,
+        json!({ "name": "blog sync", "scopes": ["blogs:read"] }),
+    )
+    .await;
+    let key = response.body["key"].as_str().unwrap().to_string();
+    let key_id = response.body["id"].as_str().unwrap().to_string();
+
+    let response = with_key(&app, Method::GET, "/api/blog/get", None, &key).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // another company can not revoke it
+    let response = app
+        .delete(&format!("/api/keys/{}", key_id), Some(&globex))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .delete(&format!("/api/keys/{}", key_id), Some(&acme))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = with_key(&app, Method::GET, "/api/blog/get", None, &key).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.get("/api/keys", Some(&acme)).await;
+    assert_eq!(response.body, json!([]));
+
+    let response = app
+        .delete(&format!("/api/keys/{}", key_id), Some(&acme))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn keys_with_an_allowlist_only_work_from_those_addresses() {
+    let app = TestApp::spawn().await;
+    let acme = app.signed_in_as("acme", "company").await;
+
+    let response = create_key(
+        &app,
+        &acme,
+        json!({ "name": "bot", "scopes": ["blogs:read"], "allowed_ips": ["not an address"] }),
+    )
+    .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let key = allowlisted_key(&app, &acme).await;
+
+    let response = with_key_from(&app, &key, "203.0.113.7", None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = with_key_from(&app, &key, "198.51.100.1", None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    // without an address the key can not be checked against the allowlist
+    let response = with_key(&app, Method::GET, "/api/blog/get", None, &key).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn a_forged_forwarded_for_does_not_pass_the_allowlist() {
+    let app = TestApp::spawn().await;
+    let acme = app.signed_in_as("acme", "company").await;
+    let key = allowlisted_key(&app, &acme).await;
+
+    // the client is not a trusted proxy, so its x-forwarded-for is ignored
+    let response = with_key_from(&app, &key, "198.51.100.1", Some("203.0.113.7")).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn behind_a_trusted_proxy_the_forwarded_client_is_checked() {
+    let app = TestApp::spawn_with(|table| {
+        let mut server = toml::Table::new();
+        server.insert(
+            "trusted_proxies".to_string(),
+            toml::Value::Array(vec!["10.0.0.0/8".into()]),
+        );
+        table.insert("server".to_string(), server.into());
+    })
+    .await;
+    let acme = app.signed_in_as("acme", "company").await;
+    let key = allowlisted_key(&app, &acme).await;
+
+    let response = with_key_from(&app, &key, "10.0.0.2", Some("203.0.113.7")).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // further trusted proxies on the way are skipped
+    let response = with_key_from(&app, &key, "10.0.0.2", Some("203.0.113.7, 10.1.2.3")).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // an allowed address the client put in front of its own is not believed
+    let response = with_key_from(&app, &key, "10.0.0.2", Some("203.0.113.7, 198.51.100.1")).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
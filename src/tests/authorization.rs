@@ -0,0 +1,203 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use surrealdb::sql::Thing;
+
+use super::harness::TestApp;
+
+// creates a post as the given user and returns its id
+// NOTE: a test creates at most one post per access level
+async fn create_post(app: &TestApp, token: &str, access_level: &str) -> String {
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": access_level, "content": "content" }),
+            Some(token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM post WHERE access_level = $access_level")
+        .bind(("access_level", access_level))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    ids[0].id.to_raw()
+}
+
+async fn comment_ids(app: &TestApp, post_id: &str) -> Vec<String> {
+    let comments: Vec<Value> = app
+        .db
+        .query("SELECT VALUE comments FROM type::thing('post', $id)")
+        .bind(("id", post_id))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    comments[0]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|comment| comment["id"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn only_the_creator_can_delete_a_post() {
+    let app = TestApp::spawn().await;
+    let owner = app.signed_in("owner").await;
+    let other = app.signed_in("other").await;
+
+    let post_id = create_post(&app, &owner, "public").await;
+    let uri = format!("/api/post/delete/{}", post_id);
+
+    let response = app.delete(&uri, Some(&other)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "ACCESS_DENIED");
+
+    let response = app.delete(&uri, Some(&owner)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.delete(&uri, Some(&owner)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn only_the_creator_can_change_a_blog() {
+    let app = TestApp::spawn().await;
+    let owner = app.signed_in("owner").await;
+    let other = app.signed_in("other").await;
+
+    let content = json!({
+        "time": 1694000000000_i64,
+        "blocks": [{ "id": "b1", "type": "paragraph", "data": { "text": "hello" } }],
+        "version": "2.27.2",
+    });
+
+    let response = app
+        .post(
+            "/api/blog/create",
+            json!({ "title": "A blog title", "content": content }),
+            Some(&owner),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED, "{}", response.body);
+
+    let ids: Vec<Thing> = app
+        .db
+        .query("SELECT VALUE id FROM blog")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    let blog_id = ids[0].id.to_raw();
+
+    let response = app
+        .request(
+            axum::http::Method::PUT,
+            &format!("/api/blog/update/{}", blog_id),
+            Some(json!({ "title": "Taken over", "content": content })),
+            Some(&other),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "ACCESS_DENIED");
+
+    let response = app
+        .delete(&format!("/api/blog/delete/{}", blog_id), Some(&other))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .delete(&format!("/api/blog/delete/{}", blog_id), Some(&owner))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn private_posts_cannot_be_reacted_to_or_commented_on_by_others() {
+    let app = TestApp::spawn().await;
+    let owner = app.signed_in("owner").await;
+    let other = app.signed_in("other").await;
+
+    let private_post = create_post(&app, &owner, "only me").await;
+    let public_post = create_post(&app, &owner, "public").await;
+
+    let response = app
+        .patch(
+            &format!("/api/post/reaction/{}", private_post),
+            None,
+            Some(&other),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .patch(
+            &format!("/api/post/comment/add/{}", private_post),
+            Some(json!({ "text": "hi" })),
+            Some(&other),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app
+        .patch(
+            &format!("/api/post/reaction/{}", public_post),
+            None,
+            Some(&other),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .patch(
+            &format!("/api/post/comment/add/{}", private_post),
+            Some(json!({ "text": "note to self" })),
+            Some(&owner),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn comments_can_be_removed_by_their_author_and_the_post_creator() {
+    let app = TestApp::spawn().await;
+    let owner = app.signed_in("owner").await;
+    let author = app.signed_in("author").await;
+    let other = app.signed_in("other").await;
+
+    let post_id = create_post(&app, &owner, "public").await;
+
+    for _ in 0..2 {
+        let response = app
+            .patch(
+                &format!("/api/post/comment/add/{}", post_id),
+                Some(json!({ "text": "nice" })),
+                Some(&author),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    }
+
+    let comments = comment_ids(&app, &post_id).await;
+    assert_eq!(comments.len(), 2);
+
+    let uri = |comment_id: &str| format!("/api/post/comment/delete/{}/{}", post_id, comment_id);
+
+    let response = app.delete(&uri(&comments[0]), Some(&other)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "ACCESS_DENIED");
+
+    let response = app.delete(&uri(&comments[0]), Some(&author)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.delete(&uri(&comments[1]), Some(&owner)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    assert!(comment_ids(&app, &post_id).await.is_empty());
+}
@@ -0,0 +1,23 @@
+use super::harness::test_config;
+
+#[test]
+fn the_test_configuration_is_valid() {
+    test_config(|_| {}).unwrap();
+}
+
+#[test]
+fn a_short_encryption_key_is_rejected() {
+    let error = test_config(|table| {
+        let security = table["security"].as_table_mut().unwrap();
+        security.insert("encryption_key".to_string(), "sixteen-byte-key".into());
+    })
+    .unwrap_err();
+
+    assert!(
+        error
+            .to_string()
+            .contains("`security.encryption_key` must be at least 32 bytes"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+use axum::http::{header, StatusCode};
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TestResponse};
+
+async fn start_export(app: &TestApp, token: &str) -> TestResponse {
+    app.post("/api/account/export", json!({}), Some(token))
+        .await
+}
+
+// the archive is collected in the background
+async fn wait_until_done(app: &TestApp, token: &str, export_id: &str) -> Value {
+    for _ in 0..50 {
+        let response = app
+            .get(&format!("/api/account/export/{}", export_id), Some(token))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+        if response.body["status"] != "pending" {
+            return response.body;
+        }
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    panic!("the data export did not finish");
+}
+
+#[tokio::test]
+async fn an_export_collects_the_data_of_the_user_without_the_password() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    app.signed_in("kamal").await;
+
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+            Some(&nimal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = start_export(&app, &nimal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let export_id = response.body["id"].as_str().unwrap().to_string();
+
+    let export = wait_until_done(&app, &nimal, &export_id).await;
+    assert_eq!(export["status"], "ready");
+    assert!(export["completed_at"].is_string());
+
+    let response = app
+        .get(
+            &format!("/api/account/export/{}/download", export_id),
+            Some(&nimal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.headers[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap()
+        .starts_with("attachment"));
+
+    let archive = response.body;
+    assert_eq!(archive["user"]["username"], "nimal");
+    assert_eq!(archive["user"]["email"], "nimal@ufora.test");
+    assert!(archive["user"].get("password").is_none());
+    assert_eq!(archive["posts"].as_array().unwrap().len(), 1);
+    assert_eq!(archive["posts"][0]["caption"], "caption");
+    assert!(archive["blogs"].as_array().unwrap().is_empty());
+    assert!(archive["chats"].as_array().unwrap().is_empty());
+    assert!(!archive["login_history"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn an_export_is_only_visible_to_its_owner() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let kamal = app.signed_in("kamal").await;
+
+    let response = start_export(&app, &nimal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let export_id = response.body["id"].as_str().unwrap().to_string();
+    wait_until_done(&app, &nimal, &export_id).await;
+
+    let response = app
+        .get(&format!("/api/account/export/{}", export_id), Some(&kamal))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .get(
+            &format!("/api/account/export/{}/download", export_id),
+            Some(&kamal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn a_pending_export_can_not_be_downloaded() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+
+    // an export whose archive is still being collected
+    app.db
+        .query(
+            "CREATE data_export:waiting SET user = (SELECT VALUE id FROM user WHERE username = 'nimal')[0], \
+             status = 'pending', expires_at = time::now() + 7d",
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .get("/api/account/export/waiting/download", Some(&nimal))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // asking again returns the pending export instead of starting another one
+    let response = start_export(&app, &nimal).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["id"], "waiting");
+    assert_eq!(response.body["status"], "pending");
+}
@@ -0,0 +1,195 @@
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TestResponse, TEST_PASSWORD};
+
+async fn request_change(app: &TestApp, token: &str, new_email: &str) -> TestResponse {
+    app.post(
+        "/api/account/email",
+        json!({ "new_email": new_email, "password": TEST_PASSWORD }),
+        Some(token),
+    )
+    .await
+}
+
+async fn confirm_change(app: &TestApp, token: &str, otp: &str) -> TestResponse {
+    app.post(
+        "/api/account/email/confirm",
+        json!({ "otp": otp }),
+        Some(token),
+    )
+    .await
+}
+
+async fn addresses(app: &TestApp, username: &str) -> Value {
+    let users: Vec<Value> = app
+        .db
+        .query(
+            "SELECT email, pending_email, email_verification_flag FROM user \
+             WHERE username = $username",
+        )
+        .bind(("username", username))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    users[0].clone()
+}
+
+// the revert link of the last notification sent to the address, without the host
+fn revert_link_sent_to(app: &TestApp, address: &str) -> String {
+    let email = app.mail.last_email_to(address).unwrap();
+    let start = email.body.find("/api/account/email/revert").unwrap();
+
+    email.body[start..]
+        .split_whitespace()
+        .next()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn the_old_address_is_used_until_the_new_one_is_confirmed() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+
+    let response = request_change(&app, &nimal, "nimal@new.test").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let user = addresses(&app, "nimal").await;
+    assert_eq!(user["email"], "nimal@ufora.test");
+    assert_eq!(user["pending_email"], "nimal@new.test");
+
+    let response = confirm_change(&app, &nimal, "wrong").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(addresses(&app, "nimal").await["email"], "nimal@ufora.test");
+
+    let otp = app.last_otp_sent_to("nimal@new.test");
+    let response = confirm_change(&app, &nimal, &otp).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let user = addresses(&app, "nimal").await;
+    assert_eq!(user["email"], "nimal@new.test");
+    assert!(user["pending_email"].is_null());
+    assert_eq!(user["email_verification_flag"], true);
+
+    let email = app.mail.last_email_to("nimal@ufora.test").unwrap();
+    assert_eq!(
+        email.subject,
+        "The email address of your Ufora account was changed"
+    );
+
+    // nothing is left to confirm
+    let response = confirm_change(&app, &nimal, &otp).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn an_address_in_use_or_a_wrong_password_is_refused() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    app.signed_in("kamal").await;
+
+    let response = request_change(&app, &nimal, "kamal@ufora.test").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = request_change(&app, &nimal, "not-an-address").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post(
+            "/api/account/email",
+            json!({ "new_email": "nimal@new.test", "password": "Wrong-Password-1" }),
+            Some(&nimal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    assert!(addresses(&app, "nimal").await["pending_email"].is_null());
+}
+
+#[tokio::test]
+async fn the_old_address_can_revert_the_change_once() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+
+    request_change(&app, &nimal, "nimal@new.test").await;
+    let otp = app.last_otp_sent_to("nimal@new.test");
+    let response = confirm_change(&app, &nimal, &otp).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // whoever changed the address logs in again before the revert
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": "nimal", "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let other = response.body["Success"]["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let other_refresh = response.body["Success"]["refresh_token"].clone();
+
+    let link = revert_link_sent_to(&app, "nimal@ufora.test");
+    let (path, token) = link.split_once("?token=").unwrap();
+
+    // opening the link, e.g. by a mail scanner, changes nothing
+    let response = app.get(&link, None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(addresses(&app, "nimal").await["email"], "nimal@new.test");
+    let response = app.get("/api/sessions", Some(&nimal)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.post(path, json!({ "token": token }), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let user = addresses(&app, "nimal").await;
+    assert_eq!(user["email"], "nimal@ufora.test");
+    assert_eq!(user["email_verification_flag"], true);
+
+    // the account may have been taken over, every session and token issued before has ended
+    let response = app.get("/api/sessions", Some(&nimal)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app.get("/api/sessions", Some(&other)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .post(
+            "/api/token/refresh",
+            json!({ "refresh_token": other_refresh }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.post(path, json!({ "token": token }), None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = app.get(&link, None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn the_pending_address_is_not_shown_in_profiles() {
+    let app = TestApp::spawn().await;
+    let nimal = app.signed_in("nimal").await;
+    let kamal = app.signed_in("kamal").await;
+
+    let response = request_change(&app, &nimal, "nimal@new.test").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .request(
+            Method::GET,
+            "/api/profile/retrieveProfile",
+            Some(json!({ "username": "nimal" })),
+            Some(&kamal),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body["username"], "nimal");
+    assert!(response.body.get("pending_email").is_none());
+    assert!(response.body.get("password").is_none());
+}
@@ -0,0 +1,156 @@
+use axum::http::{header, StatusCode};
+use futures::future::join_all;
+use serde_json::json;
+
+use super::harness::{TestApp, TestResponse, TEST_PASSWORD};
+
+async fn login(app: &TestApp, username: &str, password: &str) -> TestResponse {
+    app.post(
+        "/api/login",
+        json!({ "username": username, "password": password }),
+        None,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn five_wrong_passwords_lock_the_account_until_it_is_unlocked() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    for _ in 0..4 {
+        let response = login(&app, "nimal", "wrong-password").await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.body["code"], "INVALID_CREDENTIALS");
+    }
+
+    let response = login(&app, "nimal", "wrong-password").await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.body["code"], "ACCOUNT_LOCKED");
+    assert!(response.body["details"]["locked_until"].is_string());
+    let retry_after: i64 = response.headers[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 60, "{}", retry_after);
+
+    // the right password does not help while the account is locked
+    let response = login(&app, "nimal", TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+
+    let response = app
+        .post(
+            "/api/account/unlock/request",
+            json!({ "email": "nimal@ufora.test" }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let otp = app.last_otp_sent_to("nimal@ufora.test");
+    let response = app
+        .post(
+            "/api/account/unlock",
+            json!({ "email": "nimal@ufora.test", "otp": otp }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = login(&app, "nimal", TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn concurrent_wrong_passwords_lock_the_account_once() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    for _ in 0..4 {
+        let response = login(&app, "nimal", "wrong-password").await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    }
+
+    // the fifth wrong password arrives several times at once
+    let attempts = (0..3).map(|_| login(&app, "nimal", "wrong-password"));
+    for response in join_all(attempts).await {
+        if response.status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after: i64 = response.headers[header::RETRY_AFTER]
+                .to_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!(retry_after > 0 && retry_after <= 60, "{}", retry_after);
+        } else {
+            assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        }
+    }
+
+    // a first offence, locked for the first duration only
+    let lockouts: Option<i64> = app
+        .db
+        .query("SELECT VALUE lockouts FROM user WHERE username = 'nimal'")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(lockouts, Some(1));
+}
+
+#[tokio::test]
+async fn an_unlock_otp_is_only_sent_to_locked_accounts() {
+    let app = TestApp::spawn().await;
+    app.signed_in("kamal").await;
+    let emails_sent = app.mail.count();
+
+    let response = app
+        .post(
+            "/api/account/unlock/request",
+            json!({ "email": "kamal@ufora.test" }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // unknown addresses get the same answer
+    let response = app
+        .post(
+            "/api/account/unlock/request",
+            json!({ "email": "nobody@ufora.test" }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    assert_eq!(app.mail.count(), emails_sent);
+}
+
+#[tokio::test]
+async fn a_wrong_unlock_otp_is_rejected() {
+    let app = TestApp::spawn().await;
+    app.signed_in("sunil").await;
+
+    for _ in 0..5 {
+        login(&app, "sunil", "wrong-password").await;
+    }
+
+    app.post(
+        "/api/account/unlock/request",
+        json!({ "email": "sunil@ufora.test" }),
+        None,
+    )
+    .await;
+
+    let response = app
+        .post(
+            "/api/account/unlock",
+            json!({ "email": "sunil@ufora.test", "otp": "000000" }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = login(&app, "sunil", TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+}
@@ -0,0 +1,81 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TEST_PASSWORD};
+
+async fn login(app: &TestApp, username: &str) -> Value {
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": username, "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["Success"].clone()
+}
+
+// status of a request that needs a valid access token
+async fn create_post(app: &TestApp, token: &str) -> StatusCode {
+    app.post(
+        "/api/post/create",
+        json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+        Some(token),
+    )
+    .await
+    .status
+}
+
+#[tokio::test]
+async fn a_logged_out_token_is_rejected() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("nimal").await;
+
+    assert_eq!(create_post(&app, &token).await, StatusCode::OK);
+
+    let response = app.post("/api/logout", json!({}), Some(&token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.body["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn logging_out_everywhere_revokes_every_token_of_the_user() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+    let other_user = app.signed_in("kamal").await;
+
+    let phone = login(&app, "nimal").await;
+    let laptop = login(&app, "nimal").await;
+
+    let response = app
+        .post("/api/logout/all", json!({}), phone["token"].as_str())
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    for session in [&phone, &laptop] {
+        let token = session["token"].as_str().unwrap();
+        assert_eq!(create_post(&app, token).await, StatusCode::UNAUTHORIZED);
+
+        let response = app
+            .post(
+                "/api/token/refresh",
+                json!({ "refresh_token": session["refresh_token"] }),
+                None,
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    }
+
+    // other users are not affected
+    assert_eq!(create_post(&app, &other_user).await, StatusCode::OK);
+}
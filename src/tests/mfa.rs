@@ -0,0 +1,222 @@
+use axum::http::StatusCode;
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TEST_PASSWORD};
+use crate::services::totp;
+
+// two-factor authentication of a user as the tests need it
+struct EnabledMfa {
+    secret: String,
+    // code the enrollment was confirmed with
+    confirmed_with: String,
+    recovery_codes: Vec<String>,
+}
+
+async fn enable_mfa(app: &TestApp, token: &str) -> EnabledMfa {
+    let response = app.post("/api/mfa/enroll", json!({}), Some(token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let secret = response.body["secret"].as_str().unwrap().to_string();
+    assert!(response.body["otpauth_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("otpauth://totp/Ufora:"));
+
+    let code = totp::code_at(&secret, Utc::now().timestamp()).unwrap();
+    let response = app
+        .post("/api/mfa/confirm", json!({ "code": code }), Some(token))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let recovery_codes = response.body["recovery_codes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|code| code.as_str().unwrap().to_string())
+        .collect();
+
+    EnabledMfa {
+        secret,
+        confirmed_with: code,
+        recovery_codes,
+    }
+}
+
+// first step of the login, returns the mfa_pending token
+async fn login_with_password(app: &TestApp, username: &str) -> String {
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": username, "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body.get("Success").is_none(), "{}", response.body);
+
+    response.body["MfaRequired"]["mfa_token"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn login_with_code(app: &TestApp, mfa_token: &str, code: &str) -> (StatusCode, Value) {
+    let response = app
+        .post(
+            "/api/login/mfa",
+            json!({ "mfa_token": mfa_token, "code": code }),
+            None,
+        )
+        .await;
+
+    (response.status, response.body)
+}
+
+#[tokio::test]
+async fn a_login_needs_a_code_once_mfa_is_enabled() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("nimal").await;
+    let mfa = enable_mfa(&app, &token).await;
+
+    let mfa_token = login_with_password(&app, "nimal").await;
+
+    // the pending token is not an access token
+    let response = app.get("/api/sessions", Some(&mfa_token)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = login_with_code(&app, &mfa_token, "000000").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_CREDENTIALS");
+
+    // the code used to confirm the enrollment can not be used again, the next one can
+    let (status, _) = login_with_code(&app, &mfa_token, &mfa.confirmed_with).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let next = totp::code_at(&mfa.secret, Utc::now().timestamp() + 30).unwrap();
+    let (status, body) = login_with_code(&app, &mfa_token, &next).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let token = body["Success"]["token"].as_str().unwrap();
+    let response = app.get("/api/sessions", Some(token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn a_recovery_code_can_be_used_once() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("kamal").await;
+    let recovery_codes = enable_mfa(&app, &token).await.recovery_codes;
+    assert_eq!(recovery_codes.len(), 10);
+
+    let mfa_token = login_with_password(&app, "kamal").await;
+    let (status, body) = login_with_code(&app, &mfa_token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let mfa_token = login_with_password(&app, "kamal").await;
+    let (status, _) = login_with_code(&app, &mfa_token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = login_with_code(&app, &mfa_token, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn a_pending_token_is_exchanged_once() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("kamal").await;
+    let recovery_codes = enable_mfa(&app, &token).await.recovery_codes;
+
+    let mfa_token = login_with_password(&app, "kamal").await;
+    let (status, body) = login_with_code(&app, &mfa_token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // a valid code does not make a used pending token good again
+    let (status, body) = login_with_code(&app, &mfa_token, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "{}", body);
+
+    let mfa_token = login_with_password(&app, "kamal").await;
+    let (status, body) = login_with_code(&app, &mfa_token, &recovery_codes[1]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn a_disabled_mfa_is_no_longer_asked_for() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("sunil").await;
+    let recovery_codes = enable_mfa(&app, &token).await.recovery_codes;
+
+    let response = app
+        .post(
+            "/api/mfa/disable",
+            json!({ "code": "000000" }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post(
+            "/api/mfa/disable",
+            json!({ "code": recovery_codes[0] }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": "sunil", "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body["Success"]["token"].is_string());
+}
+
+#[tokio::test]
+async fn a_club_login_needs_a_code_from_an_official_with_mfa() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("amara").await;
+
+    let response = app
+        .post(
+            "/api/club/create",
+            json!({
+                "username": "chess",
+                "name": "Chess Club",
+                "club_type": "sports",
+                "email": "chess@ufora.test",
+                "club_verification_file": "letter.pdf",
+            }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let club_id: Option<String> = app
+        .db
+        .query("SELECT VALUE meta::id(id) FROM user WHERE username = 'chess'")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    let club_id = club_id.unwrap();
+
+    let recovery_codes = enable_mfa(&app, &token).await.recovery_codes;
+
+    let response = app
+        .post(
+            &format!("/api/club/login/{}", club_id),
+            json!({}),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert!(response.body.get("Success").is_none(), "{}", response.body);
+    let mfa_token = response.body["MfaRequired"]["mfa_token"].as_str().unwrap();
+
+    let (status, body) = login_with_code(&app, mfa_token, &recovery_codes[0]).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert!(body["Success"]["token"].is_string());
+}
@@ -0,0 +1,23 @@
+// end-to-end tests driving the http api against an embedded in-memory database
+mod action_tokens;
+mod account_lifecycle;
+mod admin;
+mod api_keys;
+mod authorization;
+mod config;
+mod data_export;
+mod email_change;
+mod harness;
+mod lockout;
+mod mfa;
+mod otp;
+mod password_change;
+mod password_policy;
+mod logout;
+mod query_builder;
+mod refresh_tokens;
+mod registration_flow;
+mod roles;
+mod sessions;
+mod signing_keys;
+mod transactions;
@@ -0,0 +1,202 @@
+use axum::http::{header, StatusCode};
+use futures::future::join_all;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TEST_PASSWORD};
+use crate::services::query_builder::Query;
+
+const EMAIL: &str = "nimal@ufora.test";
+
+async fn register(app: &TestApp) {
+    let response = app
+        .post(
+            "/api/register/undergraduate",
+            json!({
+                "name": "nimal",
+                "username": "nimal",
+                "email": EMAIL,
+                "password": TEST_PASSWORD,
+            }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+// asks for an email verification otp, returns the token sent back with it
+async fn request_otp(app: &TestApp) -> String {
+    let response = app
+        .post("/api/otp/request", json!({ "email": EMAIL }), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["token"].as_str().unwrap().to_string()
+}
+
+async fn verify_email(app: &TestApp, token: &str, otp: &str) -> StatusCode {
+    app.post(
+        "/api/otp/verify/email",
+        json!({ "token": token, "otp": otp }),
+        None,
+    )
+    .await
+    .status
+}
+
+fn wrong(otp: &str) -> &'static str {
+    if otp == "111111" {
+        "222222"
+    } else {
+        "111111"
+    }
+}
+
+#[tokio::test]
+async fn an_otp_is_dropped_after_too_many_wrong_codes() {
+    let app = TestApp::spawn().await;
+    register(&app).await;
+
+    let token = request_otp(&app).await;
+    let otp = app.last_otp_sent_to(EMAIL);
+
+    for _ in 0..5 {
+        assert_eq!(
+            verify_email(&app, &token, wrong(&otp)).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    // the right code comes too late
+    assert_eq!(
+        verify_email(&app, &token, &otp).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn concurrent_wrong_codes_do_not_get_past_the_limit() {
+    let app = TestApp::spawn().await;
+    register(&app).await;
+
+    let token = request_otp(&app).await;
+    let otp = app.last_otp_sent_to(EMAIL);
+
+    // more guesses at once than the 5 allowed
+    let guesses = (0..20).map(|_| verify_email(&app, &token, wrong(&otp)));
+    for status in join_all(guesses).await {
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // the guesses past the limit dropped the otp instead of being compared
+    let left: Vec<Value> = Query::raw("SELECT * FROM otp")
+        .execute(&app.db)
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(left.is_empty(), "{:?}", left);
+
+    assert_eq!(
+        verify_email(&app, &token, &otp).await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn a_new_otp_can_not_be_asked_for_right_away() {
+    let app = TestApp::spawn().await;
+    register(&app).await;
+
+    let token = request_otp(&app).await;
+    let otp = app.last_otp_sent_to(EMAIL);
+    let emails_sent = app.mail.count();
+
+    let response = app
+        .post("/api/otp/request", json!({ "email": EMAIL }), None)
+        .await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.body["code"], "TOO_MANY_REQUESTS");
+    let retry_after: i64 = response.headers[header::RETRY_AFTER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 60, "{}", retry_after);
+    assert_eq!(app.mail.count(), emails_sent);
+
+    // the otp sent first still works
+    assert_eq!(verify_email(&app, &token, &otp).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn concurrent_requests_send_one_otp() {
+    let app = TestApp::spawn().await;
+    register(&app).await;
+    let emails_sent = app.mail.count();
+
+    let requests = (0..5).map(|_| app.post("/api/otp/request", json!({ "email": EMAIL }), None));
+    let responses = join_all(requests).await;
+
+    // one gets the otp, the others are within its cooldown
+    let statuses: Vec<StatusCode> = responses.iter().map(|response| response.status).collect();
+    let sent: Vec<_> = responses
+        .iter()
+        .filter(|response| response.status == StatusCode::OK)
+        .collect();
+    assert_eq!(sent.len(), 1, "{:?}", statuses);
+    assert_eq!(
+        statuses
+            .iter()
+            .filter(|status| **status == StatusCode::TOO_MANY_REQUESTS)
+            .count(),
+        4,
+        "{:?}",
+        statuses
+    );
+    assert_eq!(app.mail.count(), emails_sent + 1);
+
+    // the code that was emailed is the one stored
+    let token = sent[0].body["token"].as_str().unwrap();
+    let otp = app.last_otp_sent_to(EMAIL);
+    assert_eq!(verify_email(&app, token, &otp).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn an_otp_is_only_accepted_for_its_purpose() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let response = app
+        .post(
+            "/api/password/reset/otp/request",
+            json!({ "email": EMAIL }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let reset_token = response.body["OTPSent"]["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let reset_otp = app.last_otp_sent_to(EMAIL);
+
+    // the password reset otp does not verify an email
+    let token = request_otp(&app).await;
+    let verification_otp = app.last_otp_sent_to(EMAIL);
+    if verification_otp != reset_otp {
+        assert_eq!(
+            verify_email(&app, &token, &reset_otp).await,
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    // and is still good for the password reset
+    let response = app
+        .post(
+            "/api/password/reset/otp/verify",
+            json!({ "token": reset_token, "otp": reset_otp }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
@@ -0,0 +1,97 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TestResponse, TEST_PASSWORD};
+
+const NEW_PASSWORD: &str = "Battery-Staple-7";
+
+async fn change_password(
+    app: &TestApp,
+    token: &str,
+    current_password: &str,
+    new_password: &str,
+) -> TestResponse {
+    app.post(
+        "/api/password/change",
+        json!({
+            "current_password": current_password,
+            "new_password": new_password,
+            "confirm_password": new_password,
+        }),
+        Some(token),
+    )
+    .await
+}
+
+fn login(password: &str) -> Value {
+    json!({ "username": "nimal", "password": password })
+}
+
+#[tokio::test]
+async fn a_password_change_logs_out_the_other_devices() {
+    let app = TestApp::spawn().await;
+    let phone = app.signed_in("nimal").await;
+    let response = app.post("/api/login", login(TEST_PASSWORD), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let laptop = response.body["Success"]["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let laptop_refresh_token = response.body["Success"]["refresh_token"].clone();
+
+    let response = change_password(&app, &phone, TEST_PASSWORD, NEW_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // the device that changed the password stays signed in
+    let response = app.get("/api/sessions", Some(&phone)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.body.as_array().unwrap().len(), 1);
+
+    let response = app.get("/api/sessions", Some(&laptop)).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app
+        .post(
+            "/api/token/refresh",
+            json!({ "refresh_token": laptop_refresh_token }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app.post("/api/login", login(TEST_PASSWORD), None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    let response = app.post("/api/login", login(NEW_PASSWORD), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let email = app.mail.last_email_to("nimal@ufora.test").unwrap();
+    assert_eq!(email.subject, "Your Ufora password was changed");
+}
+
+#[tokio::test]
+async fn the_current_password_is_required() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("nimal").await;
+
+    let response = change_password(&app, &token, "Wrong-Password-1", NEW_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.body["code"], "INVALID_CREDENTIALS");
+
+    let response = app.post("/api/login", login(NEW_PASSWORD), None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn a_new_password_must_meet_the_policy() {
+    let app = TestApp::spawn().await;
+    let token = app.signed_in("nimal").await;
+
+    let response = change_password(&app, &token, TEST_PASSWORD, "password").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.body["code"], "INVALID_PASSWORD");
+
+    let response = change_password(&app, &token, TEST_PASSWORD, TEST_PASSWORD).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app.post("/api/login", login(TEST_PASSWORD), None).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
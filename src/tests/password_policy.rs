@@ -0,0 +1,132 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::harness::{TestApp, TestResponse};
+
+const EMAIL: &str = "nimal@ufora.test";
+
+async fn register(app: &TestApp, password: &str) -> TestResponse {
+    app.post(
+        "/api/register/undergraduate",
+        json!({
+            "name": "nimal",
+            "username": "nimal",
+            "email": EMAIL,
+            "password": password,
+        }),
+        None,
+    )
+    .await
+}
+
+fn password_errors(response: &TestResponse) -> Vec<&str> {
+    response.body["details"]["password"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message.as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn every_broken_rule_is_reported() {
+    let app = TestApp::spawn().await;
+
+    let response = register(&app, "short").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.body["code"], "INVALID_PASSWORD");
+    assert_eq!(
+        password_errors(&response),
+        vec![
+            "Password must be at least 10 characters long",
+            "Password must contain an uppercase letter",
+            "Password must contain a digit",
+        ]
+    );
+
+    // nothing has been created
+    let response = register(&app, "Correct-Horse-42").await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn common_passwords_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    // also with digits and symbols added at the end
+    for password in ["Password1234", "Qwerty123456!"] {
+        let response = register(&app, password).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", password);
+        assert_eq!(
+            password_errors(&response),
+            vec!["Password is too common"],
+            "{}",
+            password
+        );
+    }
+}
+
+#[tokio::test]
+async fn passwords_containing_the_username_or_email_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    for password in ["Nimal-Rocks-42", "My-nimal@ufora.test-1"] {
+        let response = register(&app, password).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{}", password);
+        assert_eq!(
+            password_errors(&response),
+            vec!["Password must not contain your username or email"],
+            "{}",
+            password
+        );
+    }
+}
+
+#[tokio::test]
+async fn a_reset_password_must_meet_the_policy() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let response = app
+        .post(
+            "/api/password/reset/otp/request",
+            json!({ "email": EMAIL }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let token = response.body["OTPSent"]["token"].as_str().unwrap();
+
+    let response = app
+        .post(
+            "/api/password/reset/otp/verify",
+            json!({ "token": token, "otp": app.last_otp_sent_to(EMAIL) }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    let password_reset_token = response.body["OTPVerified"]["password_reset_token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    let reset = |password: &str| {
+        json!({
+            "password": password,
+            "confirm_password": password,
+            "password_reset_token": password_reset_token,
+        })
+    };
+
+    let response = app
+        .post("/api/password/reset", reset("Nimal-2024-pass"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.body["code"], "INVALID_PASSWORD");
+
+    // the token is not used up by a rejected password
+    let response = app
+        .post("/api/password/reset", reset("Battery-Staple-7"), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
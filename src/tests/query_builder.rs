@@ -0,0 +1,178 @@
+use surrealdb::sql::Value;
+
+use super::harness::TestApp;
+use crate::models::{
+    post::{AccessLevel, Post},
+    user::User,
+};
+use crate::services::query_builder::{field, graph, record, OrderBy, Query, Select};
+
+const QUOTED: &str = "it's a \"quoted\" caption'); DELETE post; --";
+
+#[test]
+fn values_are_bound_instead_of_written_into_the_statement() {
+    let query = Select::from("user")
+        .where_(field("username").eq(QUOTED))
+        .limit(1)
+        .build();
+
+    assert!(!query.statement().contains("quoted"));
+    assert!(query.statement().contains("username = $p0"));
+    assert_eq!(query.bindings()["p0"], Value::from(QUOTED));
+    assert_eq!(query.bindings()["p1"], Value::from(1));
+}
+
+#[test]
+fn appended_queries_keep_their_own_values() {
+    let mut first = Query::raw("SELECT * FROM ");
+    first.push_value(record("post", "a".to_string()));
+
+    let mut second = Query::raw("SELECT * FROM ");
+    second.push_value(record("post", "b".to_string()));
+    second.push(" WHERE caption = ").push_value("b");
+
+    first.append(second);
+
+    assert_eq!(
+        first.statement(),
+        "SELECT * FROM $p0; SELECT * FROM $p1 WHERE caption = $p2"
+    );
+    assert_eq!(
+        first.bindings()["p1"],
+        Value::from(record("post", "b".to_string()))
+    );
+    assert_eq!(first.bindings()["p2"], Value::from("b"));
+}
+
+#[tokio::test]
+async fn quotes_in_values_are_stored_verbatim() {
+    let app = TestApp::spawn().await;
+
+    let post = Post::new(
+        Some(QUOTED.to_string()),
+        Some(AccessLevel::Public),
+        Some("content".to_string()),
+    );
+    post.save(app.db.clone(), record("user", "someone".to_string()))
+        .await
+        .unwrap();
+
+    let captions: Vec<String> = app
+        .db
+        .query("SELECT VALUE caption FROM post")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    assert_eq!(captions, vec![QUOTED.to_string()]);
+}
+
+#[tokio::test]
+async fn quotes_in_lookups_do_not_change_the_condition() {
+    let app = TestApp::spawn().await;
+
+    app.db
+        .query("CREATE user SET username = 'nimal', email = 'nimal@ufora.test', user_type = 'undergraduate'")
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+    let user = User::get_user_by_email_or_username(
+        app.db.clone(),
+        None,
+        Some("x\" OR username != \"x".to_string()),
+    )
+    .await;
+
+    assert!(user.is_err());
+}
+
+#[test]
+fn nested_conditions_keep_their_grouping() {
+    let query = Select::from("post")
+        .where_(
+            field("access_level")
+                .eq("public")
+                .or(field("access_level").eq("friends"))
+                .and(!field("caption").contains("spam")),
+        )
+        .order_by(OrderBy::Descending(vec!["time".to_string()]))
+        .limit(10)
+        .start(20)
+        .fetch(&["reactions"])
+        .build();
+
+    assert_eq!(
+        query.statement(),
+        "SELECT * FROM post WHERE ((access_level = $p0 OR access_level = $p1) AND !(caption CONTAINS $p2)) \
+         ORDER BY time DESC LIMIT $p3 START $p4 FETCH reactions"
+    );
+}
+
+#[test]
+fn graph_traversals_are_rendered_as_fields() {
+    let user = record("user", "nimal".to_string());
+
+    let query = Select::from_record(user.clone())
+        .field_as(graph().out("create_post").to("post").all(), "posts")
+        .build();
+
+    assert_eq!(
+        query.statement(),
+        "SELECT ->create_post->post.* AS posts FROM $p0"
+    );
+    assert_eq!(query.bindings()["p0"], Value::from(user));
+
+    let query = Select::from("blog")
+        .value("id")
+        .where_(
+            graph()
+                .in_("create_blog")
+                .from("user")
+                .field()
+                .contains(record("user", "nimal".to_string())),
+        )
+        .split("tags")
+        .group_by(&["id"])
+        .build();
+
+    assert_eq!(
+        query.statement(),
+        "SELECT VALUE id FROM blog WHERE <-create_blog<-user CONTAINS $p0 SPLIT tags GROUP BY id"
+    );
+}
+
+#[tokio::test]
+async fn grouped_conditions_select_the_right_records() {
+    let app = TestApp::spawn().await;
+
+    app.db
+        .query(
+            "CREATE post SET caption = 'a', access_level = 'public', content = 'x', time = '1'; \
+             CREATE post SET caption = 'b', access_level = 'friends', content = 'x', time = '2'; \
+             CREATE post SET caption = 'c', access_level = 'only me', content = 'x', time = '3';",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+    // without the brackets this would also match post a
+    let captions: Vec<String> = Select::from("post")
+        .value("caption")
+        .where_(
+            field("access_level")
+                .eq("public")
+                .or(field("access_level").eq("friends"))
+                .and(field("time").inside(vec!["2".into(), "3".into()])),
+        )
+        .execute(&app.db)
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    assert_eq!(captions, vec!["b".to_string()]);
+}
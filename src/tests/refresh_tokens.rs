@@ -0,0 +1,100 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TEST_PASSWORD};
+
+// logs in a user created with signed_in and returns the body of the response
+async fn login(app: &TestApp, username: &str) -> Value {
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": username, "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["Success"].clone()
+}
+
+async fn refresh(app: &TestApp, refresh_token: &Value) -> (StatusCode, Value) {
+    let response = app
+        .post(
+            "/api/token/refresh",
+            json!({ "refresh_token": refresh_token }),
+            None,
+        )
+        .await;
+
+    (response.status, response.body)
+}
+
+#[tokio::test]
+async fn refresh_tokens_are_rotated_and_give_a_working_access_token() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let login = login(&app, "nimal").await;
+    let first = login["refresh_token"].clone();
+    assert!(first.is_string());
+
+    let (status, body) = refresh(&app, &first).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    let second = body["Success"]["refresh_token"].clone();
+    assert_ne!(second, first);
+
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({ "caption": "caption", "access_level": "public", "content": "content" }),
+            body["Success"]["token"].as_str(),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    // only hashes are stored, one for signing in, one for the login and one for the rotation
+    let hashes: Vec<String> = app
+        .db
+        .query("SELECT VALUE token_hash FROM refresh_token")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(hashes.len(), 3);
+    assert!(!hashes.iter().any(|hash| first == *hash || second == *hash));
+}
+
+#[tokio::test]
+async fn reusing_a_refresh_token_revokes_its_family() {
+    let app = TestApp::spawn().await;
+    app.signed_in("nimal").await;
+
+    let first = login(&app, "nimal").await["refresh_token"].clone();
+    let other_login = login(&app, "nimal").await["refresh_token"].clone();
+
+    let (status, body) = refresh(&app, &first).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let second = body["Success"]["refresh_token"].clone();
+
+    // the first token is presented again, e.g. after it was stolen
+    let (status, body) = refresh(&app, &first).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_TOKEN");
+
+    let (status, _) = refresh(&app, &second).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // other logins keep working
+    let (status, body) = refresh(&app, &other_login).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+#[tokio::test]
+async fn unknown_refresh_tokens_are_rejected() {
+    let app = TestApp::spawn().await;
+
+    let (status, body) = refresh(&app, &json!("not-a-token")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_TOKEN");
+}
@@ -0,0 +1,226 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::harness::TestApp;
+
+const EMAIL: &str = "nimal@ufora.test";
+const USERNAME: &str = "nimal";
+const PASSWORD: &str = "Correct-Horse-42";
+
+async fn register(app: &TestApp, username: &str, email: &str) -> StatusCode {
+    app.post(
+        "/api/register/undergraduate",
+        json!({
+            "name": "Nimal Perera",
+            "username": username,
+            "email": email,
+            "password": PASSWORD,
+        }),
+        None,
+    )
+    .await
+    .status
+}
+
+async fn verify_email(app: &TestApp, email: &str) {
+    let response = app
+        .post("/api/otp/request", json!({ "email": email }), None)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let token = response.body["token"].as_str().unwrap();
+
+    let otp = app.last_otp_sent_to(email);
+
+    let response = app
+        .post(
+            "/api/otp/verify/email",
+            json!({ "token": token, "otp": otp }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+async fn login(app: &TestApp, username: &str, password: &str) -> (StatusCode, Value) {
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": username, "password": password }),
+            None,
+        )
+        .await;
+
+    (response.status, response.body)
+}
+
+// registers, verifies and logs in a user, returning the jwt
+async fn signed_in_user(app: &TestApp) -> String {
+    assert_eq!(register(app, USERNAME, EMAIL).await, StatusCode::OK);
+    verify_email(app, EMAIL).await;
+
+    let (status, body) = login(app, USERNAME, PASSWORD).await;
+    assert_eq!(status, StatusCode::OK);
+
+    body["Success"]["token"].as_str().unwrap().to_string()
+}
+
+fn editor_content() -> Value {
+    json!({
+        "time": 1694000000000_i64,
+        "blocks": [
+            { "id": "b1", "type": "paragraph", "data": { "text": "hello from the tests" } }
+        ],
+        "version": "2.27.2",
+    })
+}
+
+#[tokio::test]
+async fn registered_user_can_verify_login_and_create_content() {
+    let app = TestApp::spawn().await;
+    let token = signed_in_user(&app).await;
+
+    let response = app
+        .post(
+            "/api/post/create",
+            json!({
+                "caption": "first post",
+                "access_level": "Public",
+                "content": "hello",
+            }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = app
+        .post(
+            "/api/blog/create",
+            json!({ "title": "First blog", "content": editor_content() }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    let response = app
+        .post(
+            "/api/project/create",
+            json!({ "title": "First project", "content": editor_content() }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+
+    // every piece of content is linked to its creator
+    let mut response = app
+        .db
+        .query(
+            "SELECT count(->create_post) AS posts, count(->create_blog) AS blogs, \
+             count(->create_project) AS projects FROM user WHERE username = $username",
+        )
+        .bind(("username", USERNAME))
+        .await
+        .unwrap();
+    let counts: Option<Value> = response.take(0).unwrap();
+    assert_eq!(
+        counts.unwrap(),
+        json!({ "posts": 1, "blogs": 1, "projects": 1 })
+    );
+}
+
+#[tokio::test]
+async fn verifying_the_email_sets_the_verification_flag() {
+    let app = TestApp::spawn().await;
+    register(&app, USERNAME, EMAIL).await;
+    verify_email(&app, EMAIL).await;
+
+    let mut response = app
+        .db
+        .query("SELECT VALUE email_verification_flag FROM user WHERE email = $email")
+        .bind(("email", EMAIL))
+        .await
+        .unwrap();
+    let flag: Option<bool> = response.take(0).unwrap();
+    assert_eq!(flag, Some(true));
+}
+
+#[tokio::test]
+async fn registration_rejects_taken_email_and_username() {
+    let app = TestApp::spawn().await;
+    assert_eq!(register(&app, USERNAME, EMAIL).await, StatusCode::OK);
+
+    assert_eq!(
+        register(&app, "someone_else", EMAIL).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        register(&app, USERNAME, "other@ufora.test").await,
+        StatusCode::BAD_REQUEST
+    );
+}
+
+#[tokio::test]
+async fn wrong_otp_is_rejected() {
+    let app = TestApp::spawn().await;
+    register(&app, USERNAME, EMAIL).await;
+
+    let response = app
+        .post("/api/otp/request", json!({ "email": EMAIL }), None)
+        .await;
+    let token = response.body["token"].as_str().unwrap();
+    let otp = app.last_otp_sent_to(EMAIL);
+    let wrong_otp = if otp == "111111" { "222222" } else { "111111" };
+
+    let response = app
+        .post(
+            "/api/otp/verify/email",
+            json!({ "token": token, "otp": wrong_otp }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn login_with_wrong_password_is_rejected() {
+    let app = TestApp::spawn().await;
+    register(&app, USERNAME, EMAIL).await;
+
+    let (status, body) = login(&app, USERNAME, "not-the-password").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_CREDENTIALS");
+}
+
+#[tokio::test]
+async fn creating_content_requires_a_token() {
+    let app = TestApp::spawn().await;
+
+    let response = app
+        .post(
+            "/api/blog/create",
+            json!({ "title": "First blog", "content": editor_content() }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.body["code"], "INVALID_TOKEN");
+}
+
+#[tokio::test]
+async fn invalid_content_is_reported_per_field() {
+    let app = TestApp::spawn().await;
+    let token = signed_in_user(&app).await;
+
+    let response = app
+        .post(
+            "/api/blog/create",
+            json!({ "title": "Hi", "content": editor_content() }),
+            Some(&token),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.body["code"], "VALIDATION_FAILED");
+    assert_eq!(
+        response.body["details"]["title"],
+        json!(["Title must be at least 5 characters long"])
+    );
+}
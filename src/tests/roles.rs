@@ -0,0 +1,55 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use super::harness::TestApp;
+
+fn club_request() -> serde_json::Value {
+    json!({
+        "username": "chess",
+        "name": "Chess Club",
+        "club_type": "sports",
+        "email": "chess@ufora.test",
+        "club_verification_file": "letter.pdf",
+    })
+}
+
+#[tokio::test]
+async fn only_undergraduates_can_create_clubs() {
+    let app = TestApp::spawn().await;
+    let company = app.signed_in_as("acme", "company").await;
+    let student = app.signed_in("nimal").await;
+
+    let response = app
+        .post("/api/club/create", club_request(), Some(&company))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "INSUFFICIENT_PERMISSIONS");
+
+    let response = app
+        .post("/api/club/create", club_request(), Some(&student))
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+}
+
+#[tokio::test]
+async fn clubs_and_unknown_user_types_can_not_register() {
+    let app = TestApp::spawn().await;
+
+    let body = json!({
+        "name": "Chess Club",
+        "username": "chess",
+        "email": "chess@ufora.test",
+        "password": "Correct-Horse-42",
+    });
+
+    let response = app.post("/api/register/club", body.clone(), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body["code"], "INSUFFICIENT_PERMISSIONS");
+
+    // administrators are promoted from the command line
+    let response = app.post("/api/register/admin", body.clone(), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app.post("/api/register/moderator", body, None).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
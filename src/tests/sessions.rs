@@ -0,0 +1,82 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use super::harness::{TestApp, TEST_PASSWORD, TEST_USER_AGENT};
+
+async fn login(app: &TestApp, username: &str) -> Value {
+    let response = app
+        .post(
+            "/api/login",
+            json!({ "username": username, "password": TEST_PASSWORD }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body["Success"].clone()
+}
+
+async fn sessions(app: &TestApp, token: &str) -> Vec<Value> {
+    let response = app.get("/api/sessions", Some(token)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    response.body.as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn every_login_is_listed_as_a_session() {
+    let app = TestApp::spawn().await;
+    let first = app.signed_in("nimal").await;
+    login(&app, "nimal").await;
+    app.signed_in("kamal").await;
+
+    let sessions = sessions(&app, &first).await;
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(
+        sessions
+            .iter()
+            .filter(|session| session["current"] == true)
+            .count(),
+        1
+    );
+    assert!(sessions
+        .iter()
+        .all(|session| session["user_agent"] == TEST_USER_AGENT));
+}
+
+#[tokio::test]
+async fn an_ended_session_can_not_be_used_anymore() {
+    let app = TestApp::spawn().await;
+    let phone = app.signed_in("nimal").await;
+    let laptop = login(&app, "nimal").await;
+
+    let laptop_session = sessions(&app, &phone)
+        .await
+        .into_iter()
+        .find(|session| session["current"] == false)
+        .unwrap();
+
+    let uri = format!("/api/sessions/{}", laptop_session["id"].as_str().unwrap());
+
+    // sessions of other users can not be ended
+    let other = app.signed_in("kamal").await;
+    let response = app.delete(&uri, Some(&other)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.delete(&uri, Some(&phone)).await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+
+    let response = app.get("/api/sessions", laptop["token"].as_str()).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .post(
+            "/api/token/refresh",
+            json!({ "refresh_token": laptop["refresh_token"] }),
+            None,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+
+    assert_eq!(sessions(&app, &phone).await.len(), 1);
+}
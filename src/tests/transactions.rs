@@ -0,0 +1,72 @@
+use super::harness::TestApp;
+use crate::models::post::{AccessLevel, Post};
+use crate::services::query_builder::{
+    get_create_query_for_an_object, record, transaction, DatabaseObject, Item, Return,
+};
+
+async fn count(app: &TestApp, table: &str) -> usize {
+    let ids: Vec<surrealdb::sql::Value> = app
+        .db
+        .query(format!("SELECT VALUE id FROM {}", table))
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+
+    ids.len()
+}
+
+fn text_only(text: &str) -> DatabaseObject {
+    DatabaseObject {
+        keys: vec!["text".to_string()],
+        values: vec![text.into()],
+    }
+}
+
+#[tokio::test]
+async fn content_is_not_created_when_it_cannot_be_linked() {
+    let app = TestApp::spawn().await;
+
+    let post = Post::new(
+        Some("caption".to_string()),
+        Some(AccessLevel::Public),
+        Some("content".to_string()),
+    );
+
+    // create_post edges must start at a user, so linking fails after the post was created
+    let result = post
+        .save(app.db.clone(), record("project", "not-a-user".to_string()))
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(count(&app, "post").await, 0);
+    assert_eq!(count(&app, "create_post").await, 0);
+}
+
+#[tokio::test]
+async fn the_statement_that_failed_is_reported() {
+    let app = TestApp::spawn().await;
+
+    let note_id = record("note", "twice".to_string());
+
+    let error = transaction(vec![
+        get_create_query_for_an_object(
+            Item::Table("draft".to_string()),
+            text_only("first"),
+            Return::NONE,
+        ),
+        get_create_query_for_an_object(
+            Item::Record(note_id.clone()),
+            text_only("second"),
+            Return::NONE,
+        ),
+        get_create_query_for_an_object(Item::Record(note_id), text_only("third"), Return::NONE),
+    ])
+    .execute_transaction(&app.db)
+    .await
+    .unwrap_err();
+
+    assert!(error.to_string().contains("already exists"));
+    assert_eq!(count(&app, "draft").await, 0);
+    assert_eq!(count(&app, "note").await, 0);
+}